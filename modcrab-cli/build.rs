@@ -0,0 +1,41 @@
+//! Captures build-time metadata for [`modcrab_version!`] so `--version` and `modcrab
+//! status` can report exactly which build produced a bug report, not just the Cargo
+//! version shared by every build of a given release.
+
+use std::process::Command;
+
+fn git_hash() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|hash| hash.trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+fn build_date() -> String {
+    Command::new("date")
+        .arg("+%Y-%m-%d")
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|date| date.trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+fn main() {
+    // Cargo already resolves the target triple for us; no need to shell out to
+    // `rustc --print cfg` just to re-derive what `TARGET` already holds.
+    let target = std::env::var("TARGET").unwrap_or_else(|_| "unknown".to_owned());
+
+    println!("cargo:rustc-env=MODCRAB_GIT_HASH={}", git_hash());
+    println!("cargo:rustc-env=MODCRAB_TARGET={target}");
+    println!("cargo:rustc-env=MODCRAB_BUILD_DATE={}", build_date());
+
+    // Re-run whenever HEAD moves to a different commit, so a local rebuild after a
+    // commit picks up the new hash instead of caching the previous build's.
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+}
@@ -0,0 +1,71 @@
+//! Config hot-reload for a live mount: `modcrab build`, then `kill -HUP $(cat
+//! .modcrab/mount.lock)` re-reads `.modcrab/data.bin` and applies whatever changed to
+//! the mounted overlay without unmounting.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use modcrab_core::modpack::{self, PackLayout};
+use modcrab_core::util::notice::{Notice, NoticePreset};
+use modcrab_fs::VirtualFileTree;
+
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Registers a SIGHUP handler and spawns a background thread that polls it: each time
+/// the signal fires, `.modcrab/data.bin` is re-read and diffed against the layer list
+/// from the last reload (or the mount itself, the first time). A mod whose real
+/// directory hasn't changed gets [`VirtualFileTree::update_directory`] so edits to its
+/// files are picked up; a newly-enabled mod gets [`VirtualFileTree::map_directory`] for
+/// the first time. `tree` should be a handle taken via `ModcrabFS::tree_handle` before
+/// the filesystem was moved into [`modcrab_fs::spawn_mount`] — the background thread
+/// holds only a weak reference to it, so it exits on its own once the mount ends and
+/// that's the only strong reference left standing.
+pub fn watch_for_sighup(layout: PackLayout, root_path: PathBuf, tree: Arc<RwLock<VirtualFileTree>>, mut previous_layers: Vec<PathBuf>) -> std::io::Result<()> {
+    let flag = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, flag.clone())?;
+
+    let weak_tree = Arc::downgrade(&tree);
+    drop(tree);
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let Some(tree) = weak_tree.upgrade() else { return };
+        if !flag.swap(false, Ordering::SeqCst) {
+            continue;
+        }
+
+        match reload(&layout, &root_path, &tree, &previous_layers) {
+            Ok(layers) => {
+                previous_layers = layers;
+                Notice::new(NoticePreset::Info, "SIGHUP received; overlay reloaded from data.bin").print();
+            }
+            Err(e) => Notice::new(NoticePreset::Error, format!("SIGHUP reload failed: {e}")).print(),
+        }
+    });
+
+    Ok(())
+}
+
+/// Re-reads `data.bin` and applies the new layer list to `tree`, returning it so the
+/// caller can remember it for the next reload.
+fn reload(layout: &PackLayout, root_path: &Path, tree: &RwLock<VirtualFileTree>, previous_layers: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    let data = modpack::load_modpack(layout).map_err(std::io::Error::other)?;
+    let layers = modpack::overlay_layers(layout, root_path, &data);
+    let hide = modpack::overlay_hide_patterns(&data);
+
+    let mut tree = tree.write().unwrap_or_else(|e| e.into_inner());
+    for (idx, layer) in layers.iter().enumerate() {
+        let patterns = hide.get(idx).map(Vec::as_slice).unwrap_or(&[]);
+        let skip = VirtualFileTree::hide_predicate(patterns);
+        if previous_layers.get(idx) == Some(layer) {
+            tree.update_directory_filtered(layer, Path::new("/"), idx, &skip)?;
+        } else {
+            tree.map_directory_filtered(layer, Path::new("/"), idx, &skip, &mut |_| {})?;
+        }
+    }
+
+    Ok(layers)
+}
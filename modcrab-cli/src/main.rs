@@ -0,0 +1,1199 @@
+mod hotreload;
+mod mount;
+
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use clap::{CommandFactory, Parser, Subcommand};
+
+/// The Cargo version plus the Git hash, target triple, and build date `build.rs`
+/// captured at compile time, e.g. `"0.1.0 (abc1234, x86_64-unknown-linux-gnu,
+/// 2024-01-01)"`. Fed to clap's `version` (which prepends the binary name itself) and
+/// surfaced again by `modcrab status`, since a bug report without the exact build it
+/// came from is hard to act on.
+macro_rules! modcrab_version {
+    () => {
+        concat!(
+            env!("CARGO_PKG_VERSION"),
+            " (",
+            env!("MODCRAB_GIT_HASH"),
+            ", ",
+            env!("MODCRAB_TARGET"),
+            ", ",
+            env!("MODCRAB_BUILD_DATE"),
+            ")"
+        )
+    };
+}
+
+use modcrab_core::{downloads, global_config, import, lua, mod_info, mod_rename, modpack, nexus, onboarding, overrides, structs, template, util};
+use global_config::GlobalConfigPath;
+use nix::sys::signal::Signal;
+use structs::error::AppResult;
+use util::notice::{Notice, NoticePreset};
+
+#[derive(Parser)]
+#[command(name = "modcrab", version = modcrab_version!(), about = "A FUSE-overlay mod manager for Linux")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Emits a failing command's notice as a JSON line on stdout instead of a
+    /// formatted message on stderr, for GUI frontends consuming modcrab as a backend.
+    #[arg(long, global = true)]
+    json: bool,
+    /// Overrides the global config location (default `~/.config/modcrab/config.toml`),
+    /// for running multiple isolated modcrab instances on the same machine (separate
+    /// users, Docker-based CI).
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Controls ANSI color in notice output. `auto` (the default) colors only when
+    /// stdout is a terminal and the NO_COLOR / CLICOLOR_FORCE environment variables
+    /// (see https://no-color.org) don't say otherwise.
+    #[arg(long, global = true, value_enum, default_value_t = ColorMode::Auto)]
+    color: ColorMode,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ColorMode {
+    Always,
+    Never,
+    Auto,
+}
+
+impl ColorMode {
+    /// Resolves to whether ANSI should actually be emitted. `Always`/`Never` are
+    /// literal; `Auto` follows the NO_COLOR spec: `CLICOLOR_FORCE` forces color on even
+    /// off a terminal when set to anything non-empty, `NO_COLOR` forces it off when set
+    /// to anything at all (checked first since the spec gives it the final say), and
+    /// otherwise it's on only when stdout is a real terminal.
+    fn resolve(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                if std::env::var_os("NO_COLOR").is_some() {
+                    false
+                } else if std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty()) {
+                    true
+                } else {
+                    std::io::stdout().is_terminal()
+                }
+            }
+        }
+    }
+}
+
+/// Prints `modcrab-fs`'s `log` records straight to stderr, one line per record. The
+/// whole point of `--verbose` is "tell me what the overlay is doing under the hood",
+/// so there's no filtering or formatting beyond the level tag; `init_logger` controls
+/// what actually gets through via the max level.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        eprintln!("[{}] {}", record.level(), record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+static STDERR_LOGGER: StderrLogger = StderrLogger;
+
+/// Installs [`StderrLogger`] and sets the max level modcrab-fs's and modcrab-core's
+/// `log::trace!`/`debug!`/`warn!` calls are filtered against. `MODCRAB_LOG` (one of
+/// `error`, `warn`, `info`, `debug`, `trace`, case-insensitive) takes priority when set,
+/// so `MODCRAB_LOG=trace` gets every VFT lookup, FUSE call, and Lua evaluation step
+/// regardless of `--verbose`. Without it, `--verbose` forces `Info`, and a plain
+/// `modcrab mount` falls back to `Warn` so it still surfaces the odd lock-poison or
+/// EXDEV-fallback warning without drowning in per-call noise.
+fn init_logger(verbose: bool) {
+    log::set_logger(&STDERR_LOGGER).expect("logger installed exactly once, at the top of main");
+    log::set_max_level(resolved_log_level(verbose, std::env::var("MODCRAB_LOG").ok().as_deref()));
+}
+
+/// Picks the max log level `init_logger` installs: an explicit `MODCRAB_LOG` value wins
+/// outright, otherwise it's `Info` under `--verbose` and `Warn` by default. Split out
+/// from `init_logger` so the precedence can be tested without touching the
+/// process-global logger.
+fn resolved_log_level(verbose: bool, env_value: Option<&str>) -> log::LevelFilter {
+    let default_level = if verbose { log::LevelFilter::Info } else { log::LevelFilter::Warn };
+    env_value.and_then(|v| v.parse::<log::LevelFilter>().ok()).unwrap_or(default_level)
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates a new modpack directory layout.
+    Init {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Pre-fills the config from a built-in template for this game.
+        #[arg(long)]
+        game: Option<String>,
+        /// Lists the available `--game` templates and exits.
+        #[arg(long)]
+        list_templates: bool,
+        /// Creates empty directories with no pre-filled config, even if `--game` is
+        /// omitted by default anyway. Conflicts with `--game`.
+        #[arg(long)]
+        bare: bool,
+    },
+    /// Evaluates the config, sorts the mod list, and writes `.modcrab/data.bin`.
+    #[command(visible_alias = "b")]
+    Build {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Validates and sorts without writing any output files.
+        #[arg(long)]
+        check: bool,
+        /// Deletes `data.bin`, `modlist.txt`, and `plugins.txt` before rebuilding, so a
+        /// pack confused by stale derived state starts over from the config instead of
+        /// merging into whatever's left on disk.
+        #[arg(long)]
+        clean: bool,
+        /// Prints the SHA-256 `build_hash` (already stored in every `data.bin` as
+        /// `AppData::build_hash`) in the completion notice, so two builds of the same
+        /// config can be diffed for equality without comparing the whole file.
+        #[arg(long)]
+        reproducible: bool,
+        /// Evaluates a single Lua config file (or, given `-`, stdin) against `path` as
+        /// the mods directory, instead of scanning `path/config/` for `*.lua`/`*.toml`
+        /// files. For testing a config snippet or generating a pack programmatically
+        /// without scaffolding a full modpack directory. Implies `--check`: with no
+        /// `.modcrab/` state directory to resolve, there's nowhere to save `data.bin`
+        /// into.
+        #[arg(long, value_name = "PATH|-", conflicts_with_all = ["clean", "reproducible"])]
+        config_file: Option<String>,
+        /// Writes a JSON manifest (virtual path -> winning mod) of this build to
+        /// `PATH`, for later comparison with `modcrab diff --manifest`.
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+    },
+    /// Compares two manifests written by `modcrab build --manifest`, reporting every
+    /// virtual path that appeared, disappeared, or is now won by a different mod.
+    Diff {
+        /// The two manifest files to compare, oldest first.
+        #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+        manifest: Vec<PathBuf>,
+    },
+    /// Mounts the built modpack over the game root.
+    #[command(visible_alias = "m")]
+    Mount {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Skips the session report printed after unmounting.
+        #[arg(long)]
+        no_report: bool,
+        /// FUSE worker thread count. Defaults to the number of CPUs, capped at 4.
+        #[arg(long)]
+        num_threads: Option<usize>,
+        /// Skips the confirmation prompt when mounting with zero enabled mods.
+        #[arg(long)]
+        allow_empty: bool,
+        /// Shows a progress bar naming the layer currently being scanned, instead of a
+        /// bare file count, while building the overlay.
+        #[arg(long)]
+        verbose: bool,
+        /// Clears these bits from the mode of every file/directory created through the
+        /// overlay, on top of whatever mode the caller already asked for. Octal, e.g.
+        /// `022`; an optional leading `0o` is accepted too.
+        #[arg(long, value_parser = parse_umask)]
+        umask: Option<u32>,
+    },
+    /// Prints the current state of a modpack.
+    Status {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Asks a modpack's live `mount`/`shell`/`run` session, possibly running in another
+    /// terminal, to unmount. Each mount already runs as its own process holding its own
+    /// `.modcrab/mount.lock`, so several packs can already be mounted side by side with
+    /// no coordination needed between them; this just lets you reach one of those
+    /// sessions by its pack path instead of having to go find its terminal.
+    Unmount {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Imports a Mod Organizer 2 modlist.txt into config/imported.lua.
+    ImportMo2 {
+        modlist: PathBuf,
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Mounts the modpack and spawns $SHELL inside the overlay for poking around.
+    Shell {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// FUSE worker thread count. Defaults to the number of CPUs, capped at 4.
+        #[arg(long)]
+        num_threads: Option<usize>,
+        /// Skips the confirmation prompt when mounting with zero enabled mods.
+        #[arg(long)]
+        allow_empty: bool,
+        /// Shows a progress bar naming the layer currently being scanned, instead of a
+        /// bare file count, while building the overlay.
+        #[arg(long)]
+        verbose: bool,
+        /// Clears these bits from the mode of every file/directory created through the
+        /// overlay, on top of whatever mode the caller already asked for. Octal, e.g.
+        /// `022`; an optional leading `0o` is accepted too.
+        #[arg(long, value_parser = parse_umask)]
+        umask: Option<u32>,
+    },
+    /// Mounts the modpack and runs an arbitrary command inside the overlay (a sorter
+    /// like LOOT, an archive tool, anything that needs to see the merged data
+    /// directory), unmounting once it exits.
+    #[command(visible_alias = "r")]
+    Run {
+        /// The command to run, plus its own arguments, e.g. `modcrab run loot` or
+        /// `modcrab run -- loot --some-flag`. `allow_hyphen_values` means a flag-looking
+        /// first token (`loot -y`) is captured into `command` instead of clap trying (and
+        /// failing) to parse it as one of modcrab's own flags, so the `--` separator is
+        /// only needed when `command`'s first token collides with a real modcrab flag
+        /// name like `--path`.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+        /// FUSE worker thread count. Defaults to the number of CPUs, capped at 4.
+        #[arg(long)]
+        num_threads: Option<usize>,
+        /// Skips the confirmation prompt when mounting with zero enabled mods.
+        #[arg(long)]
+        allow_empty: bool,
+        /// After the command exits, before unmounting, copies the game's `plugins.txt`
+        /// back into `.modcrab/plugins.txt` — for tools like LOOT that reorder it.
+        #[arg(long)]
+        resync_plugins: bool,
+        /// Shows a progress bar naming the layer currently being scanned, instead of a
+        /// bare file count, while building the overlay.
+        #[arg(long)]
+        verbose: bool,
+        /// Clears these bits from the mode of every file/directory created through the
+        /// overlay, on top of whatever mode the caller already asked for. Octal, e.g.
+        /// `022`; an optional leading `0o` is accepted too.
+        #[arg(long, value_parser = parse_umask)]
+        umask: Option<u32>,
+    },
+    /// Per-mod inspection commands.
+    Mod {
+        #[command(subcommand)]
+        command: ModCommand,
+    },
+    /// Enables every mod matching a selector.
+    Enable {
+        #[command(flatten)]
+        selector: NameSelectorArgs,
+    },
+    /// Disables every mod matching a selector.
+    Disable {
+        #[command(flatten)]
+        selector: NameSelectorArgs,
+    },
+    /// Sets the priority of every mod matching a selector.
+    SetPriority {
+        #[command(flatten)]
+        selector: FlagSelectorArgs,
+        priority: i32,
+    },
+    /// Moves an archive into the content-addressed `downloads/` cache, deduping it
+    /// against anything already cached under the same hash.
+    CacheAdd {
+        archive: PathBuf,
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Reads JSON-line notices (as emitted by `--json`) from stdin and pretty-prints
+    /// them, for replaying a GUI's captured log back through the CLI's own formatter.
+    ReplayNotices,
+    /// Looks up a mod's details before adding it to a config.
+    Info {
+        /// A Nexus mod id, `<mod id>:<file id>`, or a mod page URL — the same format
+        /// accepted by a `ModSpec`'s `id` field.
+        #[arg(long)]
+        nexus: String,
+    },
+    /// Inspects `config/` itself rather than the modpack it evaluates to.
+    Config {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Shows every `config/*.lua` and `*.toml` file in the order it's loaded, for
+        /// debugging load order in a modpack with many config files.
+        #[arg(long, conflicts_with = "paths")]
+        list: bool,
+        /// Prints where modcrab's global (non-pack) state lives on this machine: the
+        /// XDG config/cache/state directories and the resolved global config file path.
+        #[arg(long)]
+        paths: bool,
+    },
+    /// Prints the pack-level metadata declared via `modcrab.meta` in a built modpack.
+    Meta {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Prints the raw `ModMeta` as JSON instead of a formatted notice.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Validates a config's shape — profile/override resolution, duplicate and
+    /// root/non-root conflicts, dependency/after references, and load-order cycles —
+    /// without requiring any of its mods to be installed. For sanity-checking a config
+    /// before acquiring mods; `build` and `doctor` both additionally require the mods
+    /// to already be present on disk.
+    Check {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Checks a built modpack's mapped files against what's actually on disk, without
+    /// mounting: missing files, unreadable files, and files that changed kind since the
+    /// pack was last built.
+    Doctor {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Benchmarking commands.
+    Bench {
+        #[command(subcommand)]
+        command: BenchCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum BenchCommand {
+    /// Times the tree construction a mount would do — mapping every layer's real
+    /// files into a `VirtualFileTree` — without actually mounting via FUSE. The
+    /// mapping is run `--runs` times back to back and reported as one Statistics
+    /// notice (min/avg/max wall time plus the resulting tree's node/edge counts and an
+    /// approximate byte size). An eager, layer-by-layer scan is the only mount
+    /// strategy modcrab has today; this command's JSON output is meant to make it easy
+    /// to track that strategy's cost over time, or compare it against a lazier one if
+    /// this ever grows a second.
+    Mount {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// How many times to repeat the scan.
+        #[arg(long, default_value_t = 3)]
+        runs: usize,
+        /// Also writes the results to `PATH` as JSON, for tracking over time.
+        #[arg(long, value_name = "PATH")]
+        json_out: Option<PathBuf>,
+    },
+}
+
+/// A selector given as a bare name/glob positional, or via `--group`/`--all-disabled`.
+/// Used by `enable`/`disable`, which have no other positional argument to conflict with.
+#[derive(clap::Args)]
+struct NameSelectorArgs {
+    /// An exact mod name, or a glob pattern like 'SMIM*'.
+    selector: Option<String>,
+    /// Every mod declaring this group name.
+    #[arg(long)]
+    group: Option<String>,
+    /// Every mod that's currently disabled.
+    #[arg(long)]
+    all_disabled: bool,
+    #[arg(default_value = ".")]
+    path: PathBuf,
+    /// Skips the confirmation prompt for large selections.
+    #[arg(long)]
+    yes: bool,
+}
+
+/// Like [`NameSelectorArgs`], but the name/glob selector is a `--name` flag instead of
+/// a bare positional, since `set-priority` already uses the trailing positional for
+/// the priority value.
+#[derive(clap::Args)]
+struct FlagSelectorArgs {
+    /// An exact mod name, or a glob pattern like 'SMIM*'.
+    #[arg(long)]
+    name: Option<String>,
+    /// Every mod declaring this group name.
+    #[arg(long)]
+    group: Option<String>,
+    /// Every mod that's currently disabled.
+    #[arg(long)]
+    all_disabled: bool,
+    #[arg(default_value = ".")]
+    path: PathBuf,
+    /// Skips the confirmation prompt for large selections.
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Subcommand)]
+enum ModCommand {
+    /// Shows everything modcrab knows about one mod: its spec fields, installed
+    /// state, conflict standing, and Nexus link.
+    Info {
+        name: String,
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Emits machine-readable JSON instead of a Notice.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Renames a mod's folder and every reference to it modcrab can safely rewrite
+    /// itself, reporting any `config/*.lua` lines that still need manual editing.
+    Rename {
+        old: String,
+        new: String,
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+fn main() {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            if let Some(suggestion) = suggest_subcommand(&e) {
+                Notice::new(NoticePreset::Error, format!("unrecognized command; did you mean '{suggestion}'?")).print();
+                std::process::exit(2);
+            }
+            e.exit();
+        }
+    };
+    util::text::set_color_enabled(cli.color.resolve());
+    init_logger(mount_verbose(&cli.command));
+    let global_config_path = cli.config.map(GlobalConfigPath).unwrap_or_default();
+    if let Err(e) = run(cli.command, &global_config_path) {
+        if cli.json {
+            if let structs::error::AppError::Custom(notice) = &e {
+                notice.print_json();
+                std::process::exit(1);
+            }
+        }
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+/// Clap's default "unrecognized subcommand" error is a terse usage dump. When the
+/// parse failure is specifically an unknown subcommand, this instead looks up the
+/// closest known subcommand (or alias) by edit distance — the same
+/// [`util::misc::closest_match`] helper `mod_info`'s mod-name "did you mean"
+/// suggestions use — and returns it for the caller to report in modcrab's own
+/// `Notice` style. Returns `None` for any other kind of parse error (missing arg, bad
+/// value, `--help`, ...), which falls through to clap's own formatting.
+fn suggest_subcommand(e: &clap::Error) -> Option<String> {
+    if e.kind() != clap::error::ErrorKind::InvalidSubcommand {
+        return None;
+    }
+    let bad = e.context().find_map(|(kind, value)| (kind == clap::error::ContextKind::InvalidSubcommand).then(|| value.to_string()))?;
+
+    let command = Cli::command();
+    let names: Vec<String> =
+        command.get_subcommands().flat_map(|s| std::iter::once(s.get_name().to_owned()).chain(s.get_visible_aliases().map(str::to_owned))).collect();
+
+    util::misc::closest_match(&bad, names.iter().map(String::as_str)).map(str::to_owned)
+}
+
+/// The `--verbose` flag only exists on the mount-family subcommands (`mount`, `shell`,
+/// `run`), where it also drives the mapping progress bar in [`mount::mount_modpack`].
+/// Every other subcommand runs without touching `modcrab-fs`, so there's nothing for a
+/// higher log level to surface.
+fn mount_verbose(command: &Command) -> bool {
+    match command {
+        Command::Mount { verbose, .. } | Command::Shell { verbose, .. } | Command::Run { verbose, .. } => *verbose,
+        _ => false,
+    }
+}
+
+/// Parses a `chmod`-style octal umask for `--umask`, e.g. `022`; an optional leading
+/// `0o` is accepted too, since that's how Rust itself would spell it.
+fn parse_umask(raw: &str) -> Result<u32, String> {
+    u32::from_str_radix(raw.trim_start_matches("0o"), 8).map_err(|_| format!("'{raw}' is not a valid octal umask (e.g. 022)"))
+}
+
+fn run(command: Command, global_config_path: &GlobalConfigPath) -> AppResult<()> {
+    match command {
+        Command::Init { path, game, list_templates, bare } => {
+            if list_templates {
+                for t in template::TEMPLATES {
+                    println!("{} ({})", t.key, t.display_name);
+                }
+                return Ok(());
+            }
+
+            if bare && game.is_some() {
+                return Err(Notice::new(NoticePreset::Error, "specify either --game or --bare, not both").into());
+            }
+
+            let config_dir = path.join(modpack::CONFIG_DIR);
+            if config_dir.is_dir() && std::fs::read_dir(&config_dir).map(|mut d| d.next().is_some()).unwrap_or(false) {
+                return Err(Notice::new(NoticePreset::Error, "already a modpack; refusing to mix a template into an existing config/").into());
+            }
+
+            modpack::init_modpack(&path)?;
+            let layout = modpack::PackLayout::resolve(&path)?;
+
+            if let Some(game) = &game {
+                let tpl = template::find(game).ok_or_else(|| Notice::new(NoticePreset::Error, format!("no template for '{game}'; see --list-templates")))?;
+
+                std::fs::write(layout.config.join("template.lua"), template::render_lua(tpl))?;
+
+                let profiles_dir = path.join("profiles");
+                std::fs::create_dir_all(&profiles_dir)?;
+                std::fs::write(profiles_dir.join("README"), template::PROFILES_STUB)?;
+
+                let gitignore_path = path.join(".gitignore");
+                let mut gitignore = std::fs::read_to_string(&gitignore_path).unwrap_or_default();
+                if !gitignore.is_empty() && !gitignore.ends_with('\n') {
+                    gitignore.push('\n');
+                }
+                gitignore.push_str(template::GITIGNORE_ADDITIONS);
+                std::fs::write(&gitignore_path, gitignore)?;
+
+                Notice::new(NoticePreset::Info, format!("initialized modpack at {} from the '{}' template", path.display(), tpl.display_name)).print();
+            } else {
+                Notice::new(NoticePreset::Info, format!("initialized modpack at {}", path.display())).print();
+            }
+            Ok(())
+        }
+        Command::Build { path, check, clean, reproducible, config_file, manifest } => {
+            if let Some(config_file) = config_file {
+                let source = if config_file == "-" { lua::ConfigSource::Stdin } else { lua::ConfigSource::File(PathBuf::from(config_file)) };
+                let data = modpack::build_from_source(source, &path)?;
+                for notice in &data.notices {
+                    notice.print();
+                }
+                if let Some(manifest_path) = &manifest {
+                    let manifest = modpack::build_manifest(&path, &data);
+                    let text = serde_json::to_string_pretty(&manifest).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to serialize manifest: {e}")))?;
+                    std::fs::write(manifest_path, text)?;
+                }
+                Notice::new(NoticePreset::Info, "check complete")
+                    .field("root mods", data.root_mods.len().to_string())
+                    .field("mods", data.mods.len().to_string())
+                    .print();
+                return Ok(());
+            }
+
+            let layout = modpack::PackLayout::resolve(&path)?;
+            modpack::validate_modpack(&layout)?;
+            let previous = modpack::load_modpack(&layout).ok();
+
+            if clean {
+                modpack::clear_derived_state(&layout)?;
+                Notice::new(NoticePreset::Info, "cleared data.bin, modlist.txt, and plugins.txt; rebuilding from the config").print();
+            }
+
+            if lua::eval_config(&layout.config, global_config_path)?.target.is_none() && std::io::stdin().is_terminal() {
+                let mut prompt = util::prompt::Prompt::new(std::io::stdin().lock(), std::io::stdout());
+                onboarding::run(&layout, &mut prompt)?;
+            }
+
+            let data = modpack::build_modpack(&layout, global_config_path)?;
+            for notice in &data.notices {
+                notice.print();
+            }
+            if let Some(previous) = &previous {
+                for notice in modpack::detect_ownership_changes(&layout.mods, previous, &data) {
+                    notice.print();
+                }
+            }
+            if !check {
+                modpack::save_modpack(&layout, &data)?;
+                modpack::write_modlist(&layout, &data)?;
+                let config = lua::eval_config(&layout.config, global_config_path)?;
+                if let Some(target) = &config.target {
+                    modpack::write_plugins_list(&layout, &target.spec, &data)?;
+                }
+            }
+            if let Some(manifest_path) = &manifest {
+                let manifest = modpack::build_manifest(&layout.mods, &data);
+                let text = serde_json::to_string_pretty(&manifest).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to serialize manifest: {e}")))?;
+                std::fs::write(manifest_path, text)?;
+            }
+            let mut notice = Notice::new(NoticePreset::Info, if check { "check complete" } else { "build complete" })
+                .field("root mods", data.root_mods.len().to_string())
+                .field("mods", data.mods.len().to_string());
+            if reproducible {
+                if let Some(hash) = &data.build_hash {
+                    notice = notice.field("build hash", hash.clone());
+                }
+            }
+            notice.print();
+            Ok(())
+        }
+        Command::Diff { manifest } => {
+            let [old_path, new_path] = manifest.as_slice() else {
+                return Err(Notice::new(NoticePreset::Error, "--manifest takes exactly two paths, oldest first").into());
+            };
+            let old: modpack::Manifest = serde_json::from_str(&std::fs::read_to_string(old_path)?)?;
+            let new: modpack::Manifest = serde_json::from_str(&std::fs::read_to_string(new_path)?)?;
+
+            let notices = modpack::diff_manifests(&old, &new);
+            for notice in &notices {
+                notice.print();
+            }
+            Notice::new(NoticePreset::Info, "manifest diff complete").field("changed paths", notices.len().to_string()).print();
+            Ok(())
+        }
+        Command::Mount { path, no_report, num_threads, allow_empty, verbose, umask } => {
+            let Some(mounted) = begin_mount_session(&path, num_threads, allow_empty, verbose, umask, global_config_path)? else { return Ok(()) };
+
+            if std::io::stdin().is_terminal() {
+                Notice::new(NoticePreset::Info, "mounted; press Enter, or run `modcrab unmount` elsewhere, to unmount").print();
+                util::misc::wait_for_enter_key(&[Signal::SIGTERM, Signal::SIGINT])?;
+            } else {
+                Notice::new(NoticePreset::Info, "mounted; run `modcrab unmount` elsewhere to unmount").print();
+                util::misc::wait_for_signal(&[Signal::SIGTERM, Signal::SIGINT])?;
+            }
+
+            end_mount_session("mount", no_report, mounted)
+        }
+        Command::Status { path } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            modpack::validate_modpack(&layout)?;
+            let data = modpack::load_modpack(&layout)?;
+            let mut notice = Notice::new(NoticePreset::Info, "modpack status")
+                .field("modcrab version", modcrab_version!())
+                .field("root mods", data.root_mods.len().to_string())
+                .field("mods", data.mods.len().to_string());
+            notice = match modpack::MountLock::mounted_pid(&layout) {
+                Some(pid) => notice.field("mounted", format!("yes (pid {pid}; `modcrab unmount` to stop it)")),
+                None => notice.field("mounted", "no"),
+            };
+            if let Some(name) = &data.meta.name {
+                notice = notice.field("pack name", name.clone());
+            }
+            if let Some(version) = &data.meta.version {
+                notice = notice.field("pack version", version.clone());
+            }
+
+            let mut root_for_stats = None;
+            if let Ok(config) = lua::eval_config(&layout.config, global_config_path) {
+                if let Some(target) = &config.target {
+                    if let Ok(root) = target.resolve_root() {
+                        notice = notice.field("root path", root.display().to_string());
+                        root_for_stats = Some(root);
+                    }
+                    if target.root_path.is_none() {
+                        if let Ok((_, store)) = target.spec.scan_for_root_with_store() {
+                            notice = notice.field("store", store);
+                        }
+                    }
+                    if let Ok(data_path) = target.resolve_data() {
+                        notice = notice.field("data path", data_path.display().to_string());
+                    }
+                    if let Ok(mods_path) = target.resolve_mods() {
+                        notice = notice.field("staged mods", mods_path.display().to_string());
+                    }
+                }
+                notice = notice.field("nexus key", if config.nexus_key.is_some() { "configured" } else { "not set" });
+                if !config.shadow_passthrough.is_empty() {
+                    notice = notice.field("shadow passthrough", config.shadow_passthrough.join(", "));
+                }
+            }
+
+            notice.print();
+            if let Some(stale) = modpack::staleness_notice(&layout, &data, global_config_path) {
+                stale.print();
+            }
+
+            // A plain `VirtualFileTree` mapping, not a mount: `status` has no writable
+            // surface or live FUSE session to inspect, just the same root + mod layers
+            // `overlay_layers` would hand a real mount, scanned once to count who
+            // contributes what.
+            if let Some(root) = root_for_stats {
+                let layers = modpack::overlay_layers(&layout, &root, &data);
+                let hide = modpack::overlay_hide_patterns(&data);
+                let tree = modcrab_fs::VirtualFileTree::from_layers(&layers, &hide, &mut |_| {}).unwrap_or_default();
+
+                let stats = data.overlay_stats(&tree.node_count_by_layer());
+                if !stats.is_empty() {
+                    let mut breakdown = Notice::new(NoticePreset::Statistics, "file contribution");
+                    for (name, count) in stats {
+                        breakdown = breakdown.field(name, format!("{count} files"));
+                    }
+                    breakdown.print();
+                }
+            }
+            Ok(())
+        }
+        Command::Unmount { path } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let Some(pid) = modpack::MountLock::mounted_pid(&layout) else {
+                return Err(Notice::new(NoticePreset::Error, "this modpack is not currently mounted").into());
+            };
+
+            nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), nix::sys::signal::Signal::SIGTERM)
+                .map_err(|e| Notice::new(NoticePreset::Error, format!("failed to signal process {pid}: {e}")))?;
+            Notice::new(NoticePreset::Info, "sent unmount request; the session will finish its report and exit").field("pid", pid.to_string()).print();
+            Ok(())
+        }
+        Command::ImportMo2 { modlist, path } => {
+            let out_path = import::import_mo2(&modlist, &path)?;
+            Notice::new(NoticePreset::Info, "imported MO2 modlist").field("wrote", out_path.display().to_string()).print();
+            Ok(())
+        }
+        Command::Shell { path, num_threads, allow_empty, verbose, umask } => {
+            let Some(mounted) = begin_mount_session(&path, num_threads, allow_empty, verbose, umask, global_config_path)? else { return Ok(()) };
+
+            let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_owned());
+            Notice::new(NoticePreset::Info, "mounted; spawning shell in the overlay").field("shell", shell.clone()).print();
+            std::process::Command::new(&shell).current_dir(&mounted.root_path).status()?;
+
+            end_mount_session("shell", false, mounted)
+        }
+        Command::Run { command, path, num_threads, allow_empty, resync_plugins, verbose, umask } => {
+            let Some(mounted) = begin_mount_session(&path, num_threads, allow_empty, verbose, umask, global_config_path)? else { return Ok(()) };
+
+            let (program, args) = command.split_first().expect("clap requires at least one command arg");
+            Notice::new(NoticePreset::Info, "mounted; running command in the overlay").field("command", command.join(" ")).print();
+            let status = std::process::Command::new(program).args(args).current_dir(&mounted.root_path).status()?;
+            if !status.success() {
+                Notice::new(NoticePreset::Warning, "command exited with a non-zero status").field("status", status.to_string()).print();
+            }
+
+            if resync_plugins {
+                if let Some(data_path) = &mounted.data_path {
+                    modpack::capture_plugins_list(&mounted.layout, data_path)?;
+                }
+            }
+
+            end_mount_session("run", false, mounted)
+        }
+        Command::Mod { command: ModCommand::Info { name, path, json } } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let info = mod_info::gather(&layout, &name)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                info.notice().print();
+            }
+            Ok(())
+        }
+        Command::Mod { command: ModCommand::Rename { old, new, path } } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let report = mod_rename::rename(&layout, &old, &new)?;
+            report.notice().print();
+            Ok(())
+        }
+        Command::Enable { selector } => {
+            let sel = build_selector(selector.selector, selector.group, selector.all_disabled)?;
+            run_bulk_override(&selector.path, sel, overrides::Action::SetEnabled(true), selector.yes, global_config_path)
+        }
+        Command::Disable { selector } => {
+            let sel = build_selector(selector.selector, selector.group, selector.all_disabled)?;
+            run_bulk_override(&selector.path, sel, overrides::Action::SetEnabled(false), selector.yes, global_config_path)
+        }
+        Command::SetPriority { selector, priority } => {
+            let sel = build_selector(selector.name, selector.group, selector.all_disabled)?;
+            run_bulk_override(&selector.path, sel, overrides::Action::SetPriority(priority), selector.yes, global_config_path)
+        }
+        Command::CacheAdd { archive, path } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let already_cached = downloads::cached(&layout, &archive)?.is_some();
+            let dest = downloads::store(&layout, &archive)?;
+            if already_cached {
+                Notice::new(NoticePreset::Info, format!("already cached at {}; discarded the duplicate", dest.display())).print();
+            } else {
+                Notice::new(NoticePreset::Info, format!("cached at {}", dest.display())).print();
+            }
+            Ok(())
+        }
+        Command::ReplayNotices => {
+            use std::io::BufRead;
+            for line in std::io::stdin().lock().lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<serde_json::Value>(&line).ok().and_then(|v| Notice::from_json(&v)) {
+                    Some(notice) => notice.print(),
+                    None => eprintln!("skipping unrecognized line: {line}"),
+                }
+            }
+            Ok(())
+        }
+        Command::Info { nexus: raw_id } => {
+            let query = nexus::parse(&raw_id)?;
+            nexus::query(&query)?.print();
+            Ok(())
+        }
+        Command::Config { path, list, paths } => {
+            if paths {
+                let mut notice = Notice::new(NoticePreset::Info, "global state directories").field("global config", global_config_path.0.display().to_string());
+                if let Ok(dir) = util::xdg::config_dir() {
+                    notice = notice.field("config dir", dir.display().to_string());
+                }
+                if let Ok(dir) = util::xdg::cache_dir() {
+                    notice = notice.field("cache dir", dir.display().to_string());
+                }
+                if let Ok(dir) = util::xdg::state_dir() {
+                    notice = notice.field("state dir", dir.display().to_string());
+                }
+                notice.print();
+                return Ok(());
+            }
+            if !list {
+                return Err(Notice::new(NoticePreset::Error, "nothing to do").field("try", "modcrab config --list or modcrab config --paths").into());
+            }
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let files = lua::config_file_order(&layout.config);
+            let mut notice = Notice::new(NoticePreset::Info, "config load order");
+            for (i, file) in files.iter().enumerate() {
+                notice = notice.field((i + 1).to_string(), file.display().to_string());
+            }
+            notice.print();
+            Ok(())
+        }
+        Command::Meta { path, json } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let data = modpack::load_modpack(&layout)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&data.meta)?);
+                return Ok(());
+            }
+
+            if data.meta.is_empty() {
+                Notice::new(NoticePreset::Info, "no modcrab.meta declared for this pack").print();
+                return Ok(());
+            }
+
+            let mut notice = Notice::new(NoticePreset::Info, "pack metadata");
+            if let Some(name) = &data.meta.name {
+                notice = notice.field("name", name.clone());
+            }
+            if let Some(version) = &data.meta.version {
+                notice = notice.field("version", version.clone());
+            }
+            if let Some(author) = &data.meta.author {
+                notice = notice.field("author", author.clone());
+            }
+            if let Some(description) = &data.meta.description {
+                notice = notice.field("description", description.clone());
+            }
+            if let Some(url) = &data.meta.url {
+                notice = notice.field("url", url.clone());
+            }
+            notice.print();
+            Ok(())
+        }
+        Command::Check { path } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            modpack::validate_modpack(&layout)?;
+            let config = lua::eval_config(&layout.config, global_config_path)?;
+            let notices = modpack::check_config(&config, &layout)?;
+
+            if notices.is_empty() {
+                Notice::new(NoticePreset::Info, "no structural problems found").print();
+            } else {
+                for notice in &notices {
+                    notice.print();
+                }
+            }
+            Ok(())
+        }
+        Command::Doctor { path } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            modpack::validate_modpack(&layout)?;
+            let data = modpack::load_modpack(&layout)?;
+            let config = lua::eval_config(&layout.config, global_config_path)?;
+            let Some(target) = &config.target else {
+                return Err(Notice::new(NoticePreset::Error, "no target game configured; nothing to check against").into());
+            };
+            let root = target.resolve_root()?;
+
+            let layers = modpack::overlay_layers(&layout, &root, &data);
+            let hide = modpack::overlay_hide_patterns(&data);
+            let tree = modcrab_fs::VirtualFileTree::from_layers(&layers, &hide, &mut |_| {}).unwrap_or_default();
+
+            let issues = tree.audit_real_paths();
+            if issues.is_empty() {
+                Notice::new(NoticePreset::Info, "no issues found").print();
+            } else {
+                let mut notice = Notice::new(NoticePreset::Warning, format!("{} issue(s) found", issues.len()));
+                for issue in &issues {
+                    let (label, detail) = match issue {
+                        modcrab_fs::AuditIssue::Missing(virtual_path, _) => ("missing", virtual_path.display().to_string()),
+                        modcrab_fs::AuditIssue::NotReadable(virtual_path, _) => ("not readable", virtual_path.display().to_string()),
+                        modcrab_fs::AuditIssue::TypeMismatch(virtual_path, recorded, actual) => {
+                            ("type mismatch", format!("{} (expected {recorded:?}, found {actual:?})", virtual_path.display()))
+                        }
+                    };
+                    notice = notice.field(label, detail);
+                }
+                notice.print();
+            }
+
+            Notice::new(NoticePreset::Info, "to enable tracing, set MODCRAB_LOG=trace").print();
+            Ok(())
+        }
+        Command::Bench { command: BenchCommand::Mount { path, runs, json_out } } => {
+            let layout = modpack::PackLayout::resolve(&path)?;
+            let data = modpack::load_modpack(&layout)?;
+            let config = lua::eval_config(&layout.config, global_config_path)?;
+            let Some(target) = &config.target else {
+                return Err(Notice::new(NoticePreset::Error, "no target game configured; nothing to benchmark against").into());
+            };
+            let root = target.resolve_root()?;
+            let layers = modpack::overlay_layers(&layout, &root, &data);
+            let hide = modpack::overlay_hide_patterns(&data);
+
+            let runs = runs.max(1);
+            let mut timings = Vec::with_capacity(runs);
+            let mut stats = (0, 0, 0);
+            for _ in 0..runs {
+                let start = std::time::Instant::now();
+                let tree = modcrab_fs::VirtualFileTree::from_layers(&layers, &hide, &mut |_| {}).map_err(structs::error::AppError::Io)?;
+                timings.push(start.elapsed());
+                stats = tree.memory_stats();
+            }
+
+            let total: std::time::Duration = timings.iter().sum();
+            let avg = total / runs as u32;
+            let min = timings.iter().min().copied().unwrap_or_default();
+            let max = timings.iter().max().copied().unwrap_or_default();
+            let (nodes, edges, approx_bytes) = stats;
+
+            Notice::new(NoticePreset::Statistics, format!("eager scan, {runs} run(s)"))
+                .field("avg", format!("{:.1}ms", avg.as_secs_f64() * 1000.0))
+                .field("min", format!("{:.1}ms", min.as_secs_f64() * 1000.0))
+                .field("max", format!("{:.1}ms", max.as_secs_f64() * 1000.0))
+                .field("nodes", nodes.to_string())
+                .field("edges", edges.to_string())
+                .field("approx bytes", approx_bytes.to_string())
+                .print();
+
+            if let Some(json_path) = json_out {
+                let payload = serde_json::json!({
+                    "strategy": "eager_scan",
+                    "runs": runs,
+                    "avg_ms": avg.as_secs_f64() * 1000.0,
+                    "min_ms": min.as_secs_f64() * 1000.0,
+                    "max_ms": max.as_secs_f64() * 1000.0,
+                    "nodes": nodes,
+                    "edges": edges,
+                    "approx_bytes": approx_bytes,
+                });
+                std::fs::write(&json_path, serde_json::to_string_pretty(&payload)?)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Turns a bare `enable`/`disable`/`set-priority` selector into an [`overrides::Selector`],
+/// rejecting zero or more than one criterion given at once. A bare name/glob with any
+/// of `*?[` is treated as a glob; anything else is an exact, case-insensitive name.
+fn build_selector(name_or_glob: Option<String>, group: Option<String>, all_disabled: bool) -> AppResult<overrides::Selector> {
+    let given = [name_or_glob.is_some(), group.is_some(), all_disabled].iter().filter(|b| **b).count();
+    if given != 1 {
+        return Err(Notice::new(NoticePreset::Error, "specify exactly one of a name/glob, --group, or --all-disabled").into());
+    }
+
+    if let Some(raw) = name_or_glob {
+        return Ok(if raw.contains(['*', '?', '[']) { overrides::Selector::Glob(raw) } else { overrides::Selector::Name(raw) });
+    }
+    if let Some(group) = group {
+        return Ok(overrides::Selector::Group(group));
+    }
+    Ok(overrides::Selector::AllDisabled)
+}
+
+/// Previews, optionally confirms, then persists one bulk override rule.
+fn run_bulk_override(path: &Path, selector: overrides::Selector, action: overrides::Action, yes: bool, global_config_path: &GlobalConfigPath) -> AppResult<()> {
+    let layout = modpack::PackLayout::resolve(path)?;
+    let config = lua::eval_config(&layout.config, global_config_path)?;
+    let mut specs = config.specs;
+    overrides::apply(&overrides::load(&layout)?, &mut specs);
+
+    let affected = overrides::preview(&selector, &specs);
+    if affected.is_empty() {
+        Notice::new(NoticePreset::Warning, "no mods matched that selector").print();
+        return Ok(());
+    }
+
+    Notice::new(NoticePreset::Info, format!("will {} {} mod(s)", action.describe(), affected.len())).field("matched", affected.join(", ")).print();
+
+    if affected.len() > overrides::CONFIRM_THRESHOLD && !yes && !util::misc::confirm("proceed?")? {
+        Notice::new(NoticePreset::Info, "aborted; no changes made").print();
+        return Ok(());
+    }
+
+    overrides::append(&layout, overrides::OverrideRule { selector, action })?;
+    Notice::new(NoticePreset::Info, "override saved; it will apply on the next build").print();
+    Ok(())
+}
+
+/// A filesystem mounted for the duration of a foreground command (`mount`, `shell`,
+/// `run`): the live session, the path it's mounted at, a metrics handle taken before
+/// the `ModcrabFS` was consumed by the mount call, and a snapshot of `overwrite/` plus
+/// a start time for the session report once it ends.
+struct MountedSession {
+    layout: modpack::PackLayout,
+    root_path: PathBuf,
+    /// The resolved game data directory, if the target declares one — `run`'s
+    /// `--resync-plugins` needs this to read back whatever `plugins.txt` a tool wrote.
+    data_path: Option<PathBuf>,
+    metrics: std::sync::Arc<modcrab_fs::FsMetrics>,
+    session: modcrab_fs::BackgroundSession,
+    before: std::collections::HashMap<PathBuf, u64>,
+    started: std::time::Instant,
+    /// Bucket names from `modcrab.overwrite_rules`, for the session report to break its
+    /// `overwrite/` diff down by where each created file actually landed.
+    overwrite_buckets: Vec<String>,
+    _lock: modpack::MountLock,
+}
+
+/// Validates and loads a built modpack, resolves its target, runs the `pre_launch` hook
+/// (if any), stages the plugin list, and mounts the overlay. Returns `None` if the hook
+/// or the zero-mods confirmation aborted the mount, in which case the caller should
+/// return `Ok(())` without a report.
+fn begin_mount_session(
+    path: &Path,
+    num_threads: Option<usize>,
+    allow_empty: bool,
+    verbose: bool,
+    umask: Option<u32>,
+    global_config_path: &GlobalConfigPath,
+) -> AppResult<Option<MountedSession>> {
+    let layout = modpack::PackLayout::resolve(path)?;
+    modpack::validate_modpack(&layout)?;
+    let mut data = modpack::load_modpack(&layout)?;
+    if let Some(stale) = modpack::staleness_notice(&layout, &data, global_config_path) {
+        stale.print();
+    }
+    let config = lua::eval_config(&layout.config, global_config_path)?;
+    let mut target = config.target.ok_or_else(|| Notice::new(NoticePreset::Error, "no target game set"))?;
+    let (root_path, relocation) = target.resolve_root_checked()?;
+    if let Some(notice) = relocation {
+        notice.print();
+    }
+
+    if data.mods.is_empty() && data.root_mods.is_empty() {
+        Notice::new(NoticePreset::Warning, "zero mods are enabled; mounting now will be a vanilla run").print();
+        if !allow_empty && !util::misc::confirm("mount anyway?")? {
+            Notice::new(NoticePreset::Info, "aborted; pass --allow-empty to skip this prompt").print();
+            return Ok(None);
+        }
+    }
+
+    if let Some(hook) = &config.pre_launch {
+        if !hook.run(&root_path)? {
+            Notice::new(NoticePreset::Warning, "pre_launch hook returned false; aborting the mount").print();
+            return Ok(None);
+        }
+    }
+
+    let mut data_path = None;
+    if let Ok((resolved, relocation)) = target.resolve_data_checked() {
+        if let Some(notice) = relocation {
+            notice.print();
+        }
+        modpack::install_plugins_list(&layout, &resolved)?;
+        data_path = Some(resolved);
+    }
+
+    let before = modpack::snapshot_overwrite(&layout)?;
+    let started = std::time::Instant::now();
+
+    let overwrite_buckets = config.overwrite_rules.iter().map(|rule| rule.bucket.clone()).collect();
+
+    let lock = modpack::MountLock::acquire(&layout)?;
+    let fs = mount::mount_modpack(&layout, &root_path, &mut data, num_threads, config.shadow_passthrough, config.overwrite_rules, verbose, umask)?;
+    let metrics = fs.metrics_handle();
+    let layers = modpack::overlay_layers(&layout, &root_path, &data);
+    if let Err(e) = hotreload::watch_for_sighup(layout.clone(), root_path.clone(), fs.tree_handle(), layers) {
+        Notice::new(NoticePreset::Warning, "failed to register SIGHUP handler; config hot-reload won't be available this session")
+            .field("error", e.to_string())
+            .print();
+    }
+    let session = modcrab_fs::spawn_mount(fs, &root_path)?;
+
+    Ok(Some(MountedSession { layout, root_path, data_path, metrics, session, before, started, overwrite_buckets, _lock: lock }))
+}
+
+/// Unmounts a session started by [`begin_mount_session`] and, unless `no_report` is set,
+/// prints and logs what changed under `overwrite/` during it.
+fn end_mount_session(command: &str, no_report: bool, mounted: MountedSession) -> AppResult<()> {
+    drop(mounted.session);
+
+    if !no_report {
+        let after = modpack::snapshot_overwrite(&mounted.layout)?;
+        let report = modpack::SessionReport::build(
+            mounted.started.elapsed(),
+            &mounted.before,
+            &after,
+            mounted.metrics.bytes_read(),
+            mounted.metrics.bytes_written(),
+            mounted.metrics.lstat_calls(),
+            mounted.metrics.attr_cache_hits(),
+            &mounted.overwrite_buckets,
+        );
+        report.notice().print();
+        modpack::append_session_log(&mounted.layout, command, &report)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolved_log_level_lets_an_explicit_modcrab_log_override_verbose() {
+        assert_eq!(resolved_log_level(false, Some("trace")), log::LevelFilter::Trace);
+        assert_eq!(resolved_log_level(true, Some("error")), log::LevelFilter::Error);
+    }
+
+    #[test]
+    fn resolved_log_level_falls_back_to_verbose_then_warn_without_modcrab_log() {
+        assert_eq!(resolved_log_level(false, None), log::LevelFilter::Warn);
+        assert_eq!(resolved_log_level(true, None), log::LevelFilter::Info);
+        assert_eq!(resolved_log_level(false, Some("not a level")), log::LevelFilter::Warn);
+    }
+
+    #[test]
+    fn parse_umask_accepts_octal_with_or_without_a_0o_prefix_and_rejects_garbage() {
+        assert_eq!(parse_umask("022"), Ok(0o022));
+        assert_eq!(parse_umask("0o022"), Ok(0o022));
+        assert!(parse_umask("not an octal").is_err());
+    }
+
+    #[test]
+    fn mount_parses_an_octal_umask_flag() {
+        let Command::Mount { umask, .. } = Cli::try_parse_from(["modcrab", "mount", "--umask", "022"]).unwrap().command else { panic!("expected Command::Mount") };
+        assert_eq!(umask, Some(0o022));
+    }
+
+    #[test]
+    fn visible_aliases_parse_to_the_same_variant_as_the_full_name() {
+        assert!(matches!(Cli::try_parse_from(["modcrab", "b"]).unwrap().command, Command::Build { .. }));
+        assert!(matches!(Cli::try_parse_from(["modcrab", "build"]).unwrap().command, Command::Build { .. }));
+        assert!(matches!(Cli::try_parse_from(["modcrab", "m"]).unwrap().command, Command::Mount { .. }));
+        assert!(matches!(Cli::try_parse_from(["modcrab", "r", "loot"]).unwrap().command, Command::Run { .. }));
+    }
+
+    #[test]
+    fn run_captures_a_flag_looking_first_token_without_a_dash_dash_separator() {
+        let Command::Run { command, .. } = Cli::try_parse_from(["modcrab", "run", "loot", "-y"]).unwrap().command else {
+            panic!("expected Command::Run");
+        };
+        assert_eq!(command, vec!["loot".to_owned(), "-y".to_owned()]);
+    }
+
+    #[test]
+    fn unmount_parses_with_a_default_path() {
+        let Command::Unmount { path } = Cli::try_parse_from(["modcrab", "unmount"]).unwrap().command else { panic!("expected Command::Unmount") };
+        assert_eq!(path, PathBuf::from("."));
+    }
+
+    #[test]
+    fn suggest_subcommand_finds_the_nearest_known_name_on_a_typo() {
+        let Err(e) = Cli::try_parse_from(["modcrab", "buidl"]) else { panic!("expected a parse error") };
+        assert_eq!(suggest_subcommand(&e), Some("build".to_owned()));
+    }
+
+    #[test]
+    fn suggest_subcommand_is_none_for_errors_that_are_not_an_unknown_subcommand() {
+        let Err(e) = Cli::try_parse_from(["modcrab", "run"]) else { panic!("expected a parse error") };
+        assert_eq!(suggest_subcommand(&e), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! Mounting the overlay itself: the one place this binary talks to `modcrab-fs`
+//! directly, so that `modcrab-core` can stay free of a filesystem dependency.
+//!
+//! There's no persisted cache of path transformations to invalidate here: every mount
+//! rebuilds the [`VirtualFileTree`](modcrab_fs::VirtualFileTree) from `layers` (and the
+//! mod list that produced them) from scratch, so a move that no longer applies because
+//! a mod now ships that destination path simply isn't mapped in the first place —
+//! there's nothing stale left over to prune or report on.
+
+use std::path::Path;
+
+use modcrab_core::modpack::{self, PackLayout};
+use modcrab_core::structs::data::{AppData, OverwriteRule};
+use modcrab_core::structs::error::{AppError, AppResult};
+use modcrab_core::util::notice::{Notice, NoticePreset};
+
+/// Mounts the modpack's overlay over `root_path` using the pre-built `data`. `num_threads`
+/// overrides the default FUSE worker thread count when set. `shadow_passthrough` names
+/// virtual path components (from `modcrab.shadow_passthrough`) that write straight into
+/// `root_path` instead of `overwrite/`. `overwrite_rules` (from `modcrab.overwrite_rules`)
+/// route a matching write into a named bucket under `overwrite/` instead of its top
+/// level. With `verbose`, mapping progress is shown as an `indicatif` bar naming the
+/// layer currently being scanned instead of a bare file count. `umask`, if set, clears
+/// those bits from the mode of every file/directory `create`/`mkdir`/`mknod` creates
+/// through the overlay.
+#[allow(clippy::too_many_arguments)]
+pub fn mount_modpack(
+    layout: &PackLayout,
+    root_path: &Path,
+    data: &mut AppData,
+    num_threads: Option<usize>,
+    shadow_passthrough: Vec<String>,
+    overwrite_rules: Vec<OverwriteRule>,
+    verbose: bool,
+    umask: Option<u32>,
+) -> AppResult<modcrab_fs::ModcrabFS> {
+    modpack::guard_mount_cwd(root_path, &layout.root)?;
+    // Marks this in-memory `AppData` as bound to a live mount. Not persisted — the
+    // authoritative, PID-checked signal other commands check is `modpack::is_mounted`
+    // (see `modpack::save_modpack`); this just keeps the flag honest for anything else
+    // that's already holding this same `data` for the rest of the session.
+    data.locked = true;
+
+    let layers = modpack::overlay_layers(layout, root_path, data);
+    let hide = modpack::overlay_hide_patterns(data);
+    if let Some(layer) = modpack::find_layer_under_root(&layers, root_path) {
+        return Err(Notice::new(
+            NoticePreset::Error,
+            format!("mod layer '{}' lives under the game root; mounting would cause recursive lookups", layer.display()),
+        )
+        .into());
+    }
+
+    let overwrite_rules = overwrite_rules.into_iter().map(|rule| (rule.pattern, rule.bucket)).collect();
+    let mut options = modcrab_fs::ModcrabFSOptions::default().shadow_passthrough(shadow_passthrough).overwrite_rules(overwrite_rules);
+    if let Some(num_threads) = num_threads {
+        options = options.num_threads(num_threads);
+    }
+    if let Some(umask) = umask {
+        options = options.umask(umask);
+    }
+
+    // One name per layer index `MappingProgress::layer_idx` can take: the game root
+    // itself, then every enabled mod in the same order `overlay_layers` pushed them,
+    // then the writable surface mapped last.
+    let layer_names: Vec<String> =
+        std::iter::once("game files".to_owned()).chain(data.mods.values().map(|spec| spec.name.clone())).chain(std::iter::once("overwrite".to_owned())).collect();
+
+    let total_layers = layers.len() + 1;
+    let bar = verbose.then(|| {
+        let bar = indicatif::ProgressBar::new(0);
+        bar.set_style(indicatif::ProgressStyle::with_template("{msg} ({pos}/{len} files)").unwrap());
+        bar
+    });
+    let mut last_layer = usize::MAX;
+    let mut report_progress = |progress: modcrab_fs::MappingProgress| match &bar {
+        Some(bar) => {
+            if progress.layer_idx != last_layer {
+                last_layer = progress.layer_idx;
+                let name = layer_names.get(progress.layer_idx).map(String::as_str).unwrap_or("layer");
+                bar.set_length(progress.total_files as u64);
+                bar.set_message(format!("[{name}] scanning..."));
+            }
+            bar.set_position(progress.files_mapped as u64);
+        }
+        None => {
+            eprint!("\rmapping layer {}/{total_layers}: {} files mapped", progress.layer_idx + 1, progress.files_mapped);
+            let _ = std::io::Write::flush(&mut std::io::stderr());
+        }
+    };
+
+    let fs = modcrab_fs::ModcrabFS::new_with_progress(root_path, &layers, &hide, &layout.overwrite, options, &mut report_progress).map_err(AppError::Io)?;
+    match &bar {
+        Some(bar) => bar.finish_and_clear(),
+        None => eprintln!(),
+    }
+    Ok(fs)
+}
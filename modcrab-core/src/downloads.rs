@@ -0,0 +1,126 @@
+//! A content-addressed cache under `downloads/`, keyed by a file's SHA-256 hash so the
+//! same archive is never stored twice even under different file names. modcrab has no
+//! network acquisition path yet (mods are placed under `mods/` by hand or by other
+//! tools), so this is exposed as `modcrab cache add`: pointing it at an archive you
+//! already downloaded moves it into the cache, deduping against anything already there.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::modpack::PackLayout;
+use crate::structs::error::AppResult;
+
+/// Hex SHA-256 of a file's contents, read in fixed-size chunks so large archives don't
+/// need to be loaded into memory all at once.
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    let digest = hasher.finalize();
+    Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// The cache path a file named `file_name` with content hash `hash` would live at,
+/// preserving `file_name`'s extension so the cached copy still looks like an archive.
+fn entry_path(layout: &PackLayout, hash: &str, file_name: &str) -> PathBuf {
+    match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some(ext) => layout.downloads.join(format!("{hash}.{ext}")),
+        None => layout.downloads.join(hash),
+    }
+}
+
+/// Returns the cached copy of `source`'s content, if one is already present under
+/// `downloads/`. Checks by hash, not by name, so renamed duplicates are still found.
+pub fn cached(layout: &PackLayout, source: &Path) -> std::io::Result<Option<PathBuf>> {
+    let hash = hash_file(source)?;
+    let file_name = source.file_name().and_then(|f| f.to_str()).unwrap_or("download");
+    let path = entry_path(layout, &hash, file_name);
+    Ok(path.is_file().then_some(path))
+}
+
+/// Moves `source` into the `downloads/` cache under its content hash. If an entry with
+/// the same hash is already cached, `source` is deleted instead of duplicated.
+pub fn store(layout: &PackLayout, source: &Path) -> AppResult<PathBuf> {
+    std::fs::create_dir_all(&layout.downloads)?;
+    let hash = hash_file(source)?;
+    let file_name = source.file_name().and_then(|f| f.to_str()).unwrap_or("download");
+    let dest = entry_path(layout, &hash, file_name);
+
+    if dest.is_file() {
+        if source != dest {
+            std::fs::remove_file(source)?;
+        }
+    } else {
+        std::fs::rename(source, &dest)?;
+    }
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("modcrab-downloads-test-{label}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn hash_file_is_stable_for_identical_content() {
+        let dir = temp_dir("hash");
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.7z");
+        let b = dir.join("b.7z");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+
+        assert_eq!(hash_file(&a).unwrap(), hash_file(&b).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn store_dedupes_identical_content_under_different_names() {
+        let dir = temp_dir("store");
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = dir.join("USSEP-1.7z");
+        let second = dir.join("USSEP-1-renamed.7z");
+        std::fs::write(&first, b"archive contents").unwrap();
+        std::fs::write(&second, b"archive contents").unwrap();
+
+        let first_dest = store(&layout, &first).unwrap();
+        let second_dest = store(&layout, &second).unwrap();
+
+        assert_eq!(first_dest, second_dest);
+        assert_eq!(walkdir::WalkDir::new(&layout.downloads).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()).count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cached_finds_an_existing_entry_by_hash_not_name() {
+        let dir = temp_dir("cached");
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let original = dir.join("original.7z");
+        std::fs::write(&original, b"shared payload").unwrap();
+        store(&layout, &original).unwrap();
+
+        let renamed = dir.join("renamed.7z");
+        std::fs::write(&renamed, b"shared payload").unwrap();
+        assert!(cached(&layout, &renamed).unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
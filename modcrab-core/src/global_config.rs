@@ -0,0 +1,101 @@
+//! A user-level config, distinct from a modpack's own `config/*.lua`/`*.toml`, that
+//! holds settings meant to apply across every modpack on this machine. Currently just
+//! a fallback Nexus API key, so a user managing several modpacks doesn't have to
+//! repeat it in each one's `modcrab.nexus_key`. Lives at `$XDG_CONFIG_HOME/modcrab/config.toml`
+//! (see [`crate::util::xdg`]) by default; [`GlobalConfigPath`] lets a caller point
+//! elsewhere, for multiple users sharing a machine or for isolated CI runs.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::structs::error::AppResult;
+use crate::util::notice::{Notice, NoticePreset};
+use crate::util::xdg;
+
+/// Where to look for the global config, overriding the default
+/// `$XDG_CONFIG_HOME/modcrab/config.toml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalConfigPath(pub PathBuf);
+
+impl Default for GlobalConfigPath {
+    fn default() -> Self {
+        // `xdg::config_dir` creates the directory on first use; if that fails (no home
+        // directory at all, say), fall back to the bare relative path it would have
+        // returned, so a later read/write still fails with a sensible error instead of
+        // panicking here.
+        let dir = xdg::config_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self(dir.join("config.toml"))
+    }
+}
+
+/// The global config's contents. Every field is optional: an absent or unparsable
+/// file degrades to [`GlobalConfig::default`], same as a modpack missing a setting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GlobalConfig {
+    /// Used as a fallback wherever a modpack's own `modcrab.nexus_key` is unset.
+    #[serde(default)]
+    pub nexus_key: Option<String>,
+    /// Seconds of Lua execution [`crate::lua::eval_config`] allows before aborting a
+    /// modpack's `config/*.lua` as a runaway loop. Global-only, deliberately not
+    /// settable from `modcrab.*` — a config that hangs forever could otherwise just
+    /// raise its own timeout to dodge the guard. `None` falls back to 30 seconds.
+    #[serde(default)]
+    pub timeout_build: Option<u64>,
+}
+
+impl GlobalConfig {
+    /// Reads `path`, falling back to [`GlobalConfig::default`] if it doesn't exist.
+    pub fn load_from(path: &Path) -> AppResult<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        toml::from_str(&contents).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to parse {}: {e}", path.display())).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_from_falls_back_to_defaults_when_the_file_is_missing() {
+        let path = std::env::temp_dir().join(format!("modcrab-global-config-missing-test-{}", std::process::id()));
+        assert_eq!(GlobalConfig::load_from(&path).unwrap(), GlobalConfig::default());
+    }
+
+    #[test]
+    fn load_from_reads_the_nexus_key() {
+        let path = std::env::temp_dir().join(format!("modcrab-global-config-test-{}", std::process::id()));
+        std::fs::write(&path, "nexus_key = \"abc123\"\n").unwrap();
+
+        let config = GlobalConfig::load_from(&path).unwrap();
+        assert_eq!(config.nexus_key.as_deref(), Some("abc123"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_from_reads_timeout_build() {
+        let path = std::env::temp_dir().join(format!("modcrab-global-config-timeout-test-{}", std::process::id()));
+        std::fs::write(&path, "timeout_build = 5\n").unwrap();
+
+        let config = GlobalConfig::load_from(&path).unwrap();
+        assert_eq!(config.timeout_build, Some(5));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_from_errors_on_unparsable_toml() {
+        let path = std::env::temp_dir().join(format!("modcrab-global-config-bad-test-{}", std::process::id()));
+        std::fs::write(&path, "this is not valid toml {{{").unwrap();
+
+        assert!(GlobalConfig::load_from(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+//! Importers for migrating a modpack declaration from other mod managers.
+
+use std::path::{Path, PathBuf};
+
+use crate::modpack::{self, PackLayout};
+use crate::structs::error::AppResult;
+use crate::util::notice::{Notice, NoticePreset};
+
+/// Parses a Mod Organizer 2 `modlist.txt` (`+name` enabled, `-name` disabled, top =
+/// highest priority) and writes a starter `config/imported.lua` declaring one
+/// `ModSpec` table per entry. MO2 lists mods top-priority-first; modcrab's `priority`
+/// field sorts lowest-first, so the mapping is inverted: the top entry gets the
+/// highest priority number. `#` lines (e.g. `write_modlist`'s own `ModMeta` header
+/// comment) are ignored, not treated as entries.
+pub fn import_mo2(modlist_path: &Path, pack_root: &Path) -> AppResult<PathBuf> {
+    let text = std::fs::read_to_string(modlist_path)?;
+    let entries: Vec<(&str, bool)> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match line.strip_prefix('+') {
+            Some(name) => Some((name, true)),
+            None => line.strip_prefix('-').map(|name| (name, false)),
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Err(Notice::new(NoticePreset::Warning, format!("no MO2-style entries found in {}", modlist_path.display())).into());
+    }
+
+    let layout = PackLayout::resolve(pack_root)?;
+    warn_if_importing_an_older_version(&text, &layout);
+
+    let lua = render_lua(&entries);
+
+    std::fs::create_dir_all(&layout.config)?;
+    let out_path = layout.config.join("imported.lua");
+    std::fs::write(&out_path, lua)?;
+    Ok(out_path)
+}
+
+/// If `modlist_text` carries a `# version: ...` header comment (see
+/// [`crate::structs::data::ModMeta::header_comment`]) and the local pack already has a
+/// built `data.bin` with its own `meta.version` set, warns when the incoming version is
+/// older than the local one — importing stale modlist describes a pack state the local
+/// one has since moved past. Silent when either version is missing, unparsable, or the
+/// local pack hasn't been built yet; this is a heads-up, not a guard.
+fn warn_if_importing_an_older_version(modlist_text: &str, layout: &PackLayout) {
+    let Some(incoming) = header_comment_value(modlist_text, "version") else { return };
+    let Ok(local_data) = modpack::load_modpack(layout) else { return };
+    let Some(local) = &local_data.meta.version else { return };
+
+    if version_is_older(&incoming, local) {
+        Notice::new(NoticePreset::Warning, format!("importing an older pack version ('{incoming}') over the local one ('{local}')")).print();
+    }
+}
+
+/// Reads the value of a `# key: value` header comment line (as
+/// [`crate::structs::data::ModMeta::header_comment`] writes), if present.
+fn header_comment_value(text: &str, key: &str) -> Option<String> {
+    let prefix = format!("# {key}: ");
+    text.lines().find_map(|line| line.strip_prefix(&prefix)).map(str::trim).map(str::to_owned)
+}
+
+/// A lenient dotted-version comparison: each component is compared numerically where
+/// both sides parse as an integer, falling back to a plain string comparison for the
+/// first component that doesn't (e.g. a `"1.0-beta"` suffix), so `"0.9" < "0.10"` but
+/// a non-numeric scheme still degrades to *some* answer instead of panicking.
+fn version_is_older(a: &str, b: &str) -> bool {
+    let a_parts = a.split('.');
+    let b_parts = b.split('.');
+    for (a_part, b_part) in a_parts.zip(b_parts) {
+        match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+            (Ok(a_num), Ok(b_num)) if a_num != b_num => return a_num < b_num,
+            (Ok(_), Ok(_)) => continue,
+            _ if a_part != b_part => return a_part < b_part,
+            _ => continue,
+        }
+    }
+    a.split('.').count() < b.split('.').count()
+}
+
+fn render_lua(entries: &[(&str, bool)]) -> String {
+    let count = entries.len();
+    let mut lua = String::from("-- Imported from a Mod Organizer 2 modlist.txt by `modcrab import-mo2`.\n\n");
+    for (index, (name, is_enabled)) in entries.iter().enumerate() {
+        let priority = count - index;
+        let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+        lua.push_str(&format!("table.insert(modcrab.mods, {{ name = \"{escaped}\", is_enabled = {is_enabled}, priority = {priority} }})\n"));
+    }
+    lua
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_lua_inverts_mo2_priority_and_preserves_enabled_state() {
+        let entries = vec![("Top", true), ("Disabled", false), ("Bottom", true)];
+        let lua = render_lua(&entries);
+        assert!(lua.contains(r#"name = "Top", is_enabled = true, priority = 3"#));
+        assert!(lua.contains(r#"name = "Disabled", is_enabled = false, priority = 2"#));
+        assert!(lua.contains(r#"name = "Bottom", is_enabled = true, priority = 1"#));
+    }
+
+    #[test]
+    fn header_comment_value_reads_a_meta_header_line_and_ignores_the_rest() {
+        let text = "# name: Aurora\n# version: 0.9\n+Top\n-Bottom\n";
+        assert_eq!(header_comment_value(text, "version").as_deref(), Some("0.9"));
+        assert_eq!(header_comment_value(text, "author"), None);
+    }
+
+    #[test]
+    fn version_is_older_compares_numeric_components_not_lexically() {
+        assert!(version_is_older("0.9", "0.10"));
+        assert!(!version_is_older("0.10", "0.9"));
+        assert!(!version_is_older("1.0", "1.0"));
+        assert!(version_is_older("1.0", "1.0.1"));
+    }
+}
@@ -0,0 +1,19 @@
+//! The core modpack model shared by every modcrab frontend: config evaluation, mod
+//! list validation/sorting, the persisted override layer, and the on-disk modpack
+//! layout. Deliberately has no dependency on `modcrab-fs`; mounting the overlay itself
+//! is the CLI's job, not the core model's.
+
+pub mod downloads;
+pub mod global_config;
+pub mod import;
+pub mod lua;
+pub mod mod_info;
+pub mod mod_rename;
+pub mod modpack;
+pub mod nexus;
+pub mod onboarding;
+pub mod overrides;
+pub mod structs;
+pub mod template;
+pub mod toml_config;
+pub mod util;
@@ -0,0 +1,728 @@
+//! Evaluates a modpack's `config/*.lua` files into an [`AppConfig`].
+//!
+//! Every file is run against the same [`mlua::Lua`] instance and shares a single
+//! `modcrab` global table, so later files can see (and override) what earlier files
+//! set up. The sandbox exposes:
+//!
+//! - `modcrab.target` — a string naming a known [`GameSpec`](crate::structs::spec::GameSpec).
+//! - `modcrab.root_path` — an optional override for the target's install root, skipping
+//!   the scan in [`GameSpec::scan_for_root`](crate::structs::spec::GameSpec::scan_for_root).
+//! - `modcrab.nexus_key` — an optional Nexus API key string.
+//! - `modcrab.mods` — an array the user appends mod tables to via `table.insert`.
+//! - `modcrab.template(defaults)` — returns a function that merges a per-mod table of
+//!   overrides into `defaults`, so a pack with many similarly-configured mods doesn't
+//!   have to repeat the shared fields on every one of them.
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use mlua::{Function, HookTriggers, Lua, RegistryKey, Table, Value};
+use walkdir::WalkDir;
+
+use crate::global_config::{GlobalConfig, GlobalConfigPath};
+use crate::structs::data::{AppConfig, ModMeta, OverwriteRule};
+use crate::structs::error::{AppError, AppResult};
+use crate::structs::spec::{generate_default_game_specs, ModSpec, TargetGame};
+use crate::toml_config;
+use crate::util::notice::{Notice, NoticePreset};
+
+/// How long `config/*.lua` may run before [`eval_config`] aborts it as a runaway
+/// config, when the global config's `timeout_build` doesn't override it.
+const DEFAULT_TIMEOUT_BUILD_SECS: u64 = 30;
+
+/// The message a runaway-config's aborted execution surfaces as, via the
+/// [`mlua::Error::RuntimeError`] the deadline hook raises. Checked for verbatim when
+/// deciding whether to turn the resulting [`mlua::Error`] into a friendlier [`Notice`].
+const TIMEOUT_SENTINEL: &str = "modcrab: config/*.lua build timeout exceeded";
+
+/// Defines `modcrab.template`, run against the `Lua` instance before any user config
+/// file so every file can use it. Kept as a Lua snippet rather than a Rust
+/// `create_function`, since the merge itself is plain table manipulation with no need
+/// to cross the Rust/Lua boundary.
+const TEMPLATE_PRELUDE: &str = r#"
+    modcrab.template = function(defaults)
+        return function(overrides)
+            local merged = {}
+            for k, v in pairs(defaults) do merged[k] = v end
+            for k, v in pairs(overrides or {}) do merged[k] = v end
+            return merged
+        end
+    end
+"#;
+
+/// Where [`eval_config_source`] reads its `modcrab` config from.
+pub enum ConfigSource {
+    /// A `config/` directory of `.lua`/`.toml` files, loaded in natural sort order — the
+    /// normal on-disk modpack layout. [`eval_config`] is a thin wrapper around this
+    /// variant, for the overwhelming majority of callers that already have one of these.
+    Dir(PathBuf),
+    /// A single `.lua` file, for a one-off config snippet with no modpack directory to
+    /// scaffold around it. Evaluated in the same sandbox as a directory's files, but
+    /// with no `*.toml` sibling to merge in.
+    File(PathBuf),
+    /// Lua source read from stdin, for a config piped in by a script rather than
+    /// scaffolded onto disk at all. Named `<stdin>` in any error a parse failure
+    /// reports.
+    Stdin,
+}
+
+/// Evaluates `config_dir`'s `*.lua`/`*.toml` files into an [`AppConfig`], falling back
+/// to `global_config_path`'s `nexus_key` wherever the modpack itself doesn't set one.
+/// Files load in natural sort order (`natord::compare`), so `10_patches.lua` runs after
+/// `9_gameplay.lua` instead of before it — plain lexicographic order would put any
+/// double-digit numeric prefix ahead of its single-digit neighbors.
+pub fn eval_config(config_dir: &Path, global_config_path: &GlobalConfigPath) -> AppResult<AppConfig> {
+    eval_config_source(ConfigSource::Dir(config_dir.to_path_buf()), global_config_path)
+}
+
+/// Like [`eval_config`], but also accepts a single Lua file or stdin as the config
+/// source (see [`ConfigSource`]) instead of requiring a full `config/` directory — for
+/// scripted or ephemeral use (testing a snippet, generating a pack programmatically)
+/// that doesn't warrant scaffolding one.
+pub fn eval_config_source(source: ConfigSource, global_config_path: &GlobalConfigPath) -> AppResult<AppConfig> {
+    let global_config = GlobalConfig::load_from(&global_config_path.0)?;
+    let timeout_build = global_config.timeout_build.unwrap_or(DEFAULT_TIMEOUT_BUILD_SECS);
+
+    let lua = Lua::new();
+    let modcrab = lua.create_table()?;
+    modcrab.set("mods", lua.create_table()?)?;
+    lua.globals().set("modcrab", modcrab)?;
+    lua.load(TEMPLATE_PRELUDE).set_name("<modcrab.template prelude>").exec()?;
+
+    let sources: Vec<(String, String)> = match &source {
+        ConfigSource::Dir(dir) => scan_config_files(dir, "lua")
+            .iter()
+            .map(|entry| Ok((entry.path().to_string_lossy().into_owned(), std::fs::read_to_string(entry.path())?)))
+            .collect::<std::io::Result<_>>()?,
+        ConfigSource::File(path) => vec![(path.to_string_lossy().into_owned(), std::fs::read_to_string(path)?)],
+        ConfigSource::Stdin => {
+            let mut source = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut source)?;
+            vec![("<stdin>".to_owned(), source)]
+        }
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(timeout_build);
+    lua.set_hook(HookTriggers::new().every_nth_instruction(1 << 14), move |_, _| {
+        if Instant::now() >= deadline {
+            Err(mlua::Error::RuntimeError(TIMEOUT_SENTINEL.to_owned()))
+        } else {
+            Ok(())
+        }
+    });
+
+    let exec_result = (|| -> AppResult<()> {
+        for (name, text) in &sources {
+            log::trace!("evaluating Lua config source {name}");
+            lua.load(text).set_name(name).exec()?;
+        }
+        Ok(())
+    })();
+
+    // The deadline only guards this function's own `exec()` calls. A `PreLaunchHook`
+    // reuses this same `Lua` instance much later, at mount time — leaving the hook
+    // installed would hand it a deadline that already expired by then.
+    lua.remove_hook();
+
+    if let Err(e) = exec_result {
+        return Err(timeout_or(e));
+    }
+
+    let modcrab: Table = lua.globals().get("modcrab")?;
+    let mut target = read_target(&modcrab)?;
+    let mut nexus_key: Option<String> = modcrab.get("nexus_key")?;
+    let mut active_profile: Option<String> = modcrab.get("active_profile")?;
+    let mut specs = read_mod_specs(&modcrab)?;
+    let shadow_passthrough = read_shadow_passthrough(&modcrab)?;
+    let overwrite_rules = read_overwrite_rules(&modcrab)?;
+    let pre_launch_key = read_pre_launch_key(&lua, &modcrab)?;
+    let mut meta = read_meta(&modcrab)?;
+    drop(modcrab);
+
+    if let ConfigSource::Dir(config_dir) = &source {
+        for parsed in read_toml_configs(config_dir)? {
+            if let Some(toml_target) = parsed.target {
+                target = Some(toml_target);
+            }
+            if parsed.nexus_key.is_some() {
+                nexus_key = parsed.nexus_key;
+            }
+            if parsed.active_profile.is_some() {
+                active_profile = parsed.active_profile;
+            }
+            if !parsed.meta.is_empty() {
+                meta = parsed.meta;
+            }
+            specs.extend(parsed.specs);
+        }
+    }
+
+    if specs.is_empty() {
+        Notice::new(NoticePreset::Warning, "no mods were declared in config/").print();
+    }
+
+    if nexus_key.is_none() {
+        nexus_key = global_config.nexus_key;
+    }
+
+    let pre_launch = pre_launch_key.map(|key| Rc::new(PreLaunchHook { lua, key }));
+
+    Ok(AppConfig { target, nexus_key, specs, pre_launch, shadow_passthrough, overwrite_rules, active_profile, timeout_build: Some(timeout_build), meta })
+}
+
+/// Turns the sentinel [`mlua::Error::RuntimeError`] the deadline hook raises into a
+/// [`Notice`]-based [`AppError`] explaining what happened, leaving every other error
+/// untouched.
+fn timeout_or(e: AppError) -> AppError {
+    let is_timeout = match &e {
+        AppError::Lua(mlua::Error::RuntimeError(msg)) => msg.contains(TIMEOUT_SENTINEL),
+        AppError::Lua(mlua::Error::CallbackError { cause, .. }) => cause.to_string().contains(TIMEOUT_SENTINEL),
+        _ => false,
+    };
+
+    if is_timeout {
+        Notice::new(NoticePreset::Error, "config evaluation aborted: build timeout exceeded")
+            .field("likely cause", "an infinite loop or runaway recursion in config/*.lua")
+            .field("configured via", "timeout_build in the global config (config.toml)")
+            .into()
+    } else {
+        e
+    }
+}
+
+/// A `modcrab.pre_launch = function(root_path) ... end` callback, registered against
+/// the `Lua` instance it was declared in. A `RegistryKey` isn't valid against any other
+/// instance, so both travel together.
+#[derive(Debug)]
+pub struct PreLaunchHook {
+    lua: Lua,
+    key: RegistryKey,
+}
+
+impl PreLaunchHook {
+    /// Calls the registered function with `root_path` (as a string) as its sole
+    /// argument. An explicit `false` return aborts the mount; anything else (including
+    /// no return value) is treated as `true`.
+    pub fn run(&self, root_path: &Path) -> AppResult<bool> {
+        let func: Function = self.lua.registry_value(&self.key)?;
+        let result: Value = func.call(root_path.to_string_lossy().into_owned())?;
+        Ok(!matches!(result, Value::Boolean(false)))
+    }
+}
+
+fn read_pre_launch_key(lua: &Lua, modcrab: &Table) -> AppResult<Option<RegistryKey>> {
+    match modcrab.get("pre_launch")? {
+        Value::Function(f) => Ok(Some(lua.create_registry_value(f)?)),
+        _ => Ok(None),
+    }
+}
+
+fn read_target(modcrab: &Table) -> AppResult<Option<TargetGame>> {
+    let name: Option<String> = modcrab.get("target")?;
+    let Some(name) = name else { return Ok(None) };
+
+    let specs = generate_default_game_specs();
+    let spec = specs.into_iter().find(|s| s.name.to_lowercase() == name.to_lowercase());
+    let root_path: Option<String> = modcrab.get("root_path")?;
+
+    Ok(spec.map(|spec| TargetGame { spec, root_path: root_path.map(PathBuf::from), data_path: None, mods_path: None, store: None }))
+}
+
+/// Collects every file under `config_dir` with extension `extension`, in natural
+/// filename order. Skips any subdirectory whose name starts with `_` (e.g.
+/// `config/_disabled_graphics/`) entirely, the convention for disabling a whole group
+/// of config files without deleting them. `min_depth(1)` skips `config_dir` itself,
+/// which the extension filter would otherwise need to exclude on its own.
+fn scan_config_files(config_dir: &Path, extension: &str) -> Vec<walkdir::DirEntry> {
+    let mut files: Vec<_> = WalkDir::new(config_dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| !is_disabled_dir(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some(extension))
+        .collect();
+    files.sort_by(|a, b| natord::compare(&a.file_name().to_string_lossy(), &b.file_name().to_string_lossy()));
+    files
+}
+
+fn is_disabled_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.file_type().is_dir() && entry.file_name().to_str().is_some_and(|n| n.starts_with('_'))
+}
+
+/// Every `config/*.lua` and `config/*.toml` file [`eval_config`] would load, in the
+/// order it loads them: every `.lua` file (natural sort) first, since they all run
+/// before any `.toml` file is parsed (see [`eval_config`]'s own ordering), then every
+/// `.toml` file (natural sort). For `modcrab config --list`, debugging load order in
+/// a modpack with many config files spread across subdirectories.
+pub fn config_file_order(config_dir: &Path) -> Vec<PathBuf> {
+    scan_config_files(config_dir, "lua").into_iter().chain(scan_config_files(config_dir, "toml")).map(|e| e.into_path()).collect()
+}
+
+/// What one `config/*.toml` file contributes to the [`AppConfig`] being assembled.
+struct TomlFileConfig {
+    target: Option<TargetGame>,
+    nexus_key: Option<String>,
+    active_profile: Option<String>,
+    meta: ModMeta,
+    specs: Vec<ModSpec>,
+}
+
+/// Scans `config/*.toml` (alongside the `*.lua` files `eval_config` already loaded)
+/// and parses each into a [`TomlFileConfig`], in filename order, so a modpack can mix
+/// both formats freely.
+fn read_toml_configs(config_dir: &Path) -> AppResult<Vec<TomlFileConfig>> {
+    let files = scan_config_files(config_dir, "toml");
+
+    let specs = generate_default_game_specs();
+    let mut results = Vec::new();
+    for entry in &files {
+        let text = std::fs::read_to_string(entry.path())?;
+        let parsed = toml_config::parse(entry.path(), &text)?;
+
+        let target = parsed.modcrab.target.and_then(|name| {
+            specs.iter().find(|s| s.name.to_lowercase() == name.to_lowercase()).cloned()
+        }).map(|spec| TargetGame { spec, root_path: None, data_path: None, mods_path: None, store: None });
+
+        let mod_specs: Vec<ModSpec> = parsed.mods.into_iter().map(ModSpec::from).collect();
+        for spec in &mod_specs {
+            if spec.id.is_some() {
+                spec.source()?;
+            }
+        }
+        results.push(TomlFileConfig {
+            target,
+            nexus_key: parsed.modcrab.nexus_key,
+            active_profile: parsed.modcrab.active_profile,
+            meta: parsed.modcrab.meta,
+            specs: mod_specs,
+        });
+    }
+    Ok(results)
+}
+
+/// Reads `modcrab.shadow_passthrough`, the list of path components (e.g.
+/// `{"Crash Dumps", "Photos"}`) that mkdir/create/symlink/link should write straight
+/// into the shadowed game root instead of `overwrite/`.
+fn read_shadow_passthrough(modcrab: &Table) -> AppResult<Vec<String>> {
+    let list: Option<Vec<String>> = modcrab.get("shadow_passthrough")?;
+    Ok(list.unwrap_or_default())
+}
+
+/// Reads `modcrab.overwrite_rules = { {"skse/plugins/*.log", "logs"}, ... }`: a list of
+/// `{pattern, bucket}` pairs, each a plain two-element array table. Glob validity isn't
+/// checked here — that's [`crate::modpack::validate_overwrite_rules`]'s job once the
+/// full config (Lua plus any TOML mods) has been assembled.
+fn read_overwrite_rules(modcrab: &Table) -> AppResult<Vec<OverwriteRule>> {
+    let list: Option<Vec<Table>> = modcrab.get("overwrite_rules")?;
+    list.unwrap_or_default()
+        .into_iter()
+        .map(|rule| {
+            let pattern: String = rule.get(1)?;
+            let bucket: String = rule.get(2)?;
+            Ok(OverwriteRule { pattern, bucket })
+        })
+        .collect()
+}
+
+/// Reads `modcrab.meta = { name = "...", version = "...", author = "...", description =
+/// "...", url = "..." }`, every field optional. Absent entirely when `modcrab.meta` was
+/// never set.
+fn read_meta(modcrab: &Table) -> AppResult<ModMeta> {
+    let Value::Table(meta) = modcrab.get("meta")? else { return Ok(ModMeta::default()) };
+    Ok(ModMeta {
+        name: meta.get("name")?,
+        version: meta.get("version")?,
+        author: meta.get("author")?,
+        description: meta.get("description")?,
+        url: meta.get("url")?,
+    })
+}
+
+fn read_mod_specs(modcrab: &Table) -> AppResult<Vec<ModSpec>> {
+    let mods: Table = modcrab.get("mods")?;
+    let mut specs = Vec::new();
+    for pair in mods.sequence_values::<Table>() {
+        specs.push(table_to_mod_spec(pair?)?);
+    }
+    Ok(specs)
+}
+
+fn table_to_mod_spec(table: Table) -> AppResult<ModSpec> {
+    let name: String = table.get("name")?;
+    let mut spec = ModSpec::new(name);
+
+    if let Value::Boolean(b) = table.get("is_enabled")? {
+        spec.is_enabled = b;
+    }
+    if let Value::Boolean(b) = table.get("is_root")? {
+        spec.is_root = b;
+    }
+    if let Value::Integer(i) = table.get("priority")? {
+        spec.priority = i as i32;
+    }
+    spec.dependencies = string_array(&table, "dependencies")?;
+    spec.optional_dependencies = string_array(&table, "optional_dependencies")?;
+    spec.after = string_array(&table, "after")?;
+    spec.before = string_array(&table, "before")?;
+    spec.profiles = string_array(&table, "profiles")?;
+    spec.hide = string_array(&table, "hide")?;
+    spec.exclude = string_array(&table, "exclude")?;
+    spec.id = table.get("id")?;
+    spec.group = table.get("group")?;
+    spec.pin = table.get("pin")?;
+    if spec.id.is_some() {
+        spec.source()?;
+    }
+
+    Ok(spec)
+}
+
+fn string_array(table: &Table, key: &str) -> AppResult<Vec<String>> {
+    let value: Value = table.get(key)?;
+    let Value::Table(t) = value else { return Ok(Vec::new()) };
+    let mut out = Vec::new();
+    for v in t.sequence_values::<String>() {
+        out.push(v?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_merges_defaults_with_per_mod_overrides() {
+        let dir = std::env::temp_dir().join(format!("modcrab-template-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"
+                local texture_mod = modcrab.template({ priority = 20, group = "Textures" })
+                table.insert(modcrab.mods, texture_mod({ name = "Noble Skyrim" }))
+                table.insert(modcrab.mods, texture_mod({ name = "Skyrim 2020", priority = 25 }))
+            "#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.specs.len(), 2);
+        assert_eq!(config.specs[0].name, "Noble Skyrim");
+        assert_eq!(config.specs[0].priority, 20);
+        assert_eq!(config.specs[0].group, Some("Textures".to_owned()));
+        assert_eq!(config.specs[1].priority, 25);
+        assert_eq!(config.specs[1].group, Some("Textures".to_owned()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pin_parses_as_a_plain_version_string() {
+        let dir = std::env::temp_dir().join(format!("modcrab-pin-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"
+                table.insert(modcrab.mods, { name = "USSEP", pin = "4.3.1" })
+                table.insert(modcrab.mods, { name = "Unpinned" })
+            "#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.specs[0].pin, Some("4.3.1".to_owned()));
+        assert_eq!(config.specs[1].pin, None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn files_load_in_natural_order_not_lexicographic_order() {
+        let dir = std::env::temp_dir().join(format!("modcrab-natural-order-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for n in 1..=11 {
+            std::fs::write(dir.join(format!("{n}.lua")), format!(r#"table.insert(modcrab.mods, {{ name = "{n}" }})"#)).unwrap();
+        }
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        let names: Vec<&str> = config.specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shadow_passthrough_parses_as_a_list_of_strings_and_defaults_to_empty() {
+        let dir = std::env::temp_dir().join(format!("modcrab-shadow-passthrough-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.lua"), r#"modcrab.shadow_passthrough = { "Crash Dumps", "Photos" }"#).unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.shadow_passthrough, vec!["Crash Dumps".to_owned(), "Photos".to_owned()]);
+
+        std::fs::write(dir.join("a.lua"), "").unwrap();
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert!(config.shadow_passthrough.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn overwrite_rules_parses_as_a_list_of_pattern_bucket_pairs_and_defaults_to_empty() {
+        let dir = std::env::temp_dir().join(format!("modcrab-overwrite-rules-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"modcrab.overwrite_rules = { { "skse/plugins/*.log", "logs" }, { "meshes/actors/**", "generated/facegen" } }"#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(
+            config.overwrite_rules,
+            vec![
+                OverwriteRule { pattern: "skse/plugins/*.log".to_owned(), bucket: "logs".to_owned() },
+                OverwriteRule { pattern: "meshes/actors/**".to_owned(), bucket: "generated/facegen".to_owned() },
+            ]
+        );
+
+        std::fs::write(dir.join("a.lua"), "").unwrap();
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert!(config.overwrite_rules.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn meta_parses_every_declared_field_and_defaults_to_empty() {
+        let dir = std::env::temp_dir().join(format!("modcrab-meta-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"modcrab.meta = { name = "Aurora", version = "0.9", author = "me", description = "a pack", url = "https://example.com" }"#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.meta.name.as_deref(), Some("Aurora"));
+        assert_eq!(config.meta.version.as_deref(), Some("0.9"));
+        assert_eq!(config.meta.author.as_deref(), Some("me"));
+        assert_eq!(config.meta.description.as_deref(), Some("a pack"));
+        assert_eq!(config.meta.url.as_deref(), Some("https://example.com"));
+
+        std::fs::write(dir.join("a.lua"), "").unwrap();
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert!(config.meta.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pre_launch_hook_runs_registered_function_and_respects_false_return() {
+        let dir = std::env::temp_dir().join(format!("modcrab-pre-launch-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.lua"), "modcrab.pre_launch = function(root) return root ~= '' end").unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        let hook = config.pre_launch.expect("pre_launch hook should be registered");
+        assert!(hook.run(Path::new("/game")).unwrap());
+        assert!(!hook.run(Path::new("")).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lua_and_toml_configs_produce_equivalent_mod_specs() {
+        let lua_dir = std::env::temp_dir().join(format!("modcrab-lua-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&lua_dir).unwrap();
+        std::fs::write(
+            lua_dir.join("a.lua"),
+            r#"
+                modcrab.target = "Skyrim Special Edition"
+                modcrab.nexus_key = "abc123"
+                table.insert(modcrab.mods, { name = "USSEP", priority = 10 })
+                table.insert(modcrab.mods, { name = "Disabled Mod", is_enabled = false })
+            "#,
+        )
+        .unwrap();
+        let from_lua = eval_config(&lua_dir, &GlobalConfigPath::default()).unwrap();
+
+        let toml_dir = std::env::temp_dir().join(format!("modcrab-toml-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&toml_dir).unwrap();
+        std::fs::write(
+            toml_dir.join("a.toml"),
+            r#"
+                [modcrab]
+                target = "Skyrim Special Edition"
+                nexus_key = "abc123"
+
+                [[mods]]
+                name = "USSEP"
+                priority = 10
+
+                [[mods]]
+                name = "Disabled Mod"
+                is_enabled = false
+            "#,
+        )
+        .unwrap();
+        let from_toml = eval_config(&toml_dir, &GlobalConfigPath::default()).unwrap();
+
+        assert_eq!(from_lua.target.unwrap().spec.name, from_toml.target.unwrap().spec.name);
+        assert_eq!(from_lua.nexus_key, from_toml.nexus_key);
+        assert_eq!(from_lua.specs.len(), from_toml.specs.len());
+        for (a, b) in from_lua.specs.iter().zip(from_toml.specs.iter()) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.is_enabled, b.is_enabled);
+            assert_eq!(a.priority, b.priority);
+        }
+
+        std::fs::remove_dir_all(&lua_dir).unwrap();
+        std::fs::remove_dir_all(&toml_dir).unwrap();
+    }
+
+    #[test]
+    fn timeout_build_defaults_to_30_seconds_when_unset() {
+        let dir = std::env::temp_dir().join(format!("modcrab-timeout-default-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.lua"), r#"table.insert(modcrab.mods, { name = "Quick" })"#).unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.timeout_build, Some(DEFAULT_TIMEOUT_BUILD_SECS));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_runaway_loop_is_aborted_once_the_global_timeout_elapses() {
+        let dir = std::env::temp_dir().join(format!("modcrab-timeout-runaway-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.lua"), "while true do end").unwrap();
+
+        let global_config_path = dir.join("global-config.toml");
+        std::fs::write(&global_config_path, "timeout_build = 1\n").unwrap();
+
+        let err = eval_config(&dir, &GlobalConfigPath(global_config_path)).unwrap_err();
+        assert!(matches!(err, AppError::Custom(_)), "expected a friendly timeout notice, got {err:?}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn active_profile_and_per_mod_profiles_parse_from_lua() {
+        let dir = std::env::temp_dir().join(format!("modcrab-active-profile-parse-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"
+                modcrab.active_profile = "gameplay"
+                table.insert(modcrab.mods, { name = "Gameplay Mod", profiles = { "gameplay", "hardcore" } })
+            "#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.active_profile.as_deref(), Some("gameplay"));
+        assert_eq!(config.specs[0].profiles, vec!["gameplay".to_owned(), "hardcore".to_owned()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hide_patterns_parse_from_lua() {
+        let dir = std::env::temp_dir().join(format!("modcrab-hide-parse-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"table.insert(modcrab.mods, { name = "Patch", hide = { "*.esp", "readme.txt" } })"#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.specs[0].hide, vec!["*.esp".to_owned(), "readme.txt".to_owned()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exclude_patterns_parse_from_lua_alongside_hide() {
+        let dir = std::env::temp_dir().join(format!("modcrab-exclude-parse-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a.lua"),
+            r#"table.insert(modcrab.mods, { name = "Patch", hide = { "*.esp" }, exclude = { "readme.txt", "docs/" } })"#,
+        )
+        .unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        assert_eq!(config.specs[0].hide, vec!["*.esp".to_owned()]);
+        assert_eq!(config.specs[0].exclude, vec!["readme.txt".to_owned(), "docs/".to_owned()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lua_and_toml_files_coexist_in_the_same_config_dir() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mixed-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.lua"), r#"table.insert(modcrab.mods, { name = "FromLua" })"#).unwrap();
+        std::fs::write(dir.join("b.toml"), "[[mods]]\nname = \"FromToml\"\n").unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        let names: Vec<_> = config.specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["FromLua", "FromToml"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn eval_config_source_reads_a_single_file_with_no_toml_sibling_scan() {
+        let dir = std::env::temp_dir().join(format!("modcrab-single-file-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("snippet.lua");
+        std::fs::write(&file, r#"table.insert(modcrab.mods, { name = "FromSnippet" })"#).unwrap();
+        // A stray .toml alongside the snippet must be ignored in this mode — only the
+        // one named file is read.
+        std::fs::write(dir.join("ignored.toml"), "[[mods]]\nname = \"ShouldNotAppear\"\n").unwrap();
+
+        let config = eval_config_source(ConfigSource::File(file), &GlobalConfigPath::default()).unwrap();
+        let names: Vec<_> = config.specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["FromSnippet"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_subdirectory_prefixed_with_underscore_is_skipped_entirely() {
+        let dir = std::env::temp_dir().join(format!("modcrab-disabled-subdir-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("graphics")).unwrap();
+        std::fs::create_dir_all(dir.join("_disabled_gameplay")).unwrap();
+        std::fs::write(dir.join("graphics").join("a.lua"), r#"table.insert(modcrab.mods, { name = "Texture Mod" })"#).unwrap();
+        std::fs::write(dir.join("_disabled_gameplay").join("b.lua"), r#"table.insert(modcrab.mods, { name = "Disabled Mod" })"#).unwrap();
+
+        let config = eval_config(&dir, &GlobalConfigPath::default()).unwrap();
+        let names: Vec<_> = config.specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Texture Mod"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn config_file_order_lists_every_lua_file_before_any_toml_file() {
+        let dir = std::env::temp_dir().join(format!("modcrab-config-order-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("gameplay")).unwrap();
+        std::fs::create_dir_all(dir.join("_disabled_graphics")).unwrap();
+        std::fs::write(dir.join("gameplay").join("10_patches.lua"), "").unwrap();
+        std::fs::write(dir.join("9_base.lua"), "").unwrap();
+        std::fs::write(dir.join("overrides.toml"), "").unwrap();
+        std::fs::write(dir.join("_disabled_graphics").join("textures.lua"), "").unwrap();
+
+        let files = config_file_order(&dir);
+        let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!["9_base.lua", "10_patches.lua", "overrides.toml"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
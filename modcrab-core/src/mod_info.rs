@@ -0,0 +1,267 @@
+//! Implements `modcrab mod info <name>`: everything modcrab knows about a single mod
+//! in one view — its spec fields, installed state, how its files fare in the current
+//! load order, and its Nexus link if it has an id.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::modpack::{self, PackLayout};
+use crate::structs::error::AppResult;
+use crate::structs::spec::{ModSource, ModSpec};
+use crate::util::misc::closest_match;
+use crate::util::names;
+use crate::util::notice::{Notice, NoticePreset};
+
+/// Everything gathered about one mod. Serializable for `--json`.
+#[derive(Debug, Serialize)]
+pub struct ModInfo {
+    pub name: String,
+    pub is_enabled: bool,
+    pub is_root: bool,
+    pub priority: i32,
+    pub dependencies: Vec<String>,
+    pub optional_dependencies: Vec<String>,
+    pub after: Vec<String>,
+    pub before: Vec<String>,
+    pub nexus_id: Option<String>,
+    pub nexus_url: Option<String>,
+    pub pin: Option<String>,
+    pub installed_path: Option<String>,
+    pub installed_size_bytes: Option<u64>,
+    pub installed_file_count: Option<usize>,
+    /// How many of this mod's files are the version left standing after the rest of
+    /// the enabled load order is applied. `None` for root mods and disabled mods,
+    /// since neither attaches as an ordered overlay layer.
+    pub files_winning: Option<usize>,
+    pub files_losing: Option<usize>,
+    /// How many of this mod's own installed files are left out of its overlay layer
+    /// by its own [`ModSpec::hide`] patterns. `None` when it isn't installed at all;
+    /// `Some(0)` when it's installed but hides nothing.
+    pub files_hidden: Option<usize>,
+    /// The most recent bulk override (`enable`/`disable`/`set-priority`) currently in
+    /// effect for this mod, if any.
+    pub override_applied: Option<String>,
+}
+
+impl ModInfo {
+    pub fn notice(&self) -> Notice {
+        let mut notice = Notice::new(NoticePreset::Info, format!("mod: {}", self.name))
+            .field("enabled", self.is_enabled.to_string())
+            .field("root mod", self.is_root.to_string())
+            .field("priority", self.priority.to_string());
+
+        if !self.dependencies.is_empty() {
+            notice = notice.field("dependencies", self.dependencies.join(", "));
+        }
+        if !self.optional_dependencies.is_empty() {
+            notice = notice.field("optional dependencies", self.optional_dependencies.join(", "));
+        }
+        if !self.after.is_empty() {
+            notice = notice.field("after", self.after.join(", "));
+        }
+        if !self.before.is_empty() {
+            notice = notice.field("before", self.before.join(", "));
+        }
+        if let Some(path) = &self.installed_path {
+            notice = notice.field("installed at", path.clone());
+            notice = notice.field("size", format!("{} bytes", self.installed_size_bytes.unwrap_or(0)));
+            notice = notice.field("files", self.installed_file_count.unwrap_or(0).to_string());
+        } else {
+            notice = notice.field("installed", "not found on disk");
+        }
+        if let (Some(winning), Some(losing)) = (self.files_winning, self.files_losing) {
+            notice = notice.field("files winning conflicts", winning.to_string());
+            notice = notice.field("files losing conflicts", losing.to_string());
+        }
+        if let Some(hidden) = self.files_hidden.filter(|&n| n > 0) {
+            notice = notice.field("files hidden by its own hide list", hidden.to_string());
+        }
+        if let Some(url) = &self.nexus_url {
+            notice = notice.field("nexus", url.clone());
+        } else if let Some(id) = &self.nexus_id {
+            notice = notice.field("nexus id", id.clone());
+        }
+        if let Some(applied) = &self.override_applied {
+            notice = notice.field("override applied", applied.clone());
+        }
+        if let Some(pin) = &self.pin {
+            notice = notice.field("pinned to", pin.clone());
+        }
+
+        notice
+    }
+}
+
+/// Finds `name` (case-insensitive) among the modpack's declared mods and gathers its
+/// full detail view. Works before the first `modcrab build` by evaluating the config
+/// directly; conflict counts are only available once the mod list can be sorted
+/// (i.e. the config declares a target game).
+pub fn gather(layout: &PackLayout, name: &str) -> AppResult<ModInfo> {
+    let config = crate::lua::eval_config(&layout.config, &crate::global_config::GlobalConfigPath::default())?;
+    let mut specs = config.specs;
+    let rules = crate::overrides::load(layout)?;
+    let last_rule = crate::overrides::apply_tracking(&rules, &mut specs, Some(name));
+
+    let spec = specs
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| not_found(name, specs.iter().map(|s| s.name.as_str())))?;
+
+    let mod_dir = layout.mods.join(&spec.name);
+    let (installed_size_bytes, installed_file_count) = dir_stats(&mod_dir);
+    let installed_path = mod_dir.is_dir().then(|| mod_dir.display().to_string());
+
+    let target_domain = config.target.as_ref().and_then(|t| t.spec.nexus_domain.clone());
+    let nexus_url = match spec.source()? {
+        ModSource::NexusMod { mod_id, domain, .. } => domain.or(target_domain).map(|domain| format!("https://www.nexusmods.com/{domain}/mods/{mod_id}")),
+        ModSource::Url(_) | ModSource::Local => None,
+    };
+
+    let (files_winning, files_losing) = if spec.is_enabled && !spec.is_root {
+        count_conflicts(layout, &specs, &spec.name)
+    } else {
+        (None, None)
+    };
+
+    let files_hidden = installed_path.is_some().then(|| modpack::relative_files(&mod_dir).filter(|r| spec.hides(r)).count());
+
+    Ok(ModInfo {
+        name: spec.name,
+        is_enabled: spec.is_enabled,
+        is_root: spec.is_root,
+        priority: spec.priority,
+        dependencies: spec.dependencies,
+        optional_dependencies: spec.optional_dependencies,
+        after: spec.after,
+        before: spec.before,
+        nexus_id: spec.id,
+        nexus_url,
+        pin: spec.pin,
+        installed_path,
+        installed_size_bytes,
+        installed_file_count,
+        files_winning,
+        files_losing,
+        files_hidden,
+        override_applied: last_rule.map(|r| format!("{} ({})", r.action.describe(), r.selector.describe())),
+    })
+}
+
+fn not_found<'a>(name: &str, declared: impl Iterator<Item = &'a str>) -> crate::structs::error::AppError {
+    let mut notice = Notice::new(NoticePreset::Error, format!("no mod named '{name}' is declared in this modpack"));
+    if let Some(suggestion) = closest_match(name, declared) {
+        notice = notice.field("did you mean", suggestion.to_owned());
+    }
+    notice.into()
+}
+
+/// Sorts every enabled, non-root mod into load order and reports how `target`'s own
+/// files split between winning (the last, highest-priority copy of a path) and losing
+/// (shadowed by a later mod), via [`modpack::file_contribution_stats`].
+fn count_conflicts(layout: &PackLayout, specs: &[ModSpec], target: &str) -> (Option<usize>, Option<usize>) {
+    let mods: indexmap::IndexMap<String, ModSpec> =
+        specs.iter().filter(|s| s.is_enabled && !s.is_root).map(|s| (names::key(&s.name), s.clone())).collect();
+    let Ok(ordered) = modpack::sort_mod_list(&mods) else { return (None, None) };
+
+    let stats = modpack::file_contribution_stats(&layout.mods, ordered.iter());
+    match stats.get(target) {
+        Some(&(winning, losing)) => (Some(winning), Some(losing)),
+        None => (None, None),
+    }
+}
+
+/// Returns `(total size in bytes, file count)` for everything under `dir`, or
+/// `(None, None)` if it doesn't exist.
+fn dir_stats(dir: &Path) -> (Option<u64>, Option<usize>) {
+    if !dir.is_dir() {
+        return (None, None);
+    }
+    let mut size = 0u64;
+    let mut count = 0usize;
+    for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+        if entry.file_type().is_file() {
+            size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            count += 1;
+        }
+    }
+    (Some(size), Some(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_mod_file(mods_dir: &Path, mod_name: &str, relative: &str) {
+        let path = mods_dir.join(mod_name).join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"x").unwrap();
+    }
+
+    #[test]
+    fn gather_reports_conflicts_and_install_stats() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mod-info-test-{}", std::process::id()));
+        modpack::init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+
+        write_mod_file(&layout.mods, "Base", "shared.esp");
+        write_mod_file(&layout.mods, "Patch", "shared.esp");
+        std::fs::write(
+            layout.config.join("a.lua"),
+            r#"
+                modcrab.target = "Skyrim Special Edition"
+                table.insert(modcrab.mods, { name = "Base", priority = 0 })
+                table.insert(modcrab.mods, { name = "Patch", priority = 10 })
+            "#,
+        )
+        .unwrap();
+
+        let base = gather(&layout, "base").unwrap();
+        assert_eq!(base.installed_file_count, Some(1));
+        assert_eq!(base.files_winning, Some(0));
+        assert_eq!(base.files_losing, Some(1));
+
+        let patch = gather(&layout, "Patch").unwrap();
+        assert_eq!(patch.files_winning, Some(1));
+        assert_eq!(patch.files_losing, Some(0));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gather_reports_files_hidden_by_the_mod_s_own_hide_list() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mod-info-hide-test-{}", std::process::id()));
+        modpack::init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+
+        write_mod_file(&layout.mods, "Patch", "shared.esp");
+        write_mod_file(&layout.mods, "Patch", "readme.txt");
+        std::fs::write(
+            layout.config.join("a.lua"),
+            r#"
+                modcrab.target = "Skyrim Special Edition"
+                table.insert(modcrab.mods, { name = "Patch", hide = { "*.txt" } })
+            "#,
+        )
+        .unwrap();
+
+        let patch = gather(&layout, "Patch").unwrap();
+        assert_eq!(patch.files_hidden, Some(1));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gather_suggests_a_close_name_when_not_found() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mod-info-missing-test-{}", std::process::id()));
+        modpack::init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+        std::fs::write(layout.config.join("a.lua"), r#"table.insert(modcrab.mods, { name = "USSEP" })"#).unwrap();
+
+        let err = gather(&layout, "ussep typo").unwrap_err();
+        assert!(err.to_string().contains("no mod named"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
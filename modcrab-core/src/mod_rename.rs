@@ -0,0 +1,220 @@
+//! Implements `modcrab mod rename <old> <new>`: renames a mod's directory on disk,
+//! updates the persisted override/build state that references it by name, and reports
+//! (without touching) the `config/*.lua` lines that still need manual editing.
+
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use walkdir::WalkDir;
+
+use crate::modpack::{self, PackLayout};
+use crate::overrides::{self, Selector};
+use crate::structs::data::AppData;
+use crate::structs::error::AppResult;
+use crate::structs::spec::ModSpec;
+use crate::util::names;
+use crate::util::notice::{Notice, NoticePreset};
+
+/// One `config/*.lua` line that still mentions the old name and needs manual editing.
+#[derive(Debug, Clone)]
+pub struct LuaReference {
+    pub path: PathBuf,
+    pub line: usize,
+    pub text: String,
+}
+
+/// Everything `rename` changed or found.
+#[derive(Debug)]
+pub struct RenameReport {
+    pub old: String,
+    pub new: String,
+    pub renamed_dir: bool,
+    pub updated_overrides: usize,
+    pub updated_data: bool,
+    pub lua_references: Vec<LuaReference>,
+}
+
+impl RenameReport {
+    pub fn notice(&self) -> Notice {
+        let mut notice = Notice::new(NoticePreset::Info, format!("renamed '{}' to '{}'", self.old, self.new))
+            .field("directory renamed", self.renamed_dir.to_string())
+            .field("override rules updated", self.updated_overrides.to_string())
+            .field("data.bin updated", self.updated_data.to_string());
+
+        if !self.lua_references.is_empty() {
+            let lines: Vec<String> = self.lua_references.iter().map(|r| format!("{}:{}: {}", r.path.display(), r.line, r.text.trim())).collect();
+            notice = notice.field("still needs manual editing in config/*.lua", lines.join("\n    "));
+        }
+
+        notice
+    }
+}
+
+/// Renames a mod from `old` to `new` (both matched case-insensitively against what's
+/// actually on disk/declared), refusing outright if the modpack is currently mounted.
+pub fn rename(layout: &PackLayout, old: &str, new: &str) -> AppResult<RenameReport> {
+    if modpack::is_mounted(layout) {
+        return Err(Notice::new(NoticePreset::Error, "refusing to rename while the modpack is mounted; unmount first").into());
+    }
+
+    let renamed_dir = rename_mod_dir(layout, old, new)?;
+    let updated_overrides = update_overrides(layout, old, new)?;
+    let updated_data = update_data(layout, old, new)?;
+    let lua_references = find_lua_references(&layout.config, old);
+
+    Ok(RenameReport { old: old.to_owned(), new: new.to_owned(), renamed_dir, updated_overrides, updated_data, lua_references })
+}
+
+/// Renames the mod's directory under `mods/`, hopping through a temp name first so a
+/// case-only rename (`Foo` -> `foo`) isn't a no-op on a case-insensitive filesystem.
+fn rename_mod_dir(layout: &PackLayout, old: &str, new: &str) -> AppResult<bool> {
+    let Some(old_dir) = find_dir_case_insensitive(&layout.mods, old) else { return Ok(false) };
+    let new_dir = layout.mods.join(new);
+    let temp_dir = layout.mods.join(format!(".rename-{new}-{}", std::process::id()));
+
+    std::fs::rename(&old_dir, &temp_dir)?;
+    std::fs::rename(&temp_dir, &new_dir)?;
+    Ok(true)
+}
+
+fn find_dir_case_insensitive(dir: &Path, name: &str) -> Option<PathBuf> {
+    std::fs::read_dir(dir).ok()?.flatten().map(|e| e.path()).find(|p| p.file_name().and_then(|f| f.to_str()).is_some_and(|f| f.eq_ignore_ascii_case(name)))
+}
+
+/// Rewrites any `Selector::Name` override rule that matches `old` to point at `new`.
+/// Glob/group selectors aren't renamed; they don't name a single mod.
+fn update_overrides(layout: &PackLayout, old: &str, new: &str) -> AppResult<usize> {
+    let mut rules = overrides::load(layout)?;
+    let mut updated = 0;
+    for rule in &mut rules {
+        if let Selector::Name(name) = &mut rule.selector {
+            if name.eq_ignore_ascii_case(old) {
+                *name = new.to_owned();
+                updated += 1;
+            }
+        }
+    }
+    if updated > 0 {
+        overrides::save_rules(layout, &rules)?;
+    }
+    Ok(updated)
+}
+
+fn update_data(layout: &PackLayout, old: &str, new: &str) -> AppResult<bool> {
+    let data_path = layout.state.join("data.bin");
+    if !data_path.is_file() {
+        return Ok(false);
+    }
+
+    let mut data = AppData::load(&data_path)?;
+    let mut changed = rename_in_map(&mut data.root_mods, old, new);
+    changed |= rename_in_map(&mut data.mods, old, new);
+    for disabled in &mut data.disabled_mods {
+        if disabled.eq_ignore_ascii_case(old) {
+            *disabled = new.to_owned();
+            changed = true;
+        }
+    }
+
+    if changed {
+        data.save(&data_path)?;
+    }
+    Ok(changed)
+}
+
+/// Renames the matching entry in place, preserving its position so the already-sorted
+/// load order in `data.bin` doesn't shift until the next `modcrab build`.
+fn rename_in_map(map: &mut IndexMap<String, ModSpec>, old: &str, new: &str) -> bool {
+    let Some(key) = map.keys().find(|k| k.eq_ignore_ascii_case(old)).cloned() else { return false };
+    let Some(index) = map.get_index_of(&key) else { return false };
+    let Some(mut spec) = map.shift_remove(&key) else { return false };
+    spec.name = new.to_owned();
+    map.shift_insert(index, names::key(new), spec);
+    true
+}
+
+/// Every `config/*.lua` line that mentions `old`, for the user to edit by hand.
+fn find_lua_references(config_dir: &Path, old: &str) -> Vec<LuaReference> {
+    let needle = old.to_lowercase();
+    let mut refs = Vec::new();
+
+    for entry in WalkDir::new(config_dir).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()) {
+        if entry.path().extension().and_then(|e| e.to_str()) != Some("lua") {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(entry.path()) else { continue };
+        for (idx, line) in text.lines().enumerate() {
+            if line.to_lowercase().contains(&needle) {
+                refs.push(LuaReference { path: entry.path().to_path_buf(), line: idx + 1, text: line.to_owned() });
+            }
+        }
+    }
+
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(label: &str) -> PackLayout {
+        let dir = std::env::temp_dir().join(format!("modcrab-rename-test-{label}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        modpack::init_modpack(&dir).unwrap();
+        PackLayout::resolve(&dir).unwrap()
+    }
+
+    #[test]
+    fn rename_moves_the_directory_and_reports_lua_references() {
+        let layout = setup("moves");
+        std::fs::create_dir_all(layout.mods.join("Foo")).unwrap();
+        std::fs::write(layout.config.join("a.lua"), r#"table.insert(modcrab.mods, { name = "Foo", priority = 5 })"#).unwrap();
+
+        let report = rename(&layout, "Foo", "Foo SE").unwrap();
+        assert!(report.renamed_dir);
+        assert!(layout.mods.join("Foo SE").is_dir());
+        assert!(!layout.mods.join("Foo").is_dir());
+        assert_eq!(report.lua_references.len(), 1);
+        assert!(report.lua_references[0].text.contains("Foo"));
+
+        std::fs::remove_dir_all(&layout.root).unwrap();
+    }
+
+    #[test]
+    fn rename_handles_case_only_renames_on_disk() {
+        let layout = setup("case-only");
+        std::fs::create_dir_all(layout.mods.join("Foo")).unwrap();
+
+        let report = rename(&layout, "Foo", "foo").unwrap();
+        assert!(report.renamed_dir);
+        let entries: Vec<String> = std::fs::read_dir(&layout.mods).unwrap().flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect();
+        assert_eq!(entries, vec!["foo"]);
+
+        std::fs::remove_dir_all(&layout.root).unwrap();
+    }
+
+    #[test]
+    fn rename_refuses_while_mounted() {
+        let layout = setup("mounted");
+        let _lock = modpack::MountLock::acquire(&layout).unwrap();
+
+        let err = rename(&layout, "Foo", "Bar").unwrap_err();
+        assert!(err.to_string().contains("mounted"));
+
+        std::fs::remove_dir_all(&layout.root).unwrap();
+    }
+
+    #[test]
+    fn rename_updates_a_matching_override_rule_name() {
+        let layout = setup("overrides");
+        std::fs::create_dir_all(layout.mods.join("Foo")).unwrap();
+        overrides::append(&layout, overrides::OverrideRule { selector: Selector::Name("Foo".to_owned()), action: overrides::Action::SetEnabled(false) }).unwrap();
+
+        let report = rename(&layout, "Foo", "Bar").unwrap();
+        assert_eq!(report.updated_overrides, 1);
+        let rules = overrides::load(&layout).unwrap();
+        assert_eq!(rules[0].selector, Selector::Name("Bar".to_owned()));
+
+        std::fs::remove_dir_all(&layout.root).unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+//! Resolves where a modpack's directories actually live on disk, honoring any overrides
+//! set in `.modcrab/settings.toml`. `.modcrab/` itself can't be relocated — it's where
+//! the settings file and built state live, and everything else is resolved relative to
+//! the pack root unless overridden.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::structs::error::AppResult;
+use crate::util::misc::replace_path_home_prefix;
+use crate::util::notice::{Notice, NoticePreset};
+
+pub const MODS_DIR: &str = "mods";
+pub const OVERWRITE_DIR: &str = "overwrite";
+pub const DOWNLOADS_DIR: &str = "downloads";
+pub const CONFIG_DIR: &str = "config";
+pub const STATE_DIR: &str = ".modcrab";
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// The resolved on-disk layout for one modpack. Built once per command via
+/// [`PackLayout::resolve`] and threaded through everything that needs a path.
+#[derive(Debug, Clone)]
+pub struct PackLayout {
+    pub root: PathBuf,
+    pub mods: PathBuf,
+    pub overwrite: PathBuf,
+    pub downloads: PathBuf,
+    pub config: PathBuf,
+    pub state: PathBuf,
+}
+
+/// Directory overrides read from `.modcrab/settings.toml`. Any field left unset keeps
+/// its default, pack-root-relative location. May be an absolute path (e.g. mods kept on
+/// another filesystem) or `~`-prefixed.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PackLayoutSettings {
+    mods_dir: Option<String>,
+    overwrite_dir: Option<String>,
+    downloads_dir: Option<String>,
+    config_dir: Option<String>,
+}
+
+impl PackLayout {
+    /// Resolves the layout for a modpack rooted at `pack_root`, applying any overrides
+    /// from `.modcrab/settings.toml` if that file exists.
+    pub fn resolve(pack_root: &Path) -> AppResult<Self> {
+        let state = pack_root.join(STATE_DIR);
+        let settings = read_settings(&state)?;
+
+        let resolve_dir = |default: &str, overridden: &Option<String>| match overridden {
+            Some(raw) => PathBuf::from(replace_path_home_prefix(raw)),
+            None => pack_root.join(default),
+        };
+
+        Ok(Self {
+            mods: resolve_dir(MODS_DIR, &settings.mods_dir),
+            overwrite: resolve_dir(OVERWRITE_DIR, &settings.overwrite_dir),
+            downloads: resolve_dir(DOWNLOADS_DIR, &settings.downloads_dir),
+            config: resolve_dir(CONFIG_DIR, &settings.config_dir),
+            root: pack_root.to_path_buf(),
+            state,
+        })
+    }
+
+    /// The default layout for a pack that doesn't exist on disk yet, with no settings
+    /// to read. Used by `init_modpack`, since `.modcrab/settings.toml` can't exist
+    /// before `.modcrab/` itself does.
+    pub fn default_for(pack_root: &Path) -> Self {
+        Self {
+            mods: pack_root.join(MODS_DIR),
+            overwrite: pack_root.join(OVERWRITE_DIR),
+            downloads: pack_root.join(DOWNLOADS_DIR),
+            config: pack_root.join(CONFIG_DIR),
+            state: pack_root.join(STATE_DIR),
+            root: pack_root.to_path_buf(),
+        }
+    }
+
+    /// The required directories and their human-readable names, in the order
+    /// `validate_modpack` checks them.
+    pub fn required_dirs(&self) -> [(&'static str, &Path); 3] {
+        [("mods", &self.mods), ("config", &self.config), ("state", &self.state)]
+    }
+
+    /// Every directory `init_modpack` creates, including the optional ones.
+    pub fn all_dirs(&self) -> [&Path; 5] {
+        [&self.mods, &self.overwrite, &self.downloads, &self.config, &self.state]
+    }
+}
+
+fn read_settings(state_dir: &Path) -> AppResult<PackLayoutSettings> {
+    let path = state_dir.join(SETTINGS_FILE);
+    if !path.is_file() {
+        return Ok(PackLayoutSettings::default());
+    }
+
+    let text = std::fs::read_to_string(&path)?;
+    toml::from_str(&text).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to parse {}: {e}", path.display())).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_falls_back_to_pack_root_relative_defaults_without_settings() {
+        let dir = std::env::temp_dir().join(format!("modcrab-layout-defaults-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(STATE_DIR)).unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        assert_eq!(layout.mods, dir.join(MODS_DIR));
+        assert_eq!(layout.config, dir.join(CONFIG_DIR));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolve_honors_an_absolute_mods_dir_override() {
+        let dir = std::env::temp_dir().join(format!("modcrab-layout-override-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(STATE_DIR)).unwrap();
+        std::fs::write(dir.join(STATE_DIR).join(SETTINGS_FILE), "mods_dir = \"/mnt/big/skyrim-mods\"\n").unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        assert_eq!(layout.mods, PathBuf::from("/mnt/big/skyrim-mods"));
+        assert_eq!(layout.overwrite, dir.join(OVERWRITE_DIR));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
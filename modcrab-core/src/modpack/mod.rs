@@ -0,0 +1,1939 @@
+//! The core modpack lifecycle: init a pack directory, validate and sort its mods, and
+//! build `.modcrab/data.bin`. Mounting the resulting overlay over the game root is
+//! `modcrab-cli`'s job (see its `mount` module), since that's the only place this
+//! workspace talks to `modcrab-fs`.
+
+mod layout;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use indexmap::IndexMap;
+
+pub use layout::{PackLayout, CONFIG_DIR};
+
+use crate::global_config::GlobalConfigPath;
+use crate::lua::{eval_config_source, ConfigSource};
+use crate::structs::data::{AppConfig, AppData, OverwriteRule};
+use crate::structs::error::AppResult;
+use crate::structs::spec::{check_domain_mismatch, hide_pattern_matches, GameSpec, ModSpec};
+use crate::util::names;
+use crate::util::notice::{Notice, NoticePreset};
+
+pub const DATA_FILE: &str = "data.bin";
+pub const MODLIST_FILE: &str = "modlist.txt";
+pub const PLUGINS_FILE: &str = "plugins.txt";
+const MOUNT_LOCK_FILE: &str = "mount.lock";
+
+/// Held for the duration of a mount; removes `.modcrab/mount.lock` on drop. Lets other
+/// commands (like `mod rename`) detect a live mount via [`is_mounted`] and refuse to
+/// run rather than race the mounted overlay's in-memory tree.
+#[derive(Debug)]
+pub struct MountLock {
+    path: PathBuf,
+}
+
+impl MountLock {
+    /// Atomically creates `.modcrab/mount.lock` containing this process's PID and the
+    /// current unix timestamp, refusing if a lock left by another still-running process
+    /// is already there — so a second `modcrab run`/`mount`/`shell` against the same
+    /// pack can't end up mounting a second [`modcrab_fs`]-style overlay over the same
+    /// game directory at once, which would leave the two instances' in-memory trees
+    /// fighting over the same real files. A lock left behind by a process that's no
+    /// longer running (a crash, a `kill -9`) is stale and gets cleared automatically.
+    pub fn acquire(layout: &PackLayout) -> AppResult<Self> {
+        let path = layout.state.join(MOUNT_LOCK_FILE);
+
+        if let Err(e) = Self::create(&path) {
+            if e.kind() != std::io::ErrorKind::AlreadyExists {
+                return Err(e.into());
+            }
+
+            if let Some(pid) = Self::held_by(&path) {
+                if Self::process_is_running(pid) {
+                    return Err(Notice::new(NoticePreset::Error, format!("this modpack is already mounted (by process {pid}); unmount that session first")).into());
+                }
+            }
+
+            std::fs::remove_file(&path)?;
+            Self::create(&path)?;
+        }
+
+        Ok(Self { path })
+    }
+
+    /// Uses `create_new` rather than `write` so two processes racing to acquire the lock
+    /// at the same instant can't both succeed: exactly one `create_new` wins, and the
+    /// other sees `AlreadyExists` instead of silently overwriting the winner's lock.
+    fn create(path: &Path) -> std::io::Result<()> {
+        use std::io::Write;
+        let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut file = std::fs::File::create_new(path)?;
+        file.write_all(format!("{} {timestamp}", std::process::id()).as_bytes())
+    }
+
+    /// The PID recorded in an existing lock file at `path`, if it's readable and starts
+    /// with one.
+    fn held_by(path: &Path) -> Option<u32> {
+        std::fs::read_to_string(path).ok()?.split_whitespace().next()?.parse().ok()
+    }
+
+    fn process_is_running(pid: u32) -> bool {
+        Path::new("/proc").join(pid.to_string()).is_dir()
+    }
+
+    /// The PID of the process currently holding `mount.lock`, if any and if it's still
+    /// actually running. Lets another process (`modcrab unmount`, `modcrab status`)
+    /// address a specific live mount session by the pack path it was started from,
+    /// without the two needing any other form of coordination between them.
+    pub fn mounted_pid(layout: &PackLayout) -> Option<u32> {
+        let path = layout.state.join(MOUNT_LOCK_FILE);
+        Self::held_by(&path).filter(|&pid| Self::process_is_running(pid))
+    }
+}
+
+impl Drop for MountLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Whether this modpack currently appears to be mounted, per [`MountLock::acquire`].
+pub fn is_mounted(layout: &PackLayout) -> bool {
+    layout.state.join(MOUNT_LOCK_FILE).is_file()
+}
+
+/// Bethesda archive formats. The overlay only merges loose files; archives are loaded
+/// by the game engine independently, so a mod shipping one can't be overridden by loose
+/// files from another mod the way a user might expect.
+const ARCHIVE_EXTENSIONS: &[&str] = &["bsa", "ba2"];
+
+/// Creates the standard directory layout for a new modpack rooted at `path`.
+pub fn init_modpack(path: &Path) -> AppResult<()> {
+    for dir in PackLayout::default_for(path).all_dirs() {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Checks that every directory a modpack requires exists, naming exactly the first one
+/// that's missing.
+pub fn validate_modpack(layout: &PackLayout) -> AppResult<()> {
+    for (name, dir) in layout.required_dirs() {
+        if !dir.is_dir() {
+            return Err(Notice::new(NoticePreset::Error, format!("not a modpack: missing '{name}' directory")).field("expected", dir.display().to_string()).into());
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a single mod is installed and structurally sound, including that every
+/// pattern in its [`ModSpec::hide`] or [`ModSpec::exclude`] actually matches something
+/// on disk — a pattern that matches nothing is almost always a typo.
+pub fn validate_mod(mods_dir: &Path, spec: &ModSpec) -> Vec<Notice> {
+    let mut notices = Vec::new();
+    let mod_path = mods_dir.join(&spec.name);
+    if !mod_path.is_dir() {
+        notices.push(Notice::new(NoticePreset::Error, format!("mod '{}' is not installed", spec.name)).field("expected", mod_path.display().to_string()));
+        return notices;
+    }
+
+    for pattern in spec.hide.iter().chain(&spec.exclude) {
+        if !relative_files(&mod_path).any(|relative| hide_pattern_matches(pattern, &relative)) {
+            notices.push(Notice::new(NoticePreset::Warning, format!("'{}' hide pattern '{pattern}' doesn't match any of its files", spec.name)));
+        }
+    }
+
+    notices
+}
+
+/// Scans enabled mods for BSA/BA2 archives and warns about the ones that ship them: the
+/// overlay only merges loose files, so a lower-priority mod's loose files can't override
+/// what's packed into a higher-priority mod's archive the way a user might expect.
+fn detect_archive_mods<'a>(mods_dir: &Path, specs: impl Iterator<Item = &'a ModSpec>) -> Vec<Notice> {
+    let mut notices = Vec::new();
+    for spec in specs {
+        let Ok(entries) = std::fs::read_dir(mods_dir.join(&spec.name)) else { continue };
+        let has_archive = entries.filter_map(Result::ok).any(|entry| {
+            entry.path().extension().and_then(|e| e.to_str()).map(|e| ARCHIVE_EXTENSIONS.contains(&e.to_lowercase().as_str())).unwrap_or(false)
+        });
+        if has_archive {
+            notices.push(Notice::new(NoticePreset::Info, format!("'{}' ships a BSA/BA2 archive; its contents won't be overridden by lower-priority loose files", spec.name)));
+        }
+    }
+    notices
+}
+
+/// Flags plugins of the same filename shipped by two different enabled mods: only one
+/// survives in the overlay, so the other's records simply vanish rather than being
+/// overridden record-by-record the way loose files are. `specs` must already be in load
+/// order — the notice names the mod that loses, i.e. whichever registered the name
+/// first — so it reads the same direction a file conflict would.
+fn detect_plugin_name_conflicts<'a>(mods_dir: &Path, specs: impl Iterator<Item = &'a ModSpec>, extensions: &HashSet<String>) -> Vec<Notice> {
+    let mut owners: HashMap<String, String> = HashMap::new();
+    let mut notices = Vec::new();
+
+    for spec in specs {
+        let Ok(entries) = std::fs::read_dir(mods_dir.join(&spec.name)) else { continue };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let is_plugin = path.extension().and_then(|e| e.to_str()).map(|e| extensions.contains(&e.to_lowercase())).unwrap_or(false);
+            if !is_plugin {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+
+            match owners.insert(name.to_lowercase(), spec.name.clone()) {
+                Some(previous) if !previous.eq_ignore_ascii_case(&spec.name) => {
+                    notices.push(Notice::new(
+                        NoticePreset::Warning,
+                        format!("'{previous}' and '{}' both ship a plugin named '{name}'; only '{}' wins in the overlay and the other's records are lost", spec.name, spec.name),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    notices
+}
+
+/// Whether `spec` is in play under `active_profile`: true if it didn't declare any
+/// `profiles` at all (unaffected by profile switching), or if one of the profiles it
+/// declared matches (case-insensitively, same as [`crate::structs::spec::TargetGame`]
+/// name matching). A mod with `profiles` set but no active profile chosen is dropped,
+/// the same as any other profile mismatch.
+fn is_in_active_profile(spec: &ModSpec, active_profile: Option<&str>) -> bool {
+    if spec.profiles.is_empty() {
+        return true;
+    }
+    let Some(active_profile) = active_profile else { return false };
+    spec.profiles.iter().any(|p| p.eq_ignore_ascii_case(active_profile))
+}
+
+/// The documented range for a mod's `priority` field. A value outside it still sorts
+/// fine — `sort_mod_list` accepts any `i32` — but it usually means a typo, e.g.
+/// `priority = 9999` meant as "load last" when `100` would do.
+const PRIORITY_RANGE: std::ops::RangeInclusive<i32> = 0..=100;
+
+/// Sanity-checks a single spec's own fields, independent of the rest of the mod list:
+/// that `priority` falls inside [`PRIORITY_RANGE`], and that `after`/`before` don't
+/// reference the mod itself or contradict each other (the same mod listed in both).
+pub fn validate_mod_spec(spec: &ModSpec) -> Vec<Notice> {
+    let mut notices = Vec::new();
+    let key = names::key(&spec.name);
+
+    if names::normalize(&spec.name) != spec.name {
+        notices.push(Notice::new(
+            NoticePreset::Warning,
+            format!("'{}' has leading/trailing whitespace, repeated spaces, or a unicode quote/dash that's now normalized to '{}' when matched against other mods", spec.name, names::normalize(&spec.name)),
+        ));
+    }
+
+    if !PRIORITY_RANGE.contains(&spec.priority) {
+        notices.push(Notice::new(
+            NoticePreset::Warning,
+            format!("'{}' has priority {} outside the usual {}..={} range", spec.name, spec.priority, PRIORITY_RANGE.start(), PRIORITY_RANGE.end()),
+        ));
+    }
+
+    if spec.after.iter().any(|a| names::key(a) == key) {
+        notices.push(Notice::new(NoticePreset::Error, format!("'{}' lists itself in 'after'", spec.name)));
+    }
+    if spec.before.iter().any(|b| names::key(b) == key) {
+        notices.push(Notice::new(NoticePreset::Error, format!("'{}' lists itself in 'before'", spec.name)));
+    }
+    for after in &spec.after {
+        if spec.before.iter().any(|b| names::key(b) == names::key(after)) {
+            notices.push(Notice::new(NoticePreset::Error, format!("'{}' lists '{}' in both 'after' and 'before'", spec.name, after)));
+        }
+    }
+
+    notices
+}
+
+/// Validates dependency/after references across the whole mod list in a single O(n)
+/// pass: builds one key set from both `root_mods` and `mods`, then checks every
+/// dependency, optional dependency, and `after` entry against it in O(1). A missing
+/// required dependency is an error; a missing optional dependency or `after` entry is
+/// only a warning. Takes only shared references and returns its findings as a plain
+/// `Vec<Notice>` for the caller to collect, same as [`validate_mod`]/[`validate_mod_spec`]
+/// — nothing here mutably aliases the mod map, so callers are free to run validations
+/// like this one across several mods concurrently without any synchronization.
+pub fn validate_mod_list(root_mods: &IndexMap<String, ModSpec>, mods: &IndexMap<String, ModSpec>) -> Vec<Notice> {
+    let mut notices = Vec::new();
+    let all_keys: std::collections::HashSet<&str> = root_mods.keys().chain(mods.keys()).map(String::as_str).collect();
+
+    for spec in root_mods.values().chain(mods.values()) {
+        for dep in &spec.dependencies {
+            if !all_keys.contains(names::key(dep).as_str()) {
+                notices.push(Notice::new(NoticePreset::Error, format!("'{}' depends on missing mod '{}'", spec.name, dep)));
+            }
+        }
+        for dep in &spec.optional_dependencies {
+            if !all_keys.contains(names::key(dep).as_str()) {
+                notices.push(Notice::new(NoticePreset::Warning, format!("'{}' optionally depends on missing mod '{}'", spec.name, dep)));
+            }
+        }
+        for after in &spec.after {
+            if !all_keys.contains(names::key(after).as_str()) {
+                notices.push(Notice::new(NoticePreset::Warning, format!("'{}' lists 'after {}' but that mod isn't declared", spec.name, after)));
+            }
+        }
+    }
+
+    // A root mod and a non-root mod attach at different points in the overlay, so
+    // ordering between them is meaningless; only flag it once per offending spec, in
+    // whichever direction the cross-category reference was declared.
+    for spec in root_mods.values() {
+        for after in spec.after.iter().chain(&spec.before) {
+            if mods.contains_key(names::key(after).as_str()) {
+                notices.push(Notice::new(NoticePreset::Warning, format!("root mod '{}' orders itself against non-root mod '{}'; they attach at different points and don't share a load order", spec.name, after)));
+            }
+        }
+    }
+    for spec in mods.values() {
+        for after in spec.after.iter().chain(&spec.before) {
+            if root_mods.contains_key(names::key(after).as_str()) {
+                notices.push(Notice::new(NoticePreset::Warning, format!("mod '{}' orders itself against root mod '{}'; they attach at different points and don't share a load order", spec.name, after)));
+            }
+        }
+    }
+
+    // A after B and B after A at the spec level can never both be satisfied; report
+    // each contradictory pair once (ordered by name to dedupe the reverse direction).
+    let by_key: IndexMap<&str, &ModSpec> = root_mods.iter().chain(mods.iter()).map(|(k, v)| (k.as_str(), v)).collect();
+    for spec in root_mods.values().chain(mods.values()) {
+        for after in &spec.after {
+            let after_key = names::key(after);
+            if let Some(other) = by_key.get(after_key.as_str()) {
+                if names::key(&spec.name) < after_key && other.after.iter().any(|a| names::key(a) == names::key(&spec.name)) {
+                    notices.push(Notice::new(NoticePreset::Error, format!("'{}' and '{}' each list 'after' the other; that ordering is unsatisfiable", spec.name, other.name)));
+                }
+            }
+        }
+    }
+
+    notices
+}
+
+/// Validates a config's *shape* — profile/override resolution, duplicate and
+/// root/non-root conflicts, `dependencies`/`optional_dependencies`/`after` references,
+/// and load-order cycles — without touching `layout.mods` at all, so a pack can be
+/// authored and sanity-checked before any of its mods are actually downloaded.
+/// [`build_modpack`] runs this same shape of checks but additionally requires every mod
+/// to be installed (via [`validate_mod`]); this is the subset of its validation that
+/// doesn't, which is what `modcrab check` calls.
+pub fn check_config(config: &AppConfig, layout: &PackLayout) -> AppResult<Vec<Notice>> {
+    let mut specs = config.specs.clone();
+    crate::overrides::apply(&crate::overrides::load(layout)?, &mut specs);
+    specs.retain(|s| is_in_active_profile(s, config.active_profile.as_deref()));
+
+    let enabled: Vec<ModSpec> = specs.into_iter().filter(|s| s.is_enabled).collect();
+    let enabled = deduplicate_specs(enabled)?;
+
+    // No separate check for a name declared as both a root mod and a regular mod: `key`
+    // here is the same normalized name `deduplicate_specs` just deduplicated on, so a
+    // mod can't land in both maps without `deduplicate_specs` having already rejected
+    // the input with a "duplicate mod name(s) declared" error.
+    let mut root_mods = IndexMap::new();
+    let mut mods = IndexMap::new();
+    for spec in enabled {
+        let key = names::key(&spec.name);
+        if spec.is_root {
+            root_mods.insert(key, spec);
+        } else {
+            mods.insert(key, spec);
+        }
+    }
+
+    let mut notices = validate_mod_list(&root_mods, &mods);
+    for spec in root_mods.values().chain(mods.values()) {
+        notices.extend(validate_mod_spec(spec));
+    }
+
+    sort_mod_list(&mods)?;
+    sort_mod_list(&root_mods)?;
+
+    Ok(notices)
+}
+
+/// Rejects a `modcrab.overwrite_rules` pattern that `glob::Pattern` can't parse: unlike
+/// a hide pattern (which degrades to "matches nothing" — see [`hide_pattern_matches`]),
+/// silently routing nothing would leave a bucket the user declared looking unused when
+/// really their glob syntax just never matches, which is worth catching at build time
+/// rather than at mount time when the files have already landed in the wrong place.
+pub fn validate_overwrite_rules(rules: &[OverwriteRule]) -> AppResult<()> {
+    for rule in rules {
+        if let Err(e) = glob::Pattern::new(&rule.pattern) {
+            return Err(Notice::new(NoticePreset::Error, format!("overwrite rule pattern '{}' is invalid: {e}", rule.pattern)).into());
+        }
+    }
+    Ok(())
+}
+
+/// Sorts a mod map into load order: lower priority first, ties broken alphabetically,
+/// with `after` edges (and `before` edges, translated into the equivalent `after` on
+/// their target) enforced afterwards via a stable topological pass.
+pub fn sort_mod_list(mods: &IndexMap<String, ModSpec>) -> AppResult<Vec<ModSpec>> {
+    let mut ordered: Vec<ModSpec> = mods.values().cloned().collect();
+
+    // `before` is just `after` pointed the other way: "A before B" is the same edge as
+    // "B after A", so translating it into the target's `after` list here means the
+    // topological pass below only ever has to understand one direction. An entry naming
+    // a mod that isn't declared is dropped silently, same as an unresolved `after`.
+    for spec in mods.values() {
+        for before in &spec.before {
+            let before_key = names::key(before);
+            if let Some(target) = ordered.iter_mut().find(|m| names::key(&m.name) == before_key) {
+                target.after.push(spec.name.clone());
+            }
+        }
+    }
+
+    ordered.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| names::key(&a.name).cmp(&names::key(&b.name))));
+
+    let position = |ordered: &[ModSpec], key: &str| ordered.iter().position(|m| names::key(&m.name) == key);
+
+    let mut moved = true;
+    let mut iterations = 0;
+    while moved {
+        moved = false;
+        iterations += 1;
+        if iterations > ordered.len() * ordered.len() + 1 {
+            return Err(Notice::new(NoticePreset::Error, "mod list has an unsortable (cyclic) dependency chain").into());
+        }
+        for i in 0..ordered.len() {
+            let afters: Vec<String> = ordered[i].after.iter().map(|a| names::key(a)).collect();
+            for after_key in afters {
+                if let Some(after_pos) = position(&ordered, &after_key) {
+                    if after_pos > i {
+                        let item = ordered.remove(after_pos);
+                        ordered.insert(i, item);
+                        moved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ordered)
+}
+
+/// Checks `specs` for duplicate names (after [`names::key`] normalization) before
+/// they're collapsed into `build_modpack`'s `root_mods`/`mods` maps, where an
+/// `IndexMap` insert would otherwise silently keep the last one and discard the rest.
+pub fn deduplicate_specs(specs: Vec<ModSpec>) -> AppResult<Vec<ModSpec>> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for spec in &specs {
+        *counts.entry(names::key(&spec.name)).or_insert(0) += 1;
+    }
+
+    let mut seen = HashSet::new();
+    let duplicates: Vec<&str> = specs.iter().map(|s| s.name.as_str()).filter(|name| counts[&names::key(name)] > 1 && seen.insert(names::key(name))).collect();
+
+    if !duplicates.is_empty() {
+        return Err(Notice::new(NoticePreset::Error, format!("duplicate mod name(s) declared: {}", duplicates.join(", "))).into());
+    }
+
+    Ok(specs)
+}
+
+/// Evaluates the config and partitions its mods into root/non-root maps, deduplicated
+/// by name, filtered to those currently enabled, and validated. `global_config_path`
+/// is passed straight through to `eval_config`.
+pub fn build_modpack(layout: &PackLayout, global_config_path: &GlobalConfigPath) -> AppResult<AppData> {
+    build_modpack_from_source(ConfigSource::Dir(layout.config.clone()), layout, global_config_path)
+}
+
+/// Like [`build_modpack`], but evaluates `source` instead of always scanning
+/// `layout.config` for `*.lua`/`*.toml` files — for [`build_from_source`], the
+/// embedding entry point that wants a single Lua file or stdin as its config instead
+/// of a `config/` directory.
+fn build_modpack_from_source(source: ConfigSource, layout: &PackLayout, global_config_path: &GlobalConfigPath) -> AppResult<AppData> {
+    let config: AppConfig = eval_config_source(source, global_config_path)?;
+    validate_overwrite_rules(&config.overwrite_rules)?;
+
+    if config.target.is_none() {
+        return Err(Notice::new(NoticePreset::Error, "no target game set; add `modcrab.target = \"<game>\"` to your config").into());
+    }
+    let target_domain = config.target.as_ref().and_then(|t| t.spec.nexus_domain.clone());
+
+    let mut specs = config.specs;
+    crate::overrides::apply(&crate::overrides::load(layout)?, &mut specs);
+    specs.retain(|s| is_in_active_profile(s, config.active_profile.as_deref()));
+
+    let declared_count = specs.len();
+    let disabled_mods: Vec<String> = specs.iter().filter(|s| !s.is_enabled).map(|s| s.name.clone()).collect();
+    let enabled: Vec<ModSpec> = specs.into_iter().filter(|s| s.is_enabled).collect();
+    let enabled = deduplicate_specs(enabled)?;
+
+    let mut notices = Vec::new();
+    if declared_count > 0 && enabled.is_empty() {
+        notices.push(Notice::new(NoticePreset::Warning, "every declared mod is disabled; the overlay will be the bare game"));
+    }
+
+    // No separate check for a name declared as both a root mod and a regular mod: `key`
+    // here is the same normalized name `deduplicate_specs` just deduplicated on, so a
+    // mod can't land in both maps without `deduplicate_specs` having already rejected
+    // the input with a "duplicate mod name(s) declared" error.
+    let mut root_mods = IndexMap::new();
+    let mut mods = IndexMap::new();
+    for spec in enabled {
+        let key = names::key(&spec.name);
+        if spec.is_root {
+            root_mods.insert(key, spec);
+        } else {
+            mods.insert(key, spec);
+        }
+    }
+
+    notices.extend(validate_mod_list(&root_mods, &mods));
+    for spec in root_mods.values().chain(mods.values()) {
+        notices.extend(validate_mod_spec(spec));
+        notices.extend(validate_mod(&layout.mods, spec));
+        if let Ok(source) = spec.source() {
+            notices.extend(check_domain_mismatch(&spec.name, &source, target_domain.as_deref()));
+        }
+    }
+    notices.extend(detect_archive_mods(&layout.mods, root_mods.values().chain(mods.values())));
+
+    let sorted_mods = sort_mod_list(&mods)?;
+    let sorted_root_mods = sort_mod_list(&root_mods)?;
+
+    let mods: IndexMap<String, ModSpec> = sorted_mods.into_iter().map(|m| (names::key(&m.name), m)).collect();
+    let root_mods: IndexMap<String, ModSpec> = sorted_root_mods.into_iter().map(|m| (names::key(&m.name), m)).collect();
+
+    notices.extend(detect_fully_shadowed_mods(&file_contribution_stats(&layout.mods, mods.values())));
+
+    if let Some(target) = &config.target {
+        let extensions: HashSet<String> =
+            target.spec.plugin_extensions.iter().chain(target.spec.plugin_light_extensions.iter()).map(|e| e.to_lowercase()).collect();
+        if !extensions.is_empty() {
+            notices.extend(detect_plugin_name_conflicts(&layout.mods, mods.values(), &extensions));
+        }
+    }
+
+    let mut data = AppData { root_mods, mods, disabled_mods, build_hash: None, meta: config.meta, locked: false, notices };
+    data.build_hash = Some(build_hash(&data));
+    Ok(data)
+}
+
+/// Embedding entry point for callers that want a built `AppData` without going through
+/// [`PackLayout::resolve`]'s on-disk `.modcrab/settings.toml` lookup or a modpack's full
+/// directory layout: evaluates `config_dir`'s Lua config against `mods_dir` and returns
+/// the sorted, validated result, with no printing or saving of its own, same as
+/// [`build_modpack`] (which this just wraps). `modcrab build`'s CLI command is itself a
+/// thin wrapper around [`build_modpack`] for the same reason — this is that same
+/// contract, minus the need to construct a [`PackLayout`] by hand.
+pub fn build(config_dir: &Path, mods_dir: &Path) -> AppResult<AppData> {
+    build_from_source(ConfigSource::Dir(config_dir.to_path_buf()), mods_dir)
+}
+
+/// Like [`build`], but takes a [`ConfigSource`] instead of always scanning a `config/`
+/// directory — for a one-off config snippet or a config piped in via stdin, with no
+/// `config/` directory or `.modcrab/` state to scaffold around it at all.
+pub fn build_from_source(source: ConfigSource, mods_dir: &Path) -> AppResult<AppData> {
+    let layout = PackLayout { root: PathBuf::new(), mods: mods_dir.to_path_buf(), overwrite: PathBuf::new(), downloads: PathBuf::new(), config: PathBuf::new(), state: PathBuf::new() };
+    build_modpack_from_source(source, &layout, &GlobalConfigPath::default())
+}
+
+/// Lists every file under `mod_dir`, relative to it. Shared by [`file_contribution_stats`]
+/// and `mod_info::gather`'s own conflict count, since both need the same "what files
+/// does this mod ship" view of a mod's directory.
+pub(crate) fn relative_files(mod_dir: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    walkdir::WalkDir::new(mod_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .map(move |e| e.path().strip_prefix(mod_dir).unwrap_or(e.path()).to_path_buf())
+}
+
+/// For each currently-winning file path, the name of the mod that wins it: the
+/// highest-priority mod among `mods` (assumed already sorted into load order) that
+/// ships it, skipping anything a mod's own [`ModSpec::hide`] excludes. Shared by
+/// [`file_contribution_stats`] and [`detect_ownership_changes`], which both need the
+/// same "whose copy is this" view of the merged overlay.
+fn winning_file_owners<'a>(mods_dir: &Path, mods: impl Iterator<Item = &'a ModSpec>) -> HashMap<PathBuf, String> {
+    let mut winners = HashMap::new();
+    for spec in mods {
+        for relative in relative_files(&mods_dir.join(&spec.name)).filter(|r| !spec.hides(r)) {
+            winners.insert(relative, spec.name.clone());
+        }
+    }
+    winners
+}
+
+/// For each mod in `mods` (assumed already sorted into load order, lowest priority
+/// first), how many of its files are the version left standing after the whole order
+/// is applied (`winning`) versus shadowed by a later mod's file at the same path
+/// (`losing`). Lets `build` flag mods that are entirely overridden and could be
+/// disabled without changing the merged result. A mod's own [`ModSpec::hide`] matches
+/// are excluded on both sides: they never reach the overlay at all, so they can
+/// neither win nor lose a conflict.
+pub fn file_contribution_stats<'a>(mods_dir: &Path, mods: impl Iterator<Item = &'a ModSpec> + Clone) -> IndexMap<String, (usize, usize)> {
+    let winners = winning_file_owners(mods_dir, mods.clone());
+
+    let mut stats = IndexMap::new();
+    for spec in mods {
+        let total = relative_files(&mods_dir.join(&spec.name)).filter(|r| !spec.hides(r)).count();
+        let winning = winners.values().filter(|w| w.eq_ignore_ascii_case(&spec.name)).count();
+        stats.insert(spec.name.clone(), (winning, total.saturating_sub(winning)));
+    }
+    stats
+}
+
+/// Warns about mods whose files are all shadowed by higher-priority mods: `winning`
+/// files contributed is zero despite shipping at least one file.
+fn detect_fully_shadowed_mods(stats: &IndexMap<String, (usize, usize)>) -> Vec<Notice> {
+    stats
+        .iter()
+        .filter(|(_, (winning, losing))| *winning == 0 && *losing > 0)
+        .map(|(name, (_, losing))| {
+            Notice::new(
+                NoticePreset::Info,
+                format!("'{name}' contributes 0/{losing} files to the merged overlay; every one is shadowed by a higher-priority mod"),
+            )
+        })
+        .collect()
+}
+
+/// Compares `previous`'s merged file ownership against `current`'s, for a rebuild over
+/// top of an existing `data.bin`. `mount_modpack` already recomputes every file's
+/// winner fresh from whatever mods happen to be enabled at mount time (see
+/// `modcrab-cli`'s `mount.rs` module doc) instead of keeping any kind of persisted
+/// transformation cache to invalidate, so there's nothing left over to go silently
+/// stale at mount time — but a rebuild that quietly hands a path to a different mod
+/// than the one that won it last time is still worth flagging the moment it happens,
+/// which is here, at build time.
+pub fn detect_ownership_changes(mods_dir: &Path, previous: &AppData, current: &AppData) -> Vec<Notice> {
+    let previous_winners = winning_file_owners(mods_dir, previous.root_mods.values().chain(previous.mods.values()));
+    let current_winners = winning_file_owners(mods_dir, current.root_mods.values().chain(current.mods.values()));
+
+    let mut changes: Vec<(PathBuf, String, String)> = current_winners
+        .into_iter()
+        .filter_map(|(path, new_owner)| match previous_winners.get(&path) {
+            Some(old_owner) if *old_owner != new_owner => Some((path, old_owner.clone(), new_owner)),
+            _ => None,
+        })
+        .collect();
+    changes.sort();
+
+    changes
+        .into_iter()
+        .map(|(path, old_owner, new_owner)| {
+            Notice::new(NoticePreset::Info, format!("'{new_owner}' now wins a file that '{old_owner}' provided in the last build")).field("file", path.display().to_string())
+        })
+        .collect()
+}
+
+/// Every virtual file path in a completed build, mapped to the name of the mod that
+/// currently wins it. Keyed by `String` rather than [`PathBuf`] and backed by a
+/// [`BTreeMap`] so it serializes to deterministic, diff-friendly JSON — written by
+/// `modcrab build --manifest` and compared path-by-path with [`diff_manifests`].
+pub type Manifest = BTreeMap<String, String>;
+
+/// Builds a [`Manifest`] for `data`'s merged overlay: every file either `root_mods` or
+/// `mods` contributes, mapped to whichever one currently wins it. Reuses
+/// [`winning_file_owners`], the same "whose copy is this" view [`detect_ownership_changes`]
+/// and [`file_contribution_stats`] already rely on, so a manifest always agrees with
+/// what those report.
+pub fn build_manifest(mods_dir: &Path, data: &AppData) -> Manifest {
+    winning_file_owners(mods_dir, data.root_mods.values().chain(data.mods.values()))
+        .into_iter()
+        .map(|(path, owner)| (path.display().to_string(), owner))
+        .collect()
+}
+
+/// Compares two [`Manifest`]s path by path, reporting every virtual path that
+/// appeared, disappeared, or is now won by a different mod. More granular than
+/// [`detect_ownership_changes`], which only compares two builds of the *same* pack as
+/// it evolves over time: `old`/`new` here can come from any two manifests at all, so
+/// this also answers "what exactly did editing this one mod's priority change" when
+/// the mod list itself looks identical between them.
+pub fn diff_manifests(old: &Manifest, new: &Manifest) -> Vec<Notice> {
+    let mut notices: Vec<(String, Notice)> = Vec::new();
+
+    for (path, owner) in new {
+        match old.get(path) {
+            None => notices.push((path.clone(), Notice::new(NoticePreset::Info, format!("'{path}' appeared, now provided by '{owner}'")))),
+            Some(previous) if previous != owner => notices.push((
+                path.clone(),
+                Notice::new(NoticePreset::Info, format!("'{path}' now provided by '{owner}'")).field("was", previous.clone()),
+            )),
+            _ => {}
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            notices.push((path.clone(), Notice::new(NoticePreset::Info, format!("'{path}' disappeared"))));
+        }
+    }
+
+    notices.sort_by(|(a, _), (b, _)| a.cmp(b));
+    notices.into_iter().map(|(_, notice)| notice).collect()
+}
+
+/// Loads the built `data.bin` for a modpack. [`AppData::locked`] is set from
+/// [`is_mounted`], so a caller can check it without re-deriving the same PID-checked
+/// lockfile lookup itself.
+pub fn load_modpack(layout: &PackLayout) -> AppResult<AppData> {
+    let mut data = AppData::load(&layout.state.join(DATA_FILE))?;
+    data.locked = is_mounted(layout);
+    Ok(data)
+}
+
+/// Saves the built state back to `data.bin`. Refuses while [`is_mounted`] reports a
+/// live mount still holding `mount.lock` — writing a new `data.bin` out from under a
+/// running overlay would leave it mapping a mod list that no longer matches what's on
+/// disk for the rest of the session.
+pub fn save_modpack(layout: &PackLayout, data: &AppData) -> AppResult<()> {
+    if is_mounted(layout) {
+        return Err(Notice::new(NoticePreset::Error, "this modpack is currently mounted; unmount it before writing a new data.bin").into());
+    }
+    data.save(&layout.state.join(DATA_FILE))
+}
+
+/// SHA-256 of `data`'s final sorted mod list (each mod's name and priority, in load
+/// order), stored as [`AppData::build_hash`] on every build and recomputed against a
+/// fresh read of the config by [`staleness_notice`] to detect a `data.bin` that no
+/// longer matches it. Two builds from an identical set of enabled mods at identical
+/// priorities hash identically no matter when either build ran.
+pub fn build_hash(data: &AppData) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for spec in data.root_mods.values().chain(data.mods.values()) {
+        hasher.update(spec.name.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(spec.priority.to_le_bytes());
+    }
+    let digest = hasher.finalize();
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Warns when `data`'s recorded [`AppData::build_hash`] no longer matches what
+/// rebuilding the modpack from its current config would produce — e.g. `config/` was
+/// hand-edited, or `data.bin` was copied in from another pack, without an intervening
+/// `modcrab build`. Re-runs the full build to get a fresh hash, so this isn't free;
+/// callers like `modcrab status` and the mount commands only call it once per
+/// invocation. Silent (`None`) when `data.build_hash` predates this field, or when the
+/// rebuild itself fails — a broken config is already reported elsewhere and doesn't
+/// need a second, more confusing notice layered on top.
+pub fn staleness_notice(layout: &PackLayout, data: &AppData, global_config_path: &GlobalConfigPath) -> Option<Notice> {
+    let recorded = data.build_hash.as_ref()?;
+    let current = build_modpack(layout, global_config_path).ok()?;
+    if *recorded == build_hash(&current) {
+        return None;
+    }
+    Some(Notice::new(NoticePreset::Warning, "data.bin is out of date with your config; run build"))
+}
+
+/// Deletes every file `build_modpack`/`save_modpack` derives from `config/` —
+/// `data.bin`, `modlist.txt`, `plugins.txt` — so the next build starts from nothing
+/// instead of merging into whatever's left over from a previous, possibly confused,
+/// run. Missing files are not an error: there may be nothing to clear yet. Leaves
+/// `settings.toml`, `mount.lock`, and `sessions.log` alone — none of those are derived
+/// from the config, so a `--clean` rebuild has no reason to touch them.
+pub fn clear_derived_state(layout: &PackLayout) -> AppResult<()> {
+    for file in [DATA_FILE, MODLIST_FILE, PLUGINS_FILE] {
+        let path = layout.state.join(file);
+        if path.is_file() {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `.modcrab/modlist.txt` in Mod Organizer's format: one mod per line, `+name`
+/// for enabled and `-name` for disabled, with the topmost line being the highest
+/// priority. `data.mods` is sorted lowest-priority-first (mount order), so it's
+/// reversed here to get MO's highest-priority-first convention. Prefixed with
+/// [`ModMeta::header_comment`] when `modcrab.meta` was declared, so an exported list
+/// carries the pack's name/version/author along with it.
+pub fn write_modlist(layout: &PackLayout, data: &AppData) -> AppResult<()> {
+    let mut lines: Vec<String> = data.mods.values().rev().map(|spec| format!("+{}", spec.name)).collect();
+    lines.extend(data.disabled_mods.iter().map(|name| format!("-{name}")));
+
+    std::fs::write(layout.state.join(MODLIST_FILE), data.meta.header_comment() + &lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Generates `.modcrab/plugins.txt`: every plugin file (matching `plugin_extensions` or
+/// `plugin_light_extensions`) found across enabled mods, in load order, each marked
+/// active with a leading `*`. A no-op for games without a `plugins_path`, since they
+/// don't use a Bethesda-style plugin load order.
+pub fn write_plugins_list(layout: &PackLayout, spec: &GameSpec, data: &AppData) -> AppResult<()> {
+    if spec.plugins_path.is_none() || spec.plugin_extensions.is_empty() {
+        return Ok(());
+    }
+
+    let extensions: HashSet<String> = spec.plugin_extensions.iter().chain(spec.plugin_light_extensions.iter()).map(|e| e.to_lowercase()).collect();
+
+    let mut lines = Vec::new();
+    for mod_spec in data.mods.values() {
+        let mut plugins: Vec<String> = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(layout.mods.join(&mod_spec.name)) {
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                let is_plugin = path.extension().and_then(|e| e.to_str()).map(|e| extensions.contains(&e.to_lowercase())).unwrap_or(false);
+                if is_plugin {
+                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                        plugins.push(name.to_owned());
+                    }
+                }
+            }
+        }
+        plugins.sort();
+        lines.extend(plugins.into_iter().map(|name| format!("*{name}")));
+    }
+
+    std::fs::write(layout.state.join(PLUGINS_FILE), data.meta.header_comment() + &lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Copies the staged `.modcrab/plugins.txt` into the game's data directory, backing up
+/// whatever real `plugins.txt` was already there. A no-op if nothing was staged (either
+/// the game doesn't use one, or `modcrab build` hasn't run yet).
+pub fn install_plugins_list(layout: &PackLayout, data_path: &Path) -> AppResult<()> {
+    let staged = layout.state.join(PLUGINS_FILE);
+    if !staged.is_file() {
+        return Ok(());
+    }
+
+    let dest = data_path.join(PLUGINS_FILE);
+    if dest.is_file() {
+        std::fs::copy(&dest, data_path.join(format!("{PLUGINS_FILE}.bak")))?;
+    }
+    std::fs::copy(&staged, &dest)?;
+    Ok(())
+}
+
+/// The reverse of [`install_plugins_list`]: copies the game's `plugins.txt` back into
+/// `.modcrab/plugins.txt`, so load order changes a tool made while mounted (LOOT
+/// sorting, say) are captured into modcrab's own staged state instead of being lost the
+/// next time something overwrites it. A no-op if the game doesn't use a `plugins.txt`
+/// or nothing was ever written to it under this mount.
+pub fn capture_plugins_list(layout: &PackLayout, data_path: &Path) -> AppResult<()> {
+    let real = data_path.join(PLUGINS_FILE);
+    if !real.is_file() {
+        return Ok(());
+    }
+    std::fs::copy(&real, layout.state.join(PLUGINS_FILE))?;
+    Ok(())
+}
+
+/// Builds the overlay layer list (lowest priority first) for a built modpack: the
+/// game root, then each enabled mod in load order, then the overwrite directory on top.
+pub fn overlay_layers(layout: &PackLayout, root_path: &Path, data: &AppData) -> Vec<PathBuf> {
+    let mut layers = vec![root_path.to_path_buf()];
+    for spec in data.mods.values() {
+        layers.push(layout.mods.join(&spec.name));
+    }
+    layers
+}
+
+/// Each enabled mod's [`ModSpec::hide`] patterns, indexed the same way as
+/// [`overlay_layers`]'s output: an empty list for the game root at index 0, then each
+/// mod's own list in the same load order. Zipped with `overlay_layers` by the caller
+/// (`modcrab-cli`'s mount and SIGHUP reload code) to build `modcrab-fs`'s per-layer
+/// skip predicate via `VirtualFileTree::hide_predicate`.
+pub fn overlay_hide_patterns(data: &AppData) -> Vec<Vec<String>> {
+    std::iter::once(Vec::new()).chain(data.mods.values().map(|spec| spec.hide.clone())).collect()
+}
+
+/// If the process's current directory lies inside `root_path` (the mount target), it
+/// would dangle once the mount replaces that directory and unmounting could fail with
+/// `EBUSY` while we hold it open. Switch to `pack_root` for the duration instead.
+///
+/// Mounting the overlay itself lives in `modcrab-cli` (it's the only place that talks
+/// to `modcrab-fs`); this and the two helpers below are `pub` so that code can build on
+/// them without duplicating the checks.
+pub fn guard_mount_cwd(root_path: &Path, pack_root: &Path) -> AppResult<()> {
+    let cwd = std::env::current_dir()?;
+    if path_is_under(root_path, &cwd) {
+        Notice::new(NoticePreset::Warning, "current directory is inside the mount target; switching to the modpack root for the duration of the mount").print();
+        std::env::set_current_dir(pack_root)?;
+    }
+    Ok(())
+}
+
+/// Returns the first overlay layer (other than the sanctioned base at index 0) that
+/// lives under `root_path`.
+pub fn find_layer_under_root<'a>(layers: &'a [PathBuf], root_path: &Path) -> Option<&'a PathBuf> {
+    layers.iter().skip(1).find(|layer| path_is_under(root_path, layer))
+}
+
+/// Returns true if `candidate` is `base` or a descendant of it. Paths are canonicalized
+/// where possible so symlinks can't defeat the check; paths that don't exist yet fall
+/// back to a plain prefix comparison.
+fn path_is_under(base: &Path, candidate: &Path) -> bool {
+    let base = base.canonicalize().unwrap_or_else(|_| base.to_path_buf());
+    let candidate = candidate.canonicalize().unwrap_or_else(|_| candidate.to_path_buf());
+    candidate.starts_with(&base)
+}
+
+/// Snapshots every file currently under the `overwrite/` surface, keyed by its path
+/// relative to that directory. Used to diff a mount session's changes at unmount.
+pub fn snapshot_overwrite(layout: &PackLayout) -> AppResult<HashMap<PathBuf, u64>> {
+    let dir = &layout.overwrite;
+    let mut snapshot = HashMap::new();
+    if !dir.is_dir() {
+        return Ok(snapshot);
+    }
+    for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path()).to_path_buf();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            snapshot.insert(relative, size);
+        }
+    }
+    Ok(snapshot)
+}
+
+/// What changed in the `overwrite/` surface and how much FS traffic passed through a
+/// mount, from the moment it was mounted to the moment it was unmounted.
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub duration: Duration,
+    pub created: Vec<(PathBuf, u64)>,
+    pub deleted: Vec<PathBuf>,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub lstat_calls: u64,
+    pub attr_cache_hits: u64,
+    /// How many `created` files landed under each `modcrab.overwrite_rules` bucket, in
+    /// the order `buckets` was given. There's no separate command for triaging what a
+    /// session scattered across `overwrite/`'s buckets — this report, already the one
+    /// place that sees the full before/after diff, is where that breakdown belongs.
+    pub bucket_counts: Vec<(String, usize)>,
+}
+
+impl SessionReport {
+    /// Diffs an `overwrite/` snapshot taken before mounting against one taken after
+    /// unmounting, and pairs it with the filesystem's IO counters (read from
+    /// `modcrab-fs`'s metrics handle by the caller, since core has no dependency on
+    /// that crate). `buckets` are the bucket names from `modcrab.overwrite_rules`, used
+    /// to break `created`'s flat list down by where each file was actually routed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build(
+        duration: Duration,
+        before: &HashMap<PathBuf, u64>,
+        after: &HashMap<PathBuf, u64>,
+        bytes_read: u64,
+        bytes_written: u64,
+        lstat_calls: u64,
+        attr_cache_hits: u64,
+        buckets: &[String],
+    ) -> Self {
+        let mut created: Vec<(PathBuf, u64)> = after.iter().filter(|(path, _)| !before.contains_key(*path)).map(|(path, size)| (path.clone(), *size)).collect();
+        created.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut deleted: Vec<PathBuf> = before.keys().filter(|path| !after.contains_key(*path)).cloned().collect();
+        deleted.sort();
+
+        let bucket_counts = buckets
+            .iter()
+            .map(|bucket| (bucket.clone(), created.iter().filter(|(path, _)| path.starts_with(bucket)).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+
+        Self { duration, created, deleted, bytes_read, bytes_written, lstat_calls, attr_cache_hits, bucket_counts }
+    }
+
+    /// Total number of files created or deleted under `overwrite/` during the session.
+    pub fn change_count(&self) -> usize {
+        self.created.len() + self.deleted.len()
+    }
+
+    /// Renders this report as a `Statistics` notice for the CLI to print.
+    pub fn notice(&self) -> Notice {
+        let mut notice = Notice::new(NoticePreset::Statistics, "session report")
+            .field("play time", format!("{:.1}s", self.duration.as_secs_f64()))
+            .field("files created", self.created.len().to_string())
+            .field("files deleted", self.deleted.len().to_string())
+            .field("bytes read", self.bytes_read.to_string())
+            .field("bytes written", self.bytes_written.to_string());
+        if self.lstat_calls > 0 || self.attr_cache_hits > 0 {
+            notice = notice
+                .field("lstat calls", self.lstat_calls.to_string())
+                .field("attr cache hits", self.attr_cache_hits.to_string());
+        }
+        for (bucket, count) in &self.bucket_counts {
+            notice = notice.field(format!("{bucket} files"), count.to_string());
+        }
+        notice
+    }
+}
+
+/// Appends one line to `.modcrab/sessions.log`: a unix timestamp, the command that ran,
+/// its duration, and its change count.
+pub fn append_session_log(layout: &PackLayout, command: &str, report: &SessionReport) -> AppResult<()> {
+    use std::io::Write;
+
+    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let line = format!("{timestamp} {command} duration={:.1}s changes={}\n", report.duration.as_secs_f64(), report.change_count());
+
+    let log_path = layout.state.join("sessions.log");
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(log_path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, priority: i32, after: &[&str]) -> ModSpec {
+        let mut s = ModSpec::new(name);
+        s.priority = priority;
+        s.after = after.iter().map(|s| s.to_string()).collect();
+        s
+    }
+
+    #[test]
+    fn sorts_by_priority_then_name() {
+        let mut mods = IndexMap::new();
+        mods.insert("b".to_owned(), spec("B", 10, &[]));
+        mods.insert("a".to_owned(), spec("A", 5, &[]));
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn after_constraint_reorders() {
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 10, &["B"]));
+        mods.insert("b".to_owned(), spec("B", 10, &[]));
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["B", "A"]);
+    }
+
+    #[test]
+    fn sort_mod_list_walks_a_linear_after_chain_of_ten_mods() {
+        let mut mods = IndexMap::new();
+        for k in 0..10 {
+            let name = format!("M{k}");
+            let mut s = ModSpec::new(&name);
+            s.priority = 50;
+            if k < 9 {
+                s.after = vec![format!("M{}", k + 1)];
+            }
+            mods.insert(name.clone(), s);
+        }
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        let expected: Vec<String> = (0..10).rev().map(|k| format!("M{k}")).collect();
+        assert_eq!(sorted.iter().map(|m| m.name.clone()).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn sort_mod_list_honors_a_before_constraint_the_same_as_the_equivalent_after() {
+        // Alphabetically, and by priority, "Alpha" would sort ahead of "Zeta" by
+        // default — so this only passes if `before` actually moved "Zeta" ahead of it.
+        let mut mods = IndexMap::new();
+        mods.insert("alpha".to_owned(), spec("Alpha", 50, &[]));
+        let mut zeta = spec("Zeta", 50, &[]);
+        zeta.before = vec!["Alpha".to_owned()];
+        mods.insert("zeta".to_owned(), zeta);
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Zeta", "Alpha"]);
+    }
+
+    #[test]
+    fn sort_mod_list_resolves_a_diamond_after_graph() {
+        let mut mods = IndexMap::new();
+        mods.insert("zeta".to_owned(), spec("Zeta", 50, &[]));
+        mods.insert("alpha".to_owned(), spec("Alpha", 50, &["Zeta"]));
+        mods.insert("beta".to_owned(), spec("Beta", 50, &["Zeta"]));
+        mods.insert("gamma".to_owned(), spec("Gamma", 50, &["Alpha", "Beta"]));
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Zeta", "Alpha", "Beta", "Gamma"]);
+    }
+
+    #[test]
+    fn sort_mod_list_breaks_priority_ties_alphabetically() {
+        let mut mods = IndexMap::new();
+        mods.insert("zeta".to_owned(), spec("Zeta", 7, &[]));
+        mods.insert("mid".to_owned(), spec("Mid", 7, &[]));
+        mods.insert("alpha".to_owned(), spec("Alpha", 7, &[]));
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Alpha", "Mid", "Zeta"]);
+    }
+
+    fn bare_layout() -> PackLayout {
+        PackLayout { root: PathBuf::new(), mods: PathBuf::new(), overwrite: PathBuf::new(), downloads: PathBuf::new(), config: PathBuf::new(), state: PathBuf::new() }
+    }
+
+    #[test]
+    fn check_config_reports_a_missing_dependency_without_the_mod_being_installed() {
+        let mut dependent = spec("Dependent", 10, &[]);
+        dependent.dependencies = vec!["Ghost".to_owned()];
+        let config = AppConfig { specs: vec![dependent], ..Default::default() };
+
+        let notices = check_config(&config, &bare_layout()).unwrap();
+        assert!(notices.iter().any(|n| n.preset == NoticePreset::Error && n.header.contains("depends on missing mod")));
+    }
+
+    #[test]
+    fn check_config_errors_on_a_cyclic_after_dependency_without_needing_the_mods_installed() {
+        let config = AppConfig { specs: vec![spec("A", 10, &["B"]), spec("B", 10, &["A"])], ..Default::default() };
+        let err = check_config(&config, &bare_layout()).unwrap_err();
+        assert!(err.to_string().contains("unsortable"));
+    }
+
+    #[test]
+    fn check_config_is_clean_for_a_well_formed_config() {
+        let config = AppConfig { specs: vec![spec("A", 10, &[]), spec("B", 20, &["A"])], ..Default::default() };
+        assert!(check_config(&config, &bare_layout()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sort_mod_list_errors_on_a_cyclic_after_dependency() {
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 10, &["B"]));
+        mods.insert("b".to_owned(), spec("B", 10, &["A"]));
+
+        let err = sort_mod_list(&mods).unwrap_err();
+        assert!(err.to_string().contains("unsortable"));
+    }
+
+    #[test]
+    fn sort_mod_list_ignores_an_after_entry_naming_a_mod_that_is_not_declared() {
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 10, &["Ghost"]));
+        mods.insert("b".to_owned(), spec("B", 10, &[]));
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn sort_mod_list_is_stable_and_alphabetical_when_nothing_constrains_it() {
+        let mut mods = IndexMap::new();
+        mods.insert("zeta".to_owned(), spec("Zeta", 50, &[]));
+        mods.insert("mid".to_owned(), spec("Mid", 50, &[]));
+        mods.insert("alpha".to_owned(), spec("Alpha", 50, &[]));
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Alpha", "Mid", "Zeta"]);
+    }
+
+    #[test]
+    fn after_constraint_reorders_across_a_smart_quote_vs_ascii_apostrophe_mismatch() {
+        let mut mods = IndexMap::new();
+        mods.insert(names::key("JK\u{2019}s Skyrim"), spec("JK\u{2019}s Skyrim", 10, &["Embers XD - Fire and Candle"]));
+        mods.insert(names::key("Embers XD \u{2013} Fire and Candle"), spec("Embers XD \u{2013} Fire and Candle", 10, &[]));
+
+        let notices = validate_mod_list(&IndexMap::new(), &mods);
+        assert!(notices.is_empty(), "the dash-variant dependency should resolve, not be reported missing: {notices:?}");
+
+        let sorted = sort_mod_list(&mods).unwrap();
+        assert_eq!(sorted.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Embers XD \u{2013} Fire and Candle", "JK\u{2019}s Skyrim"]);
+    }
+
+    #[test]
+    fn build_modpack_resolves_after_across_unicode_name_variants_end_to_end() {
+        let dir = std::env::temp_dir().join(format!("modcrab-unicode-names-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join(".modcrab")).unwrap();
+        std::fs::write(
+            dir.join("config/a.lua"),
+            "modcrab.target = \"Skyrim\"\n\
+             table.insert(modcrab.mods, { name = \"JK\u{2019}s Skyrim\", after = { \"Embers XD \u{2013} Fire and Candle\" } })\n\
+             table.insert(modcrab.mods, { name = \"Embers XD \u{2013} Fire and Candle\" })\n",
+        )
+        .unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        let data = build_modpack(&layout, &GlobalConfigPath::default()).unwrap();
+
+        assert!(
+            data.notices.iter().all(|n| !n.header.contains("isn't declared")),
+            "the after-reference should resolve despite the dash variant: {:?}",
+            data.notices
+        );
+        assert_eq!(data.mods.values().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["Embers XD \u{2013} Fire and Candle", "JK\u{2019}s Skyrim"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_mod_spec_warns_when_normalization_changes_the_name() {
+        let notices = validate_mod_spec(&spec("  Embers  XD  ", 50, &[]));
+        assert!(notices.iter().any(|n| n.preset == NoticePreset::Warning && n.header.contains("normalized")));
+    }
+
+    #[test]
+    fn deduplicate_specs_errors_on_a_normalized_name_collision() {
+        let specs = vec![spec("JK's Skyrim", 10, &[]), spec("JK\u{2019}s Skyrim", 20, &[])];
+        let err = deduplicate_specs(specs).unwrap_err();
+        assert!(err.to_string().contains("JK's Skyrim"));
+    }
+
+    #[test]
+    fn deduplicate_specs_passes_distinct_names_through_unchanged() {
+        let specs = vec![spec("A", 10, &[]), spec("B", 20, &[])];
+        assert_eq!(deduplicate_specs(specs).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn build_modpack_errors_when_a_mod_is_declared_as_both_root_and_non_root() {
+        let dir = std::env::temp_dir().join(format!("modcrab-root-and-mod-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join(".modcrab")).unwrap();
+        std::fs::write(
+            dir.join("config/a.lua"),
+            "modcrab.target = \"Skyrim\"\n\
+             table.insert(modcrab.mods, { name = \"Conflicted\", is_root = true })\n\
+             table.insert(modcrab.mods, { name = \"Conflicted\", is_root = false })\n",
+        )
+        .unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        let err = build_modpack(&layout, &GlobalConfigPath::default()).unwrap_err();
+        // Caught by `deduplicate_specs`, the same way any other repeated name would be —
+        // there's no separate root-vs-regular overlap check, since a mod can't land in
+        // both categories without sharing a normalized name with itself first.
+        assert!(err.to_string().contains("duplicate mod name(s) declared"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_modpack_warns_distinctly_when_every_declared_mod_is_disabled() {
+        let dir = std::env::temp_dir().join(format!("modcrab-all-disabled-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join(".modcrab")).unwrap();
+        std::fs::write(
+            dir.join("config/a.lua"),
+            "modcrab.target = \"Skyrim\"\n\
+             table.insert(modcrab.mods, { name = \"Foo\", is_enabled = false })\n",
+        )
+        .unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        let data = build_modpack(&layout, &GlobalConfigPath::default()).unwrap();
+
+        assert!(data.notices.iter().any(|n| n.header.contains("every declared mod is disabled")));
+        assert!(!data.notices.iter().any(|n| n.header.contains("no mods were declared")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_is_a_pure_wrapper_over_build_modpack_needing_only_the_two_directories() {
+        let dir = std::env::temp_dir().join(format!("modcrab-embedding-build-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods/Foo")).unwrap();
+        std::fs::write(dir.join("config/a.lua"), "modcrab.target = \"Skyrim\"\ntable.insert(modcrab.mods, { name = \"Foo\" })\n").unwrap();
+
+        let data = build(&dir.join("config"), &dir.join("mods")).unwrap();
+        assert_eq!(data.mods.keys().map(String::as_str).collect::<Vec<_>>(), vec!["foo"]);
+        assert!(!dir.join(".modcrab").exists(), "build must not create or touch any state on disk");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn session_report_diffs_created_and_deleted_files() {
+        let mut before = HashMap::new();
+        before.insert(PathBuf::from("old.esp"), 10);
+        before.insert(PathBuf::from("kept.ini"), 20);
+
+        let mut after = HashMap::new();
+        after.insert(PathBuf::from("kept.ini"), 20);
+        after.insert(PathBuf::from("new.esp"), 30);
+
+        let report = SessionReport::build(Duration::from_secs(5), &before, &after, 100, 50, 12, 8, &[]);
+        assert_eq!(report.created, vec![(PathBuf::from("new.esp"), 30)]);
+        assert_eq!(report.deleted, vec![PathBuf::from("old.esp")]);
+        assert_eq!(report.bytes_read, 100);
+        assert_eq!(report.bytes_written, 50);
+        assert_eq!(report.lstat_calls, 12);
+        assert_eq!(report.attr_cache_hits, 8);
+        assert_eq!(report.change_count(), 2);
+    }
+
+    #[test]
+    fn session_report_breaks_created_files_down_by_overwrite_bucket() {
+        let before = HashMap::new();
+        let mut after = HashMap::new();
+        after.insert(PathBuf::from("logs/skse.log"), 10);
+        after.insert(PathBuf::from("generated/facegen/npc.nif"), 20);
+        after.insert(PathBuf::from("save.sav"), 5);
+
+        let buckets = vec!["logs".to_owned(), "generated/facegen".to_owned(), "unused".to_owned()];
+        let report = SessionReport::build(Duration::from_secs(1), &before, &after, 0, 0, 0, 0, &buckets);
+
+        assert_eq!(report.bucket_counts, vec![("logs".to_owned(), 1), ("generated/facegen".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn write_modlist_reverses_mount_order_and_lists_disabled() {
+        let dir = std::env::temp_dir().join(format!("modcrab-write-modlist-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 0, &[]));
+        mods.insert("b".to_owned(), spec("B", 10, &[]));
+        let data = AppData { root_mods: IndexMap::new(), mods, disabled_mods: vec!["C".to_owned()], build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+
+        write_modlist(&layout, &data).unwrap();
+        let contents = std::fs::read_to_string(layout.state.join(MODLIST_FILE)).unwrap();
+        assert_eq!(contents, "+B\n+A\n-C\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn clear_derived_state_removes_only_the_files_build_derives() {
+        let dir = std::env::temp_dir().join(format!("modcrab-clear-derived-state-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+        std::fs::write(layout.state.join(DATA_FILE), b"stale").unwrap();
+        std::fs::write(layout.state.join(MODLIST_FILE), b"stale").unwrap();
+        std::fs::write(layout.state.join(PLUGINS_FILE), b"stale").unwrap();
+        std::fs::write(layout.state.join("settings.toml"), b"mods_dir = \"mods\"").unwrap();
+
+        clear_derived_state(&layout).unwrap();
+
+        assert!(!layout.state.join(DATA_FILE).exists());
+        assert!(!layout.state.join(MODLIST_FILE).exists());
+        assert!(!layout.state.join(PLUGINS_FILE).exists());
+        assert!(layout.state.join("settings.toml").exists(), "settings.toml isn't derived from config and must survive a clean");
+
+        // Calling it again with nothing left to remove must not error.
+        clear_derived_state(&layout).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mount_lock_refuses_a_second_acquire_while_the_first_is_still_held() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mount-lock-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+
+        let first = MountLock::acquire(&layout).unwrap();
+        assert!(is_mounted(&layout));
+
+        let err = MountLock::acquire(&layout).unwrap_err();
+        assert!(format!("{err}").contains(&std::process::id().to_string()), "the error should name the PID already holding the lock");
+
+        drop(first);
+        assert!(!is_mounted(&layout), "dropping the lock should remove mount.lock");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mounted_pid_reports_the_holder_only_while_it_s_actually_running() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mounted-pid-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+
+        assert_eq!(MountLock::mounted_pid(&layout), None);
+
+        let lock = MountLock::acquire(&layout).unwrap();
+        assert_eq!(MountLock::mounted_pid(&layout), Some(std::process::id()));
+
+        drop(lock);
+        assert_eq!(MountLock::mounted_pid(&layout), None);
+
+        // A lock file naming a PID that isn't actually running (see the stale-lock test
+        // below) shouldn't be reported as mounted either.
+        std::fs::write(layout.state.join(MOUNT_LOCK_FILE), "4000000000 1700000000").unwrap();
+        assert_eq!(MountLock::mounted_pid(&layout), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mount_lock_clears_a_stale_lock_left_by_a_dead_process() {
+        let dir = std::env::temp_dir().join(format!("modcrab-mount-lock-stale-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+
+        // PID 1 is always running under `/proc`, so a very large, essentially
+        // never-allocated PID stands in for one that's no longer running without this
+        // test needing to spawn and kill a real process.
+        std::fs::write(layout.state.join(MOUNT_LOCK_FILE), "4000000000 1700000000").unwrap();
+
+        let lock = MountLock::acquire(&layout).unwrap();
+        assert_eq!(MountLock::held_by(&layout.state.join(MOUNT_LOCK_FILE)), Some(std::process::id()));
+
+        drop(lock);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn save_modpack_refuses_to_write_while_the_pack_is_mounted() {
+        let dir = std::env::temp_dir().join(format!("modcrab-save-modpack-locked-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+
+        let data = AppData::default();
+        let lock = MountLock::acquire(&layout).unwrap();
+        let err = save_modpack(&layout, &data).unwrap_err();
+        assert!(format!("{err}").contains("mounted"), "the error should explain that the pack is mounted");
+        assert!(!layout.state.join(DATA_FILE).exists(), "a refused save must not touch data.bin");
+
+        drop(lock);
+        save_modpack(&layout, &data).unwrap();
+        assert!(layout.state.join(DATA_FILE).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn load_modpack_sets_locked_from_whether_the_pack_is_mounted() {
+        let dir = std::env::temp_dir().join(format!("modcrab-load-modpack-locked-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+        AppData::default().save(&layout.state.join(DATA_FILE)).unwrap();
+
+        let data = load_modpack(&layout).unwrap();
+        assert!(!data.locked);
+
+        let lock = MountLock::acquire(&layout).unwrap();
+        let data = load_modpack(&layout).unwrap();
+        assert!(data.locked);
+
+        drop(lock);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_plugins_list_orders_by_load_order_and_skips_non_plugins() {
+        let dir = std::env::temp_dir().join(format!("modcrab-write-plugins-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+        std::fs::create_dir_all(layout.mods.join("A")).unwrap();
+        std::fs::write(layout.mods.join("A").join("A.esp"), b"").unwrap();
+        std::fs::write(layout.mods.join("A").join("readme.txt"), b"").unwrap();
+        std::fs::create_dir_all(layout.mods.join("B")).unwrap();
+        std::fs::write(layout.mods.join("B").join("B.esl"), b"").unwrap();
+
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 0, &[]));
+        mods.insert("b".to_owned(), spec("B", 10, &[]));
+        let data = AppData { root_mods: IndexMap::new(), mods, disabled_mods: Vec::new(), build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+
+        let game_spec = GameSpec {
+            plugin_extensions: vec!["esp".to_owned()],
+            plugin_light_extensions: vec!["esl".to_owned()],
+            plugins_path: Some("plugins.txt".to_owned()),
+            ..Default::default()
+        };
+
+        write_plugins_list(&layout, &game_spec, &data).unwrap();
+        let contents = std::fs::read_to_string(layout.state.join(PLUGINS_FILE)).unwrap();
+        assert_eq!(contents, "*A.esp\n*B.esl\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn install_and_capture_plugins_list_round_trip_through_the_data_directory() {
+        let dir = std::env::temp_dir().join(format!("modcrab-plugins-round-trip-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        let data_path = dir.join("data");
+        std::fs::create_dir_all(&layout.state).unwrap();
+        std::fs::create_dir_all(&data_path).unwrap();
+
+        std::fs::write(layout.state.join(PLUGINS_FILE), "*A.esp\n*B.esl\n").unwrap();
+        install_plugins_list(&layout, &data_path).unwrap();
+        assert_eq!(std::fs::read_to_string(data_path.join(PLUGINS_FILE)).unwrap(), "*A.esp\n*B.esl\n");
+
+        // A tool like LOOT reorders the real plugins.txt while mounted.
+        std::fs::write(data_path.join(PLUGINS_FILE), "*B.esl\n*A.esp\n").unwrap();
+        capture_plugins_list(&layout, &data_path).unwrap();
+        assert_eq!(std::fs::read_to_string(layout.state.join(PLUGINS_FILE)).unwrap(), "*B.esl\n*A.esp\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn capture_plugins_list_is_a_no_op_when_the_data_directory_has_none() {
+        let dir = std::env::temp_dir().join(format!("modcrab-plugins-capture-noop-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        let data_path = dir.join("data");
+        std::fs::create_dir_all(&layout.state).unwrap();
+        std::fs::create_dir_all(&data_path).unwrap();
+
+        capture_plugins_list(&layout, &data_path).unwrap();
+        assert!(!layout.state.join(PLUGINS_FILE).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn path_is_under_detects_descendants_and_siblings() {
+        assert!(path_is_under(Path::new("/a/b"), Path::new("/a/b/c")));
+        assert!(path_is_under(Path::new("/a/b"), Path::new("/a/b")));
+        assert!(!path_is_under(Path::new("/a/b"), Path::new("/a/c")));
+    }
+
+    #[test]
+    fn find_layer_under_root_skips_the_sanctioned_base() {
+        let root = PathBuf::from("/game");
+        let layers = vec![root.clone(), PathBuf::from("/mods/a"), PathBuf::from("/game/Data/bad")];
+        let hit = find_layer_under_root(&layers, &root);
+        assert_eq!(hit, Some(&PathBuf::from("/game/Data/bad")));
+    }
+
+    #[test]
+    fn find_layer_under_root_allows_clean_layers() {
+        let root = PathBuf::from("/game");
+        let layers = vec![root.clone(), PathBuf::from("/mods/a"), PathBuf::from("/mods/b")];
+        assert_eq!(find_layer_under_root(&layers, &root), None);
+    }
+
+    #[test]
+    fn validate_mod_list_warns_on_missing_optional_dependency() {
+        let mut mods = IndexMap::new();
+        let mut dependent = spec("Dependent", 10, &[]);
+        dependent.optional_dependencies = vec!["ghost".to_owned()];
+        mods.insert("dependent".to_owned(), dependent);
+
+        let notices = validate_mod_list(&IndexMap::new(), &mods);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].preset, NoticePreset::Warning);
+    }
+
+    #[test]
+    fn detect_plugin_name_conflicts_flags_two_mods_shipping_the_same_plugin_name() {
+        let dir = std::env::temp_dir().join(format!("modcrab-plugin-name-conflict-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Base")).unwrap();
+        std::fs::write(dir.join("Base").join("Patch.esp"), b"").unwrap();
+        std::fs::create_dir_all(dir.join("Overhaul")).unwrap();
+        std::fs::write(dir.join("Overhaul").join("Patch.esp"), b"").unwrap();
+        std::fs::create_dir_all(dir.join("Unrelated")).unwrap();
+        std::fs::write(dir.join("Unrelated").join("Other.esp"), b"").unwrap();
+
+        let specs = [spec("Base", 0, &[]), spec("Overhaul", 10, &[]), spec("Unrelated", 20, &[])];
+        let extensions: HashSet<String> = ["esp".to_owned()].into_iter().collect();
+        let notices = detect_plugin_name_conflicts(&dir, specs.iter(), &extensions);
+
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].header.contains("Base"));
+        assert!(notices[0].header.contains("Overhaul"));
+        assert!(notices[0].header.contains("Patch.esp"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_modpack_warns_when_two_mods_ship_the_same_plugin_name() {
+        let dir = std::env::temp_dir().join(format!("modcrab-plugin-name-conflict-build-test-{}", std::process::id()));
+        init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+
+        let write_mod_file = |mod_name: &str, relative: &str| {
+            let path = layout.mods.join(mod_name).join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"x").unwrap();
+        };
+        write_mod_file("Base", "Patch.esp");
+        write_mod_file("Overhaul", "Patch.esp");
+        std::fs::write(
+            layout.config.join("a.lua"),
+            "modcrab.target = \"Skyrim Special Edition\"\n\
+             table.insert(modcrab.mods, { name = \"Base\", priority = 0 })\n\
+             table.insert(modcrab.mods, { name = \"Overhaul\", priority = 10 })\n",
+        )
+        .unwrap();
+
+        let data = build_modpack(&layout, &GlobalConfigPath::default()).unwrap();
+        assert!(data.notices.iter().any(|n| n.header.contains("Patch.esp")), "expected a plugin-name-conflict notice: {:?}", data.notices);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_hash_is_stable_across_identical_mod_lists_and_changes_with_priority() {
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 0, &[]));
+        mods.insert("b".to_owned(), spec("B", 10, &[]));
+        let data = AppData { root_mods: IndexMap::new(), mods: mods.clone(), disabled_mods: Vec::new(), build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+
+        let same_data = AppData { root_mods: IndexMap::new(), mods, disabled_mods: vec!["ignored".to_owned()], build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+        assert_eq!(build_hash(&data), build_hash(&same_data), "disabled_mods and notices must not affect the hash");
+
+        let mut reprioritized = IndexMap::new();
+        reprioritized.insert("a".to_owned(), spec("A", 0, &[]));
+        reprioritized.insert("b".to_owned(), spec("B", 20, &[]));
+        let different_data = AppData { root_mods: IndexMap::new(), mods: reprioritized, disabled_mods: Vec::new(), build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+        assert_ne!(build_hash(&data), build_hash(&different_data));
+    }
+
+    #[test]
+    fn staleness_notice_fires_only_after_the_config_changes_out_from_under_a_saved_build() {
+        let dir = std::env::temp_dir().join(format!("modcrab-staleness-notice-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join(".modcrab")).unwrap();
+        std::fs::write(dir.join("config/a.lua"), "modcrab.target = \"Skyrim\"\ntable.insert(modcrab.mods, { name = \"Foo\" })\n").unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        let data = build_modpack(&layout, &GlobalConfigPath::default()).unwrap();
+        assert!(staleness_notice(&layout, &data, &GlobalConfigPath::default()).is_none());
+
+        std::fs::write(dir.join("config/a.lua"), "modcrab.target = \"Skyrim\"\ntable.insert(modcrab.mods, { name = \"Bar\" })\n").unwrap();
+        let notice = staleness_notice(&layout, &data, &GlobalConfigPath::default());
+        assert!(notice.is_some_and(|n| n.header.contains("out of date")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn staleness_notice_is_none_for_a_data_bin_saved_before_build_hash_existed() {
+        let dir = std::env::temp_dir().join(format!("modcrab-staleness-notice-no-hash-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join(".modcrab")).unwrap();
+        std::fs::write(dir.join("config/a.lua"), "modcrab.target = \"Skyrim\"\n").unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        let mut data = build_modpack(&layout, &GlobalConfigPath::default()).unwrap();
+        data.build_hash = None;
+        assert!(staleness_notice(&layout, &data, &GlobalConfigPath::default()).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detect_archive_mods_flags_only_mods_shipping_archives() {
+        let dir = std::env::temp_dir().join(format!("modcrab-detect-archives-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Archived")).unwrap();
+        std::fs::write(dir.join("Archived").join("Archived.bsa"), b"").unwrap();
+        std::fs::create_dir_all(dir.join("Loose")).unwrap();
+        std::fs::write(dir.join("Loose").join("texture.dds"), b"").unwrap();
+
+        let specs = [spec("Archived", 0, &[]), spec("Loose", 0, &[])];
+        let notices = detect_archive_mods(&dir, specs.iter());
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].header.contains("Archived"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_contribution_stats_splits_winning_and_losing_files_by_load_order() {
+        let dir = std::env::temp_dir().join(format!("modcrab-contribution-stats-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Base")).unwrap();
+        std::fs::write(dir.join("Base").join("shared.esp"), b"").unwrap();
+        std::fs::write(dir.join("Base").join("base_only.esp"), b"").unwrap();
+        std::fs::create_dir_all(dir.join("Patch")).unwrap();
+        std::fs::write(dir.join("Patch").join("shared.esp"), b"").unwrap();
+
+        let specs = [spec("Base", 0, &[]), spec("Patch", 10, &[])];
+        let stats = file_contribution_stats(&dir, specs.iter());
+        assert_eq!(stats.get("Base"), Some(&(1, 1)));
+        assert_eq!(stats.get("Patch"), Some(&(1, 0)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_contribution_stats_excludes_files_hidden_by_their_own_mod() {
+        let dir = std::env::temp_dir().join(format!("modcrab-contribution-stats-hide-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Base")).unwrap();
+        std::fs::write(dir.join("Base").join("shared.esp"), b"").unwrap();
+        std::fs::create_dir_all(dir.join("Patch")).unwrap();
+        std::fs::write(dir.join("Patch").join("shared.esp"), b"").unwrap();
+        std::fs::write(dir.join("Patch").join("extra.esp"), b"").unwrap();
+
+        let mut patch = spec("Patch", 10, &[]);
+        patch.hide = vec!["extra.esp".to_owned()];
+        let specs = [spec("Base", 0, &[]), patch];
+        let stats = file_contribution_stats(&dir, specs.iter());
+        assert_eq!(stats.get("Base"), Some(&(0, 1)));
+        assert_eq!(stats.get("Patch"), Some(&(1, 0)), "the hidden file should count toward neither winning nor losing");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_mod_warns_when_a_hide_pattern_matches_nothing() {
+        let dir = std::env::temp_dir().join(format!("modcrab-validate-hide-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Patch")).unwrap();
+        std::fs::write(dir.join("Patch").join("shared.esp"), b"").unwrap();
+
+        let mut matching = spec("Patch", 0, &[]);
+        matching.hide = vec!["*.esp".to_owned()];
+        assert!(validate_mod(&dir, &matching).is_empty());
+
+        let mut typo = spec("Patch", 0, &[]);
+        typo.hide = vec!["*.esl".to_owned()];
+        let notices = validate_mod(&dir, &typo);
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].header.contains("hide pattern"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detect_ownership_changes_flags_only_files_whose_winner_actually_changed() {
+        let dir = std::env::temp_dir().join(format!("modcrab-ownership-changes-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Foo")).unwrap();
+        std::fs::create_dir_all(dir.join("Bar")).unwrap();
+        std::fs::write(dir.join("Foo/plugin.esp"), "").unwrap();
+        std::fs::write(dir.join("Bar/plugin.esp"), "").unwrap();
+        std::fs::write(dir.join("Bar/other.esp"), "").unwrap();
+
+        let mut old_mods = IndexMap::new();
+        old_mods.insert("foo".to_owned(), spec("Foo", 0, &[]));
+        old_mods.insert("bar".to_owned(), spec("Bar", 10, &[]));
+        let previous = AppData { root_mods: IndexMap::new(), mods: old_mods, disabled_mods: Vec::new(), build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+
+        // Bar is now the lower priority mod, so Foo wins `plugin.esp` instead; `other.esp`
+        // still only exists in Bar and keeps the same winner either way.
+        let mut new_mods = IndexMap::new();
+        new_mods.insert("bar".to_owned(), spec("Bar", 0, &[]));
+        new_mods.insert("foo".to_owned(), spec("Foo", 10, &[]));
+        let current = AppData { root_mods: IndexMap::new(), mods: new_mods, disabled_mods: Vec::new(), build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+
+        let notices = detect_ownership_changes(&dir, &previous, &current);
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].header.contains("'Foo' now wins"));
+        assert!(notices[0].header.contains("'Bar'"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_manifest_maps_every_winning_file_to_its_owner() {
+        let dir = std::env::temp_dir().join(format!("modcrab-build-manifest-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Base")).unwrap();
+        std::fs::write(dir.join("Base/shared.esp"), "").unwrap();
+        std::fs::create_dir_all(dir.join("Patch")).unwrap();
+        std::fs::write(dir.join("Patch/shared.esp"), "").unwrap();
+        std::fs::write(dir.join("Patch/extra.esp"), "").unwrap();
+
+        let mut mods = IndexMap::new();
+        mods.insert("base".to_owned(), spec("Base", 0, &[]));
+        mods.insert("patch".to_owned(), spec("Patch", 10, &[]));
+        let data = AppData { root_mods: IndexMap::new(), mods, disabled_mods: Vec::new(), build_hash: None, meta: Default::default(), locked: false, notices: Vec::new() };
+
+        let manifest = build_manifest(&dir, &data);
+        assert_eq!(manifest.get("shared.esp"), Some(&"Patch".to_owned()));
+        assert_eq!(manifest.get("extra.esp"), Some(&"Patch".to_owned()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn diff_manifests_reports_appeared_disappeared_and_changed_provider_paths() {
+        let mut old = Manifest::new();
+        old.insert("shared.esp".to_owned(), "Base".to_owned());
+        old.insert("removed.esp".to_owned(), "Base".to_owned());
+
+        let mut new = Manifest::new();
+        new.insert("shared.esp".to_owned(), "Patch".to_owned());
+        new.insert("added.esp".to_owned(), "Patch".to_owned());
+
+        let notices = diff_manifests(&old, &new);
+        assert_eq!(notices.len(), 3);
+        assert!(notices.iter().any(|n| n.header.contains("'added.esp' appeared")));
+        assert!(notices.iter().any(|n| n.header.contains("'removed.esp' disappeared")));
+        assert!(notices.iter().any(|n| n.header.contains("'shared.esp' now provided by 'Patch'")));
+    }
+
+    #[test]
+    fn detect_fully_shadowed_mods_flags_only_mods_with_zero_winning_files() {
+        let mut stats = IndexMap::new();
+        stats.insert("Overridden".to_owned(), (0, 3));
+        stats.insert("Partial".to_owned(), (1, 1));
+        stats.insert("Untouched".to_owned(), (2, 0));
+
+        let notices = detect_fully_shadowed_mods(&stats);
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].header.contains("Overridden"));
+    }
+
+    #[test]
+    fn build_modpack_warns_when_a_mod_is_entirely_shadowed_by_a_higher_priority_mod() {
+        let dir = std::env::temp_dir().join(format!("modcrab-shadowed-mod-test-{}", std::process::id()));
+        init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+
+        let write_mod_file = |mod_name: &str, relative: &str| {
+            let path = layout.mods.join(mod_name).join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"x").unwrap();
+        };
+        write_mod_file("Base", "shared.esp");
+        write_mod_file("Patch", "shared.esp");
+        std::fs::write(
+            layout.config.join("a.lua"),
+            r#"
+                modcrab.target = "Skyrim Special Edition"
+                table.insert(modcrab.mods, { name = "Base", priority = 0 })
+                table.insert(modcrab.mods, { name = "Patch", priority = 10 })
+            "#,
+        )
+        .unwrap();
+
+        let data = build_modpack(&layout, &crate::global_config::GlobalConfigPath::default()).unwrap();
+        assert!(data.notices.iter().any(|n| n.header.contains("'Base' contributes 0/1 files")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_mod_list_allows_root_mods_as_dependencies() {
+        let mut root_mods = IndexMap::new();
+        root_mods.insert("root".to_owned(), spec("Root", 0, &[]));
+        let mut mods = IndexMap::new();
+        let mut dependent = spec("Dependent", 10, &[]);
+        dependent.dependencies = vec!["root".to_owned()];
+        mods.insert("dependent".to_owned(), dependent);
+
+        let notices = validate_mod_list(&root_mods, &mods);
+        assert!(notices.is_empty());
+    }
+
+    #[test]
+    fn validate_mod_list_flags_mutual_after_contradictions() {
+        let mut mods = IndexMap::new();
+        mods.insert("a".to_owned(), spec("A", 0, &["B"]));
+        mods.insert("b".to_owned(), spec("B", 0, &["A"]));
+
+        let notices = validate_mod_list(&IndexMap::new(), &mods);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].preset, NoticePreset::Error);
+    }
+
+    #[test]
+    fn validate_overwrite_rules_errors_on_an_unparsable_glob() {
+        let good = OverwriteRule { pattern: "skse/plugins/*.log".to_owned(), bucket: "logs".to_owned() };
+        assert!(validate_overwrite_rules(std::slice::from_ref(&good)).is_ok());
+
+        let bad = OverwriteRule { pattern: "meshes/[".to_owned(), bucket: "generated".to_owned() };
+        assert!(validate_overwrite_rules(&[good, bad]).is_err());
+    }
+
+    #[test]
+    fn build_modpack_rejects_an_unparsable_overwrite_rule_pattern() {
+        let dir = std::env::temp_dir().join(format!("modcrab-bad-overwrite-rule-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join(".modcrab")).unwrap();
+        std::fs::write(dir.join("config/a.lua"), r#"modcrab.target = "Skyrim"; modcrab.overwrite_rules = { { "meshes/[", "generated" } }"#).unwrap();
+
+        let layout = PackLayout::resolve(&dir).unwrap();
+        assert!(build_modpack(&layout, &GlobalConfigPath::default()).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_mod_list_warns_when_a_root_mod_orders_against_a_non_root_mod() {
+        let mut root_mods = IndexMap::new();
+        root_mods.insert("root".to_owned(), spec("Root", 0, &["Leaf"]));
+        let mut mods = IndexMap::new();
+        mods.insert("leaf".to_owned(), spec("Leaf", 0, &[]));
+
+        let notices = validate_mod_list(&root_mods, &mods);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].preset, NoticePreset::Warning);
+    }
+
+    #[test]
+    fn validate_mod_list_warns_when_a_non_root_mod_orders_against_a_root_mod() {
+        let mut root_mods = IndexMap::new();
+        root_mods.insert("root".to_owned(), spec("Root", 0, &[]));
+        let mut mods = IndexMap::new();
+        mods.insert("leaf".to_owned(), spec("Leaf", 0, &["Root"]));
+
+        let notices = validate_mod_list(&root_mods, &mods);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].preset, NoticePreset::Warning);
+    }
+
+    #[test]
+    fn validate_mod_spec_warns_on_out_of_range_priority() {
+        let notices = validate_mod_spec(&spec("Extreme", 9999, &[]));
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].preset, NoticePreset::Warning);
+    }
+
+    #[test]
+    fn validate_mod_spec_errors_on_self_reference_and_after_before_contradiction() {
+        let self_ref = spec("Self", 0, &["Self"]);
+        assert_eq!(validate_mod_spec(&self_ref).len(), 1);
+
+        let mut contradictory = spec("Contrary", 0, &["Other"]);
+        contradictory.before = vec!["Other".to_owned()];
+        assert_eq!(validate_mod_spec(&contradictory).len(), 1);
+    }
+
+    #[test]
+    fn build_modpack_warns_when_a_mod_id_url_names_a_different_game_domain() {
+        let dir = std::env::temp_dir().join(format!("modcrab-domain-mismatch-test-{}", std::process::id()));
+        init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+
+        std::fs::write(
+            layout.config.join("a.lua"),
+            r#"
+                modcrab.target = "Skyrim Special Edition"
+                table.insert(modcrab.mods, { name = "USSEP", id = "https://www.nexusmods.com/skyrim/mods/266" })
+            "#,
+        )
+        .unwrap();
+
+        let data = build_modpack(&layout, &crate::global_config::GlobalConfigPath::default()).unwrap();
+        assert!(data.notices.iter().any(|n| n.header.contains("has a Nexus URL for 'skyrim'")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_modpack_filters_mods_to_those_declared_for_the_active_profile() {
+        let dir = std::env::temp_dir().join(format!("modcrab-active-profile-test-{}", std::process::id()));
+        init_modpack(&dir).unwrap();
+        let layout = PackLayout::resolve(&dir).unwrap();
+
+        std::fs::write(
+            layout.config.join("a.lua"),
+            r#"
+                modcrab.target = "Skyrim Special Edition"
+                modcrab.active_profile = "gameplay"
+                table.insert(modcrab.mods, { name = "Always Present" })
+                table.insert(modcrab.mods, { name = "Gameplay Mod", profiles = { "gameplay" } })
+                table.insert(modcrab.mods, { name = "Graphics Mod", profiles = { "graphics" } })
+            "#,
+        )
+        .unwrap();
+
+        let data = build_modpack(&layout, &crate::global_config::GlobalConfigPath::default()).unwrap();
+        let names: Vec<_> = data.mods.values().map(|m| m.name.as_str()).collect();
+        assert!(names.contains(&"Always Present"));
+        assert!(names.contains(&"Gameplay Mod"));
+        assert!(!names.contains(&"Graphics Mod"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn is_in_active_profile_treats_an_empty_profiles_list_as_every_profile() {
+        let mut s = spec("No Profile", 50, &[]);
+        assert!(is_in_active_profile(&s, None));
+        assert!(is_in_active_profile(&s, Some("gameplay")));
+
+        s.profiles = vec!["Gameplay".to_owned()];
+        assert!(is_in_active_profile(&s, Some("gameplay")));
+        assert!(!is_in_active_profile(&s, Some("graphics")));
+        assert!(!is_in_active_profile(&s, None));
+    }
+}
@@ -0,0 +1,77 @@
+//! Nexus Mods API access. modcrab has no network client yet (see
+//! [`crate::downloads`]'s own note that mods are placed under `mods/` by hand), so
+//! [`query`] exists to give `modcrab info --nexus` a stable, honest place to land
+//! once one is built, rather than leaving the CLI to fake success or silently do
+//! nothing for an id it can already parse.
+
+use crate::structs::error::AppResult;
+use crate::structs::spec::{ModSource, ModSpec};
+use crate::util::notice::{Notice, NoticePreset};
+
+/// A Nexus mod's id, resolved file id (if one was pinned), and domain — whatever
+/// [`query`] will eventually be able to fill in from a real API response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NexusQuery {
+    pub mod_id: String,
+    pub file_id: Option<String>,
+    pub domain: Option<String>,
+}
+
+/// Resolves `raw` into a [`NexusQuery`]. Accepts anything [`ModSpec::source`] would
+/// (a bare mod id, `<mod id>:<file id>`, or a mod page URL), so a user can paste the
+/// same value they'd eventually put in a `ModSpec`'s `id` field. Errors the same way
+/// a malformed `id` would.
+pub fn parse(raw: &str) -> AppResult<NexusQuery> {
+    let spec = ModSpec { id: Some(raw.to_owned()), ..ModSpec::new("nexus query") };
+    match spec.source()? {
+        ModSource::NexusMod { mod_id, file_id, domain } => Ok(NexusQuery { mod_id, file_id, domain }),
+        ModSource::Url(_) | ModSource::Local => Err(not_a_nexus_id(raw)),
+    }
+}
+
+/// Queries the Nexus API for `query`'s name, version, file list, and requirements.
+/// Always fails today: modcrab has no Nexus API client yet, so there's nothing to
+/// query with. Kept as the single place `modcrab info --nexus` calls through, so
+/// wiring up a real client later is a one-function change instead of a CLI rewrite.
+pub fn query(query: &NexusQuery) -> AppResult<Notice> {
+    Err(Notice::new(NoticePreset::Error, "querying the Nexus API isn't supported yet")
+        .field("mod id", query.mod_id.clone())
+        .field("next step", "install the mod by hand and check it with 'modcrab mod info' instead")
+        .into())
+}
+
+fn not_a_nexus_id(raw: &str) -> crate::structs::error::AppError {
+    Notice::new(NoticePreset::Error, format!("'{raw}' isn't a Nexus mod id")).field("expected", "a Nexus mod id, '<mod id>:<file id>', or a mod page URL").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_a_bare_mod_id() {
+        let query = parse("12345").unwrap();
+        assert_eq!(query.mod_id, "12345");
+        assert_eq!(query.file_id, None);
+    }
+
+    #[test]
+    fn parse_accepts_a_mod_id_with_a_file_id() {
+        let query = parse("12345:67890").unwrap();
+        assert_eq!(query.mod_id, "12345");
+        assert_eq!(query.file_id, Some("67890".to_owned()));
+    }
+
+    #[test]
+    fn parse_rejects_a_non_nexus_url() {
+        let err = parse("https://example.com/mod.7z").unwrap_err();
+        assert!(err.to_string().contains("isn't a Nexus mod id"));
+    }
+
+    #[test]
+    fn query_reports_that_the_nexus_api_client_does_not_exist_yet() {
+        let query_value = parse("12345").unwrap();
+        let err = query(&query_value).unwrap_err();
+        assert!(err.to_string().contains("isn't supported yet"));
+    }
+}
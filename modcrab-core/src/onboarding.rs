@@ -0,0 +1,97 @@
+//! A guided first run for `modcrab build` when no target game is configured yet: pick
+//! a game from the known spec list, confirm (or correct) its detected install root, and
+//! optionally set a Nexus API key, then write the answers to `config/00-init.lua`.
+//!
+//! The CLI is responsible for deciding *when* to run this (only when stdin is a TTY;
+//! scripted invocations should keep hitting the plain "no target game set" error) and
+//! for supplying a real terminal-backed [`Prompt`]. This module only knows how to ask
+//! the questions and write the result, which is what makes it easy to drive with
+//! injected input in tests.
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::modpack::PackLayout;
+use crate::structs::error::AppResult;
+use crate::structs::spec::generate_default_game_specs;
+use crate::util::prompt::Prompt;
+
+const INIT_FILE: &str = "00-init.lua";
+
+/// Walks the user through picking a target game, confirming its root path, and
+/// optionally a Nexus API key, then writes `config/00-init.lua`. Returns the path
+/// written.
+pub fn run<R: BufRead, W: Write>(layout: &PackLayout, prompt: &mut Prompt<R, W>) -> AppResult<std::path::PathBuf> {
+    let specs = generate_default_game_specs();
+    let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+    let choice = prompt.select("which game is this modpack for?", &names)?;
+    let spec = &specs[choice];
+
+    let detected = spec.scan_for_root().ok();
+    let root_path = match &detected {
+        Some(path) => prompt.ask_or(&format!("found it at {}; press Enter to use that, or type a different path:", path.display()), &path.display().to_string())?,
+        None => prompt.ask_or("couldn't find it automatically; enter its install path (or leave blank to set this up later):", "")?,
+    };
+
+    let nexus_key = prompt.ask("Nexus API key (optional, press Enter to skip):")?;
+
+    write_init_lua(&layout.config, &spec.name, &root_path, nexus_key.as_deref())
+}
+
+fn write_init_lua(config_dir: &Path, game_name: &str, root_path: &str, nexus_key: Option<&str>) -> AppResult<std::path::PathBuf> {
+    let mut lua = format!("modcrab.target = {game_name:?}\n");
+    if !root_path.is_empty() {
+        lua.push_str(&format!("modcrab.root_path = {root_path:?}\n"));
+    }
+    if let Some(key) = nexus_key {
+        lua.push_str(&format!("modcrab.nexus_key = {key:?}\n"));
+    }
+
+    let path = config_dir.join(INIT_FILE);
+    std::fs::write(&path, lua)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn layout() -> PackLayout {
+        let dir = std::env::temp_dir().join(format!("modcrab-onboarding-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.config).unwrap();
+        layout
+    }
+
+    #[test]
+    fn writes_target_and_root_path_and_nexus_key_from_injected_answers() {
+        let layout = layout();
+        // Game #1 (Skyrim Special Edition), accept the blank detected root with a typed
+        // path, then supply a Nexus key.
+        let mut prompt = Prompt::new(Cursor::new(&b"1\n/games/skyrimse\nabc123\n"[..]), Vec::new());
+
+        let path = run(&layout, &mut prompt).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("modcrab.target = \"Skyrim Special Edition\""));
+        assert!(contents.contains("modcrab.root_path = \"/games/skyrimse\""));
+        assert!(contents.contains("modcrab.nexus_key = \"abc123\""));
+
+        std::fs::remove_dir_all(&layout.root).unwrap();
+    }
+
+    #[test]
+    fn skips_root_path_and_nexus_key_when_both_are_left_blank() {
+        let layout = layout();
+        let mut prompt = Prompt::new(Cursor::new(&b"2\n\n\n"[..]), Vec::new());
+
+        let path = run(&layout, &mut prompt).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("modcrab.target = \"Skyrim\"\n"));
+        assert!(!contents.contains("root_path"));
+        assert!(!contents.contains("nexus_key"));
+
+        std::fs::remove_dir_all(&layout.root).unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+//! A persisted override layer for bulk mod edits (`enable`/`disable`/`set-priority`).
+//! Each CLI invocation appends one [`OverrideRule`] to `.modcrab/overrides.toml`;
+//! `apply` replays them in file order against a mod list evaluated from `config/`, so
+//! a later rule always wins over an earlier one for the same mod.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::modpack::PackLayout;
+use crate::structs::error::AppResult;
+use crate::structs::spec::ModSpec;
+use crate::util::notice::{Notice, NoticePreset};
+
+const OVERRIDES_FILE: &str = "overrides.toml";
+
+/// Above this many affected mods, a bulk override prompts for confirmation unless
+/// `--yes` is given.
+pub const CONFIRM_THRESHOLD: usize = 5;
+
+/// Which mods a rule applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Selector {
+    /// An exact name match, case-insensitive.
+    Name(String),
+    /// A glob pattern (e.g. `"SMIM*"`) matched against a mod's name.
+    Glob(String),
+    /// Every mod declaring this `group`, case-insensitive.
+    Group(String),
+    /// Every mod that's currently disabled (effective state, after earlier rules).
+    AllDisabled,
+}
+
+impl Selector {
+    fn matches(&self, spec: &ModSpec) -> bool {
+        match self {
+            Selector::Name(name) => spec.name.eq_ignore_ascii_case(name),
+            Selector::Glob(pattern) => glob::Pattern::new(pattern).map(|p| p.matches(&spec.name)).unwrap_or(false),
+            Selector::Group(group) => spec.group.as_deref().is_some_and(|g| g.eq_ignore_ascii_case(group)),
+            Selector::AllDisabled => !spec.is_enabled,
+        }
+    }
+
+    /// A short label for dry-run output and `mod info`.
+    pub fn describe(&self) -> String {
+        match self {
+            Selector::Name(name) => format!("name '{name}'"),
+            Selector::Glob(pattern) => format!("glob '{pattern}'"),
+            Selector::Group(group) => format!("group '{group}'"),
+            Selector::AllDisabled => "all currently-disabled mods".to_owned(),
+        }
+    }
+}
+
+/// What a matching rule does to a mod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    SetEnabled(bool),
+    SetPriority(i32),
+}
+
+impl Action {
+    fn apply(&self, spec: &mut ModSpec) {
+        match self {
+            Action::SetEnabled(enabled) => spec.is_enabled = *enabled,
+            Action::SetPriority(priority) => spec.priority = *priority,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Action::SetEnabled(true) => "enable".to_owned(),
+            Action::SetEnabled(false) => "disable".to_owned(),
+            Action::SetPriority(p) => format!("set priority to {p}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideRule {
+    pub selector: Selector,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct OverrideFile {
+    #[serde(default)]
+    rules: Vec<OverrideRule>,
+}
+
+/// Loads every persisted override rule, oldest first.
+pub fn load(layout: &PackLayout) -> AppResult<Vec<OverrideRule>> {
+    let path = layout.state.join(OVERRIDES_FILE);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)?;
+    let file: OverrideFile = toml::from_str(&text).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to parse {}: {e}", path.display())))?;
+    Ok(file.rules)
+}
+
+/// Appends one rule to the persisted override layer.
+pub fn append(layout: &PackLayout, rule: OverrideRule) -> AppResult<()> {
+    let mut rules = load(layout)?;
+    rules.push(rule);
+    save(&layout.state, &rules)
+}
+
+/// Overwrites the persisted override layer with `rules` wholesale, for callers (like
+/// `mod rename`) that need to rewrite existing rules rather than append a new one.
+pub fn save_rules(layout: &PackLayout, rules: &[OverrideRule]) -> AppResult<()> {
+    save(&layout.state, rules)
+}
+
+fn save(state_dir: &Path, rules: &[OverrideRule]) -> AppResult<()> {
+    let file = OverrideFile { rules: rules.to_vec() };
+    let text = toml::to_string_pretty(&file).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to serialize overrides: {e}")))?;
+    std::fs::write(state_dir.join(OVERRIDES_FILE), text)?;
+    Ok(())
+}
+
+/// Replays `rules` in order against `specs`, mutating matching mods in place. A mod
+/// matched by more than one rule ends up with the last matching rule's effect.
+pub fn apply(rules: &[OverrideRule], specs: &mut [ModSpec]) {
+    apply_tracking(rules, specs, None);
+}
+
+/// Like [`apply`], but also returns the last rule that matched `track`, for surfacing
+/// which override (if any) is currently responsible for a mod's state in `mod info`.
+pub fn apply_tracking<'a>(rules: &'a [OverrideRule], specs: &mut [ModSpec], track: Option<&str>) -> Option<&'a OverrideRule> {
+    let mut last_match = None;
+    for rule in rules {
+        for spec in specs.iter_mut() {
+            if rule.selector.matches(spec) {
+                rule.action.apply(spec);
+                if track.is_some_and(|name| spec.name.eq_ignore_ascii_case(name)) {
+                    last_match = Some(rule);
+                }
+            }
+        }
+    }
+    last_match
+}
+
+/// Returns the names of every mod `selector` currently matches, against the effective
+/// state (config specs with already-persisted overrides applied). Used for dry-run
+/// previews before a new rule is appended.
+pub fn preview<'a>(selector: &Selector, specs: &'a [ModSpec]) -> Vec<&'a str> {
+    specs.iter().filter(|s| selector.matches(s)).map(|s| s.name.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str) -> ModSpec {
+        ModSpec::new(name)
+    }
+
+    #[test]
+    fn apply_replays_rules_in_order_with_last_wins() {
+        let mut specs = vec![spec("SMIM Rocks"), spec("SMIM Trees")];
+        let rules = vec![
+            OverrideRule { selector: Selector::Glob("SMIM*".to_owned()), action: Action::SetEnabled(false) },
+            OverrideRule { selector: Selector::Name("SMIM Rocks".to_owned()), action: Action::SetEnabled(true) },
+        ];
+
+        apply(&rules, &mut specs);
+        assert!(specs[0].is_enabled);
+        assert!(!specs[1].is_enabled);
+    }
+
+    #[test]
+    fn group_selector_matches_case_insensitively() {
+        let mut a = spec("A");
+        a.group = Some("Texture Packs".to_owned());
+        let mut specs = vec![a, spec("B")];
+
+        let rules = vec![OverrideRule { selector: Selector::Group("texture packs".to_owned()), action: Action::SetPriority(90) }];
+        apply(&rules, &mut specs);
+        assert_eq!(specs[0].priority, 90);
+        assert_eq!(specs[1].priority, 50);
+    }
+
+    #[test]
+    fn all_disabled_selector_tracks_effective_state_between_rules() {
+        let mut specs = vec![spec("A"), spec("B")];
+        specs[1].is_enabled = false;
+
+        let rules = vec![
+            OverrideRule { selector: Selector::Name("A".to_owned()), action: Action::SetEnabled(false) },
+            OverrideRule { selector: Selector::AllDisabled, action: Action::SetPriority(1) },
+        ];
+        apply(&rules, &mut specs);
+        assert_eq!(specs[0].priority, 1);
+        assert_eq!(specs[1].priority, 1);
+    }
+
+    #[test]
+    fn load_and_append_round_trip_through_disk() {
+        let dir = std::env::temp_dir().join(format!("modcrab-overrides-test-{}", std::process::id()));
+        let layout = PackLayout::default_for(&dir);
+        std::fs::create_dir_all(&layout.state).unwrap();
+
+        append(&layout, OverrideRule { selector: Selector::Name("A".to_owned()), action: Action::SetEnabled(false) }).unwrap();
+        append(&layout, OverrideRule { selector: Selector::Glob("B*".to_owned()), action: Action::SetPriority(5) }).unwrap();
+
+        let rules = load(&layout).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].action, Action::SetPriority(5));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
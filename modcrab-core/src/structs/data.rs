@@ -0,0 +1,199 @@
+//! Persisted application state. [`AppConfig`] is what Lua evaluation produces from a
+//! modpack's `config/` directory; [`AppData`] is the built, sorted result that gets
+//! written to `.modcrab/data.bin` and consumed at mount time.
+
+use std::path::Path;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::lua::PreLaunchHook;
+use crate::structs::error::AppResult;
+use crate::structs::spec::{ModSpec, TargetGame};
+use crate::util::notice::Notice;
+
+/// The result of evaluating a modpack's Lua config: the target game plus the raw,
+/// unsorted mod specs the user declared.
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    pub target: Option<TargetGame>,
+    pub nexus_key: Option<String>,
+    pub specs: Vec<ModSpec>,
+    /// A `modcrab.pre_launch` callback to run just before mounting, if one was set.
+    pub pre_launch: Option<Rc<PreLaunchHook>>,
+    /// `modcrab.shadow_passthrough` entries: virtual path components (matched
+    /// case-insensitively against a path's first segment) that write directly into the
+    /// shadowed game root instead of `overwrite/`, for game-created directories like
+    /// crash dumps or photo mode output that should reappear in the real game folder
+    /// once unmounted.
+    pub shadow_passthrough: Vec<String>,
+    /// `modcrab.overwrite_rules`: glob patterns that route a newly created file under
+    /// `overwrite/` into a named sub-bucket instead of landing flat at the surface's
+    /// top level, keeping e.g. SKSE logs and generated facegen separate from hand-edited
+    /// inis. The file's virtual path is unaffected — only where it physically lands
+    /// under `overwrite/` changes. Checked for unparsable glob patterns at build time by
+    /// [`crate::modpack::validate_overwrite_rules`].
+    pub overwrite_rules: Vec<OverwriteRule>,
+    /// `modcrab.active_profile`, if set: the name checked against each [`ModSpec::profiles`]
+    /// list by [`crate::modpack::build_modpack`] to decide whether a mod is in play at
+    /// all, ahead of its own `is_enabled`. A mod with an empty `profiles` list is
+    /// unaffected by this and always considered.
+    pub active_profile: Option<String>,
+    /// How long, in seconds, Lua evaluation may run before [`crate::lua::eval_config`]
+    /// aborts it as a runaway config. Resolved from the global TOML config's
+    /// `timeout_build` (a modpack's own `config/*.lua` can't set this itself — that
+    /// would let a runaway config disable the guard meant to catch it), defaulting to
+    /// 30 seconds when the global config leaves it unset; always `Some` in practice.
+    pub timeout_build: Option<u64>,
+    /// `modcrab.meta`, if set: pack-level name/version/author/description/url, carried
+    /// through into [`AppData`] so it survives into `.modcrab/data.bin` for `modcrab
+    /// status`/`modcrab meta` and the generated files' header comments.
+    pub meta: ModMeta,
+}
+
+/// One `modcrab.overwrite_rules` entry: a glob `pattern` matched against a newly
+/// written file's virtual path, and the `bucket` subdirectory under `overwrite/` it
+/// should physically land in when it matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverwriteRule {
+    pub pattern: String,
+    pub bucket: String,
+}
+
+/// Pack-level metadata declared via `modcrab.meta = { name = "...", version = "...",
+/// author = "...", description = "...", url = "..." }`, every field optional. Round-trips
+/// through `.modcrab/data.bin` on [`AppData`] so it's still available after the Lua
+/// environment that declared it is gone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModMeta {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+impl ModMeta {
+    /// True when `modcrab.meta` was never declared, i.e. every field is unset.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Renders as a block of `# key: value` comment lines, one per set field, in
+    /// declaration order, for prepending to a generated file like `modlist.txt`. Empty
+    /// (no trailing newline either) when [`Self::is_empty`].
+    pub fn header_comment(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(name) = &self.name {
+            lines.push(format!("# name: {name}"));
+        }
+        if let Some(version) = &self.version {
+            lines.push(format!("# version: {version}"));
+        }
+        if let Some(author) = &self.author {
+            lines.push(format!("# author: {author}"));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("# description: {description}"));
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("# url: {url}"));
+        }
+        if lines.is_empty() { String::new() } else { lines.join("\n") + "\n" }
+    }
+}
+
+/// The built modpack state: mods partitioned into attach points and sorted into load
+/// order. This is what gets serialized to `.modcrab/data.bin`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppData {
+    pub root_mods: IndexMap<String, ModSpec>,
+    pub mods: IndexMap<String, ModSpec>,
+    /// Names of mods that were declared but disabled, kept around so `modlist.txt`
+    /// can still list them (with a `-` prefix) the way Mod Organizer does.
+    #[serde(default)]
+    pub disabled_mods: Vec<String>,
+    /// SHA-256 of the final sorted mod list, recorded on every build (see
+    /// [`crate::modpack::build_hash`]). `modcrab build --reproducible` additionally
+    /// prints it; `modcrab status`/`mount` recompute it against the current config via
+    /// [`crate::modpack::staleness_notice`] to warn when `data.bin` no longer matches.
+    /// `None` only for a `data.bin` saved before this field existed.
+    #[serde(default)]
+    pub build_hash: Option<String>,
+    /// `modcrab.meta`, carried over from [`AppConfig::meta`] at build time.
+    #[serde(default)]
+    pub meta: ModMeta,
+    /// Never persisted in `data.bin` itself — set by [`crate::modpack::load_modpack`]
+    /// from [`crate::modpack::is_mounted`]'s PID-checked `mount.lock`, and by
+    /// `modcrab-cli`'s `mount_modpack` right before it actually mounts. Lets
+    /// [`crate::modpack::save_modpack`] (and anything else holding an already-loaded
+    /// `AppData`) refuse to write a new `data.bin` out from under a live mount without
+    /// re-deriving the same check itself.
+    #[serde(skip)]
+    pub locked: bool,
+    #[serde(skip)]
+    pub notices: Vec<Notice>,
+}
+
+impl AppData {
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let bytes = std::fs::read(path)?;
+        let data: AppData = toml::from_str(&String::from_utf8_lossy(&bytes))
+            .map_err(|e| Notice::new(crate::util::notice::NoticePreset::Error, format!("failed to parse {}: {e}", path.display())))?;
+        Ok(data)
+    }
+
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| Notice::new(crate::util::notice::NoticePreset::Error, format!("failed to serialize data: {e}")))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Maps `counts_by_layer` (as produced by `VirtualFileTree::node_count_by_layer`
+    /// against a tree mounted from this same `AppData`) back to names, in the same
+    /// order `modcrab-cli`'s mount step assigns layer indices: the game root, then
+    /// every mod in `self.mods`' load order, then the writable overlay surface. A layer
+    /// index past the last one this `AppData` would mount is left out, since there's
+    /// no name for it to report.
+    pub fn overlay_stats(&self, counts_by_layer: &[(usize, usize)]) -> Vec<(String, usize)> {
+        let names: Vec<String> =
+            std::iter::once("game files".to_owned()).chain(self.mods.values().map(|spec| spec.name.clone())).chain(std::iter::once("overwrite".to_owned())).collect();
+
+        counts_by_layer.iter().filter_map(|(layer_idx, count)| names.get(*layer_idx).map(|name| (name.clone(), *count))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::spec::ModSpec;
+
+    fn spec(name: &str) -> ModSpec {
+        ModSpec::new(name)
+    }
+
+    #[test]
+    fn overlay_stats_names_layers_by_mod_load_order_and_drops_unmapped_indices() {
+        let mut mods = IndexMap::new();
+        mods.insert("elfx".to_owned(), spec("ELFX"));
+        mods.insert("smim".to_owned(), spec("SMIM"));
+        let data = AppData { root_mods: IndexMap::new(), mods, disabled_mods: Vec::new(), build_hash: None, meta: ModMeta::default(), locked: false, notices: Vec::new() };
+
+        // Layer 0 is the game root, 1 and 2 are the two mods in load order, 3 would be
+        // the overwrite surface (absent here, the way `status` builds its tree without
+        // one), and 9 is a stray index that doesn't correspond to anything this
+        // `AppData` would ever mount.
+        let counts = vec![(0, 5), (1, 2341), (2, 8912), (9, 1)];
+        assert_eq!(data.overlay_stats(&counts), vec![("game files".to_owned(), 5), ("ELFX".to_owned(), 2341), ("SMIM".to_owned(), 8912)]);
+    }
+
+    #[test]
+    fn header_comment_is_empty_for_a_default_meta_and_one_line_per_set_field_otherwise() {
+        assert_eq!(ModMeta::default().header_comment(), "");
+
+        let meta = ModMeta { name: Some("Aurora".to_owned()), version: Some("0.9".to_owned()), ..ModMeta::default() };
+        assert_eq!(meta.header_comment(), "# name: Aurora\n# version: 0.9\n");
+    }
+}
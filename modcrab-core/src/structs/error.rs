@@ -0,0 +1,47 @@
+//! Error types shared across modcrab. [`GameError`] covers the narrow case of resolving
+//! a [`crate::structs::spec::GameSpec`] against the real filesystem; [`AppError`] is the
+//! catch-all returned by everything else.
+
+use std::path::PathBuf;
+
+use crate::util::notice::Notice;
+
+/// Errors that can occur while resolving a game install from a [`GameSpec`](crate::structs::spec::GameSpec).
+#[derive(Debug, thiserror::Error)]
+pub enum GameError {
+    /// The spec doesn't ship any candidate paths for this label (e.g. `"data"`), so
+    /// there was nothing to scan in the first place.
+    #[error("no candidate paths configured to scan for the '{0}' location")]
+    ScanUnavailable(&'static str),
+    /// Candidate paths were scanned but none of them exist.
+    #[error("could not find the '{0}' location on disk")]
+    ScanFailed(&'static str),
+    /// A previously resolved `'{0}'` location no longer exists at `{1}`, and re-scanning
+    /// for a replacement didn't turn one up either.
+    #[error("the '{0}' location no longer exists at {}, and no replacement could be found", .1.display())]
+    InvalidPath(&'static str, PathBuf),
+}
+
+/// The top-level error type for modcrab. Most code should return [`AppResult`].
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Lua(#[from] mlua::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A notice that already carries the full user-facing explanation.
+    #[error("{}", .0.header)]
+    Custom(Box<Notice>),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl From<Notice> for AppError {
+    fn from(n: Notice) -> Self {
+        AppError::Custom(Box::new(n))
+    }
+}
@@ -0,0 +1,775 @@
+//! Specs describing a game install ([`GameSpec`]) and a single mod ([`ModSpec`]).
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::structs::error::{AppResult, GameError};
+use crate::util::misc::replace_path_home_prefix;
+use crate::util::notice::{Notice, NoticePreset};
+
+/// Static knowledge about a particular game: where it's typically installed, and how
+/// its mods are laid out on disk. One `GameSpec` is shipped per supported game via
+/// [`generate_default_game_specs`].
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GameSpec {
+    pub name: String,
+    /// Candidate install roots to scan, in priority order. May contain `~`.
+    pub common_root_paths: Vec<String>,
+    /// Candidate data-directory paths to scan, in priority order. Empty for games
+    /// whose data directory can always be derived from the root (most of them);
+    /// scanning for `"data"` is unavailable until a spec provides at least one entry.
+    pub common_data_paths: Vec<String>,
+    /// The directory under the game root where mods get installed (e.g. `"Data"`).
+    pub mod_directory: String,
+    pub plugin_extensions: Vec<String>,
+    pub plugin_light_extensions: Vec<String>,
+    pub launch_binary: Option<String>,
+    pub is_windows: bool,
+    /// Where this game's real `plugins.txt` lives. `None` for games that don't use a
+    /// Bethesda-style plugin load order; gates whether `modcrab build` generates one.
+    pub plugins_path: Option<String>,
+    /// Candidate paths where mods staged by other tools (Vortex, NMM) might already
+    /// sit on disk, in priority order. Empty means scanning for them is unavailable,
+    /// same convention as `common_data_paths`.
+    #[serde(default)]
+    pub common_mod_paths: Vec<String>,
+    /// The plugin-count limits this game enforces on its load order. `None` for games
+    /// without a meaningful limit.
+    #[serde(default)]
+    pub plugin_support: Option<GamePluginSupportSpec>,
+    /// This game's URL slug on nexusmods.com (e.g. `"skyrimspecialedition"`), used to
+    /// build a mod's Nexus link from its [`ModSpec::id`]. `None` for games without a
+    /// known slug.
+    #[serde(default)]
+    pub nexus_domain: Option<String>,
+    /// This game's title as it appears in Heroic's GOG library, used to look up an
+    /// exact install path via [`GameSpec::scan_for_root_with_store`] before falling
+    /// back to `common_root_paths`. `None` for games not known to be on GOG.
+    #[serde(default)]
+    pub heroic_game_title: Option<String>,
+    /// This game's slug in Lutris's per-game YAML config, used the same way as
+    /// `heroic_game_title`. `None` for games not known to be managed by Lutris.
+    #[serde(default)]
+    pub lutris_slug: Option<String>,
+}
+
+/// Per-game limits on how many plugins a load order can contain.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct GamePluginSupportSpec {
+    /// Maximum number of full (non-light) plugins the game will load.
+    pub max_plugins: Option<u32>,
+    /// Maximum number of light (ESL-flagged) plugins the game will load, if the game
+    /// supports the ESL format at all.
+    pub max_light_plugins: Option<u32>,
+}
+
+impl GameSpec {
+    pub fn scan_for_root(&self) -> Result<PathBuf, GameError> {
+        scan_paths(&self.common_root_paths, "root")
+    }
+
+    pub fn scan_for_data(&self) -> Result<PathBuf, GameError> {
+        scan_paths(&self.common_data_paths, "data")
+    }
+
+    /// Scans `common_mod_paths` for an existing mod-staging directory from another
+    /// tool, the same way `scan_for_root`/`scan_for_data` scan for the game itself.
+    pub fn scan_for_mods(&self) -> Result<PathBuf, GameError> {
+        scan_paths(&self.common_mod_paths, "mods")
+    }
+
+    /// Like [`scan_for_root`](Self::scan_for_root), but first checks whether Heroic or
+    /// Lutris have this game installed under a launcher-managed path, returning which
+    /// store it came from alongside the path (this affects which Proton/Wine build to
+    /// prefer). Degrades to the static `common_root_paths` list, labeled `"steam"`,
+    /// when neither launcher's config is present or parsable.
+    pub fn scan_for_root_with_store(&self) -> Result<(PathBuf, &'static str), GameError> {
+        if let Some(path) = self.heroic_game_title.as_deref().and_then(scan_heroic_library) {
+            return Ok((path, "heroic"));
+        }
+        if let Some(path) = self.lutris_slug.as_deref().and_then(scan_lutris_config) {
+            return Ok((path, "lutris"));
+        }
+        self.scan_for_root().map(|path| (path, "steam"))
+    }
+}
+
+/// One entry in Heroic's GOG library manifest (`~/.config/heroic/installed.json`).
+/// Heroic has shipped this either as a bare array or wrapped in an `{"installed": [...]}`
+/// object across versions, and entries have used either `title` or `app_name` for the
+/// display name depending on how the game was added; this tries to cover both.
+#[derive(Debug, Deserialize)]
+struct HeroicInstall {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    app_name: Option<String>,
+    install_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeroicManifest {
+    installed: Vec<HeroicInstall>,
+}
+
+/// Looks up `title` in Heroic's GOG library manifest, matching case-insensitively
+/// against each entry's `title` or `app_name`. Returns `None` if the manifest is
+/// missing, unparsable, doesn't list the game, or its recorded path no longer exists.
+fn scan_heroic_library(title: &str) -> Option<PathBuf> {
+    let manifest_path = replace_path_home_prefix("~/.config/heroic/installed.json");
+    let contents = std::fs::read_to_string(manifest_path).ok()?;
+    let entries = serde_json::from_str::<Vec<HeroicInstall>>(&contents)
+        .or_else(|_| serde_json::from_str::<HeroicManifest>(&contents).map(|m| m.installed))
+        .ok()?;
+
+    entries
+        .into_iter()
+        .find(|entry| entry.title.as_deref().or(entry.app_name.as_deref()).is_some_and(|t| t.eq_ignore_ascii_case(title)))
+        .map(|entry| PathBuf::from(entry.install_path))
+        .filter(|path| path.exists())
+}
+
+/// The parts of a Lutris per-game YAML config (`~/.config/lutris/games/<slug>.yml`)
+/// this cares about. The install directory has moved between a top-level `directory`
+/// key and a nested `game.directory` key across Lutris versions, so both are checked.
+#[derive(Debug, Deserialize)]
+struct LutrisGameConfig {
+    #[serde(default)]
+    directory: Option<String>,
+    #[serde(default)]
+    game: Option<LutrisGameSection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LutrisGameSection {
+    #[serde(default)]
+    directory: Option<String>,
+}
+
+/// Looks up `slug`'s install directory in Lutris's per-game YAML config. Returns
+/// `None` if the config is missing, unparsable, names no directory, or the directory
+/// no longer exists.
+fn scan_lutris_config(slug: &str) -> Option<PathBuf> {
+    let config_path = replace_path_home_prefix(&format!("~/.config/lutris/games/{slug}.yml"));
+    let contents = std::fs::read_to_string(config_path).ok()?;
+    let config: LutrisGameConfig = serde_yaml::from_str(&contents).ok()?;
+    let directory = config.directory.or_else(|| config.game.and_then(|g| g.directory))?;
+    let path = PathBuf::from(directory);
+    path.exists().then_some(path)
+}
+
+/// Caps how many matches a single glob pattern in `common_*_paths` can expand to, so a
+/// pattern like `/*` left in by mistake can't turn one scan into a filesystem crawl.
+const MAX_GLOB_RESULTS: usize = 100;
+
+fn scan_paths(paths: &[String], label: &'static str) -> Result<PathBuf, GameError> {
+    if paths.is_empty() {
+        return Err(GameError::ScanUnavailable(label));
+    }
+
+    for raw in paths {
+        let expanded = replace_path_home_prefix(raw);
+        if expanded.contains('*') || expanded.contains('?') {
+            if let Some(candidate) = expand_glob(&expanded).into_iter().find(|p| p.exists()) {
+                return Ok(candidate);
+            }
+        } else {
+            let candidate = PathBuf::from(expanded);
+            if candidate.exists() {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    Err(GameError::ScanFailed(label))
+}
+
+/// Expands a `~`-resolved glob pattern into its matches, in whatever order the
+/// filesystem yields them, capped at [`MAX_GLOB_RESULTS`]. Unreadable entries and
+/// malformed patterns are skipped rather than failing the whole scan.
+fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    let Ok(paths) = glob::glob(pattern) else { return Vec::new() };
+    paths.filter_map(Result::ok).take(MAX_GLOB_RESULTS).collect()
+}
+
+/// Resolves a location that may have been overridden by the user, falling back to
+/// scanning the spec for it and wrapping the result as an [`AppResult`].
+pub fn to_real(spec: &GameSpec, user_override: &Option<PathBuf>, label: &'static str) -> AppResult<PathBuf> {
+    if let Some(path) = user_override {
+        return Ok(path.clone());
+    }
+
+    let scanned = match label {
+        "root" => spec.scan_for_root(),
+        "data" => spec.scan_for_data(),
+        "mods" => spec.scan_for_mods(),
+        _ => unreachable!("to_real only knows the 'root', 'data' and 'mods' labels"),
+    };
+
+    Ok(scanned?)
+}
+
+/// A game spec paired with any paths the user has pinned down themselves, either via
+/// the config or because a previous scan already resolved them.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TargetGame {
+    pub spec: GameSpec,
+    pub root_path: Option<PathBuf>,
+    pub data_path: Option<PathBuf>,
+    #[serde(default)]
+    pub mods_path: Option<PathBuf>,
+    /// Which store `root_path` was last resolved from (`"steam"`, `"heroic"`, or
+    /// `"lutris"`), set by [`resolve_root_checked`](Self::resolve_root_checked). Stays
+    /// `None` until that's called, and untouched by a manual `root_path` override,
+    /// since no scan occurs in that case.
+    #[serde(default)]
+    pub store: Option<String>,
+}
+
+impl TargetGame {
+    pub fn resolve_root(&self) -> AppResult<PathBuf> {
+        to_real(&self.spec, &self.root_path, "root")
+    }
+
+    pub fn resolve_data(&self) -> AppResult<PathBuf> {
+        to_real(&self.spec, &self.data_path, "data")
+    }
+
+    /// Resolves where mods staged by another tool (Vortex, NMM) might already sit, if
+    /// the user hasn't pinned it down and the spec knows where to look.
+    pub fn resolve_mods(&self) -> AppResult<PathBuf> {
+        to_real(&self.spec, &self.mods_path, "mods")
+    }
+
+    /// Like [`resolve_root`](Self::resolve_root), but re-scans the spec if the resolved
+    /// path has since vanished from disk (a Steam library migration, a drive swap),
+    /// instead of handing back a dangling path that only fails later with a bare
+    /// canonicalize error. Returns a relocation notice alongside the path when a
+    /// replacement was found somewhere else. Also remembers which store the path came
+    /// from on `self.store` whenever a scan (rather than a manual override) produced it.
+    pub fn resolve_root_checked(&mut self) -> AppResult<(PathBuf, Option<Notice>)> {
+        if let Some(path) = self.root_path.clone() {
+            if path.exists() {
+                return Ok((path, None));
+            }
+            let (found, store) = self.spec.scan_for_root_with_store().map_err(|_| GameError::InvalidPath("root", path.clone()))?;
+            self.store = Some(store.to_owned());
+            let notice = Notice::new(NoticePreset::Warning, format!("the 'root' location moved from {} to {}", path.display(), found.display()));
+            return Ok((found, Some(notice)));
+        }
+
+        let (resolved, store) = self.spec.scan_for_root_with_store()?;
+        self.store = Some(store.to_owned());
+        Ok((resolved, None))
+    }
+
+    /// Like [`resolve_root_checked`](Self::resolve_root_checked), for the data path.
+    pub fn resolve_data_checked(&self) -> AppResult<(PathBuf, Option<Notice>)> {
+        revalidate(&self.spec, self.resolve_data()?, "data")
+    }
+}
+
+/// Re-validates a previously resolved `label` location before it's relied on again: if
+/// `resolved` no longer exists on disk, re-scans the spec for a replacement and returns
+/// a relocation notice alongside it. Errors with [`GameError::InvalidPath`] naming the
+/// stale path if nothing turns up.
+fn revalidate(spec: &GameSpec, resolved: PathBuf, label: &'static str) -> AppResult<(PathBuf, Option<Notice>)> {
+    if resolved.exists() {
+        return Ok((resolved, None));
+    }
+
+    let scanned = match label {
+        "root" => spec.scan_for_root(),
+        "data" => spec.scan_for_data(),
+        _ => unreachable!("revalidate only knows the 'root' and 'data' labels"),
+    };
+
+    match scanned {
+        Ok(found) => {
+            let notice = Notice::new(
+                NoticePreset::Warning,
+                format!("the '{label}' location moved from {} to {}", resolved.display(), found.display()),
+            );
+            Ok((found, Some(notice)))
+        }
+        Err(_) => Err(GameError::InvalidPath(label, resolved).into()),
+    }
+}
+
+/// The declaration of a single mod, as authored in a modpack's Lua config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModSpec {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub is_enabled: bool,
+    #[serde(default)]
+    pub is_root: bool,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Like `dependencies`, but a missing entry is only a warning, not an error.
+    #[serde(default)]
+    pub optional_dependencies: Vec<String>,
+    #[serde(default)]
+    pub after: Vec<String>,
+    #[serde(default)]
+    pub before: Vec<String>,
+    /// This mod's Nexus Mods ID, if it's hosted there. Combined with the target
+    /// game's [`GameSpec::nexus_domain`] to build a link in `mod info`.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// An arbitrary user-defined group name (e.g. `"Texture Packs"`), matched by the
+    /// `--group` selector on `enable`/`disable`/`set-priority`.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// A version string to stay on (e.g. `"1.4.2"`), surfaced in `mod info` as a
+    /// reminder that this mod is deliberately held back. modcrab has no
+    /// update/installer pipeline yet to enforce this against — it's informational for
+    /// now. Unpinning is just deleting the field.
+    #[serde(default)]
+    pub pin: Option<String>,
+    /// Profile names this mod belongs to (e.g. `{"gameplay"}`). An empty list means
+    /// "every profile". Checked by [`crate::modpack::build_modpack`] against
+    /// `modcrab.active_profile`, ahead of [`Self::is_enabled`], so a mod can be
+    /// skipped by profile mismatch without touching its own enabled state.
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    /// Glob patterns (e.g. `{"meshes/foo.nif", "*.esp"}`), relative to this mod's own
+    /// root and matched case-insensitively, for files to leave out of its overlay
+    /// layer entirely rather than uninstalling them — see [`Self::hides`] and
+    /// [`crate::modpack::overlay_hide_patterns`]. A lower-priority mod's copy of a
+    /// hidden path, if any, wins instead; unlike disabling the mod outright, every
+    /// other file it ships is unaffected.
+    #[serde(default)]
+    pub hide: Vec<String>,
+    /// Same glob patterns as [`Self::hide`], under the name some users reach for first
+    /// when what they want is "strip this file out of the mod, but keep it in my
+    /// config so it survives a reinstall" rather than "hide a conflicting path". Kept
+    /// as a separate field instead of a `#[serde(alias)]` on `hide` so a spec can use
+    /// both names side by side without one silently shadowing the other; checked by
+    /// [`Self::hides`] the exact same way.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ModSpec {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            is_enabled: true,
+            is_root: false,
+            priority: 50,
+            dependencies: Vec::new(),
+            optional_dependencies: Vec::new(),
+            after: Vec::new(),
+            before: Vec::new(),
+            id: None,
+            group: None,
+            pin: None,
+            profiles: Vec::new(),
+            hide: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Parses [`Self::id`] into its typed [`ModSource`], erroring on a value that
+    /// looks like it was meant to be a Nexus id or mod page URL but isn't well-formed.
+    /// `id` itself is kept as a plain string for data.bin/TOML compatibility and so
+    /// it's easy to hand-edit; downstream Nexus-aware code should go through this
+    /// instead of re-parsing the raw string itself.
+    pub fn source(&self) -> AppResult<ModSource> {
+        match &self.id {
+            Some(raw) => parse_mod_source(&self.name, raw),
+            None => Ok(ModSource::Local),
+        }
+    }
+
+    /// True if `relative_path` (relative to this mod's own root) matches one of
+    /// [`Self::hide`] or [`Self::exclude`]'s glob patterns, meaning
+    /// [`crate::modpack::overlay_hide_patterns`] should keep it out of this mod's
+    /// overlay layer.
+    pub fn hides(&self, relative_path: &Path) -> bool {
+        self.hide.iter().chain(&self.exclude).any(|pattern| hide_pattern_matches(pattern, relative_path))
+    }
+}
+
+/// Case-insensitive glob match of `pattern` against `relative_path`, shared by
+/// [`ModSpec::hides`] and [`crate::modpack::validate_mod`]'s "hide pattern matches
+/// nothing" check. Separators aren't required to match literally, so `"*.esp"` catches
+/// a plugin at any depth under the mod root, not just its top level. An unparsable
+/// pattern never matches anything, the same way `overrides::Selector::Glob` already
+/// degrades on one.
+pub(crate) fn hide_pattern_matches(pattern: &str, relative_path: &Path) -> bool {
+    let Ok(pattern) = glob::Pattern::new(pattern) else { return false };
+    let options = glob::MatchOptions { case_sensitive: false, require_literal_separator: false, require_literal_leading_dot: false };
+    pattern.matches_with(&relative_path.to_string_lossy(), options)
+}
+
+/// The typed form of [`ModSpec::id`], returned by [`ModSpec::source`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModSource {
+    /// A Nexus mod id, optionally with a specific file id (`"12345:67890"`) or a game
+    /// domain (when `id` was pasted as a full mod page URL).
+    NexusMod { mod_id: String, file_id: Option<String>, domain: Option<String> },
+    /// Hosted somewhere other than Nexus; the raw URL is kept as-is.
+    Url(String),
+    /// No `id` set: not tracked against any external source.
+    Local,
+}
+
+/// Parses a raw `ModSpec::id` string into a [`ModSource`]. Accepts a bare Nexus mod id
+/// (`"12345"`), a mod id with a file id (`"12345:67890"`), a nexusmods.com mod page URL
+/// (extracting the game domain and mod id from its path), or any other URL (kept
+/// opaque). Anything else is rejected with a message naming what was expected, so a
+/// typo surfaces immediately instead of silently producing a dead Nexus link later.
+fn parse_mod_source(mod_name: &str, raw: &str) -> AppResult<ModSource> {
+    let raw = raw.trim();
+
+    if let Some(rest) = raw.strip_prefix("https://www.nexusmods.com/").or_else(|| raw.strip_prefix("https://nexusmods.com/")) {
+        let mut segments = rest.splitn(3, '/');
+        let domain = segments.next().filter(|s| !s.is_empty());
+        let is_mods_segment = segments.next() == Some("mods");
+        let mod_id = segments.next().and_then(|s| s.split(['?', '#']).next()).filter(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()));
+
+        return match (domain, is_mods_segment, mod_id) {
+            (Some(domain), true, Some(mod_id)) => Ok(ModSource::NexusMod { mod_id: mod_id.to_owned(), file_id: None, domain: Some(domain.to_owned()) }),
+            _ => Err(malformed_id(mod_name, raw, "a nexusmods.com mod page URL, like 'https://www.nexusmods.com/<domain>/mods/<id>'")),
+        };
+    }
+
+    if raw.starts_with("http://") || raw.starts_with("https://") {
+        return Ok(ModSource::Url(raw.to_owned()));
+    }
+
+    if let Some((mod_id, file_id)) = raw.split_once(':') {
+        let is_numeric = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        return if is_numeric(mod_id) && is_numeric(file_id) {
+            Ok(ModSource::NexusMod { mod_id: mod_id.to_owned(), file_id: Some(file_id.to_owned()), domain: None })
+        } else {
+            Err(malformed_id(mod_name, raw, "'<mod id>:<file id>', both numeric"))
+        };
+    }
+
+    if !raw.is_empty() && raw.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(ModSource::NexusMod { mod_id: raw.to_owned(), file_id: None, domain: None });
+    }
+
+    Err(malformed_id(mod_name, raw, "a Nexus mod id, '<mod id>:<file id>', or a mod page URL"))
+}
+
+fn malformed_id(mod_name: &str, raw: &str, expected: &str) -> crate::structs::error::AppError {
+    Notice::new(NoticePreset::Error, format!("'{mod_name}' has an unrecognized 'id' value: '{raw}'")).field("expected", expected.to_owned()).into()
+}
+
+/// Warns when a [`ModSource::NexusMod`] parsed from a mod page URL names a different
+/// game domain than `target_domain` (the target game's own [`GameSpec::nexus_domain`]),
+/// which usually means the URL was pasted from the wrong game's Nexus page.
+pub fn check_domain_mismatch(mod_name: &str, source: &ModSource, target_domain: Option<&str>) -> Option<Notice> {
+    let ModSource::NexusMod { domain: Some(domain), .. } = source else { return None };
+    let target_domain = target_domain?;
+    if domain.eq_ignore_ascii_case(target_domain) {
+        return None;
+    }
+    Some(Notice::new(
+        NoticePreset::Warning,
+        format!("'{mod_name}' has a Nexus URL for '{domain}', but the target game's domain is '{target_domain}'"),
+    ))
+}
+
+/// The default set of [`GameSpec`]s modcrab ships out of the box.
+pub fn generate_default_game_specs() -> Vec<GameSpec> {
+    vec![skyrim_special_edition(), skyrim(), fallout4(), fallout_new_vegas(), starfield()]
+}
+
+fn skyrim_special_edition() -> GameSpec {
+    GameSpec {
+        name: "Skyrim Special Edition".to_owned(),
+        common_root_paths: vec![
+            "~/.steam/steam/steamapps/common/Skyrim Special Edition".to_owned(),
+            "/mnt/*/steamapps/common/Skyrim Special Edition".to_owned(),
+        ],
+        common_data_paths: Vec::new(),
+        mod_directory: "Data".to_owned(),
+        plugin_extensions: vec!["esp".to_owned(), "esm".to_owned()],
+        plugin_light_extensions: vec!["esl".to_owned()],
+        launch_binary: Some("SkyrimSE.exe".to_owned()),
+        is_windows: true,
+        plugins_path: Some("~/Documents/My Games/Skyrim Special Edition/plugins.txt".to_owned()),
+        common_mod_paths: Vec::new(),
+        plugin_support: Some(GamePluginSupportSpec { max_plugins: Some(255), max_light_plugins: Some(4096) }),
+        nexus_domain: Some("skyrimspecialedition".to_owned()),
+        heroic_game_title: Some("Skyrim Special Edition".to_owned()),
+        lutris_slug: Some("the-elder-scrolls-v-skyrim-special-edition".to_owned()),
+    }
+}
+
+fn skyrim() -> GameSpec {
+    GameSpec {
+        name: "Skyrim".to_owned(),
+        common_root_paths: vec![
+            "~/.steam/steam/steamapps/common/Skyrim".to_owned(),
+            "/mnt/*/steamapps/common/Skyrim".to_owned(),
+        ],
+        common_data_paths: Vec::new(),
+        mod_directory: "Data".to_owned(),
+        plugin_extensions: vec!["esp".to_owned(), "esm".to_owned()],
+        plugin_light_extensions: Vec::new(),
+        launch_binary: Some("TESV.exe".to_owned()),
+        is_windows: true,
+        plugins_path: Some("~/Documents/My Games/Skyrim/plugins.txt".to_owned()),
+        common_mod_paths: Vec::new(),
+        plugin_support: Some(GamePluginSupportSpec { max_plugins: Some(255), max_light_plugins: None }),
+        nexus_domain: Some("skyrim".to_owned()),
+        heroic_game_title: Some("The Elder Scrolls V: Skyrim".to_owned()),
+        lutris_slug: Some("the-elder-scrolls-v-skyrim".to_owned()),
+    }
+}
+
+fn fallout4() -> GameSpec {
+    GameSpec {
+        name: "Fallout 4".to_owned(),
+        common_root_paths: vec![
+            "~/.steam/steam/steamapps/common/Fallout 4".to_owned(),
+            "/mnt/*/steamapps/common/Fallout 4".to_owned(),
+        ],
+        common_data_paths: Vec::new(),
+        mod_directory: "Data".to_owned(),
+        plugin_extensions: vec!["esp".to_owned(), "esm".to_owned()],
+        plugin_light_extensions: vec!["esl".to_owned()],
+        launch_binary: Some("Fallout4.exe".to_owned()),
+        is_windows: true,
+        plugins_path: Some("~/Documents/My Games/Fallout4/plugins.txt".to_owned()),
+        common_mod_paths: Vec::new(),
+        plugin_support: Some(GamePluginSupportSpec { max_plugins: Some(255), max_light_plugins: Some(4096) }),
+        nexus_domain: Some("fallout4".to_owned()),
+        heroic_game_title: Some("Fallout 4".to_owned()),
+        lutris_slug: Some("fallout-4".to_owned()),
+    }
+}
+
+fn fallout_new_vegas() -> GameSpec {
+    GameSpec {
+        name: "Fallout: New Vegas".to_owned(),
+        common_root_paths: vec![
+            "~/.steam/steam/steamapps/common/Fallout New Vegas".to_owned(),
+            "~/GOG Games/Fallout New Vegas".to_owned(),
+        ],
+        // NVSE lives alongside `FalloutNV.exe` at the root; mods themselves still
+        // install under `mod_directory` same as every other Gamebryo-era Bethesda game.
+        common_data_paths: vec!["~/.local/share/FalloutNV/".to_owned()],
+        mod_directory: "Data".to_owned(),
+        plugin_extensions: vec!["esp".to_owned(), "esm".to_owned()],
+        plugin_light_extensions: Vec::new(),
+        launch_binary: Some("FalloutNV.exe".to_owned()),
+        is_windows: true,
+        plugins_path: Some("~/Documents/My Games/FalloutNV/plugins.txt".to_owned()),
+        common_mod_paths: Vec::new(),
+        plugin_support: Some(GamePluginSupportSpec { max_plugins: Some(255), max_light_plugins: None }),
+        nexus_domain: Some("newvegas".to_owned()),
+        heroic_game_title: Some("Fallout: New Vegas".to_owned()),
+        lutris_slug: Some("fallout-new-vegas".to_owned()),
+    }
+}
+
+fn starfield() -> GameSpec {
+    GameSpec {
+        name: "Starfield".to_owned(),
+        common_root_paths: vec!["~/.steam/steam/steamapps/common/Starfield".to_owned()],
+        common_data_paths: vec!["~/.local/share/Starfield/".to_owned()],
+        mod_directory: "Data".to_owned(),
+        plugin_extensions: vec!["esp".to_owned(), "esm".to_owned()],
+        plugin_light_extensions: Vec::new(),
+        launch_binary: Some("Starfield.exe".to_owned()),
+        is_windows: true,
+        plugins_path: Some("~/Documents/My Games/Starfield/plugins.txt".to_owned()),
+        common_mod_paths: Vec::new(),
+        plugin_support: None,
+        nexus_domain: Some("starfield".to_owned()),
+        heroic_game_title: None,
+        lutris_slug: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_for_data_is_unavailable_by_default() {
+        let spec = skyrim_special_edition();
+        assert!(matches!(spec.scan_for_data(), Err(GameError::ScanUnavailable("data"))));
+    }
+
+    #[test]
+    fn scan_for_root_fails_when_nothing_exists() {
+        let spec = GameSpec {
+            common_root_paths: vec!["/nonexistent/path/for/modcrab/tests".to_owned()],
+            ..Default::default()
+        };
+        assert!(matches!(spec.scan_for_root(), Err(GameError::ScanFailed("root"))));
+    }
+
+    #[test]
+    fn scan_for_root_expands_glob_patterns() {
+        let dir = std::env::temp_dir().join(format!("modcrab-glob-scan-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("disk1/steamapps/common/Skyrim Special Edition")).unwrap();
+
+        let pattern = format!("{}/disk*/steamapps/common/Skyrim*", dir.display());
+        let spec = GameSpec { common_root_paths: vec![pattern], ..Default::default() };
+
+        assert_eq!(spec.scan_for_root().unwrap(), dir.join("disk1/steamapps/common/Skyrim Special Edition"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_for_mods_is_unavailable_by_default() {
+        let spec = skyrim_special_edition();
+        assert!(matches!(spec.scan_for_mods(), Err(GameError::ScanUnavailable("mods"))));
+    }
+
+    #[test]
+    fn bethesda_specs_carry_their_known_plugin_limits() {
+        assert_eq!(skyrim_special_edition().plugin_support, Some(GamePluginSupportSpec { max_plugins: Some(255), max_light_plugins: Some(4096) }));
+        assert_eq!(skyrim().plugin_support, Some(GamePluginSupportSpec { max_plugins: Some(255), max_light_plugins: None }));
+    }
+
+    #[test]
+    fn resolve_root_checked_rescans_and_warns_when_the_pinned_path_has_moved() {
+        let dir = std::env::temp_dir().join(format!("modcrab-relocate-test-{}", std::process::id()));
+        let stale = dir.join("old-location");
+        let moved = dir.join("steamapps/common/Skyrim Special Edition");
+        std::fs::create_dir_all(&moved).unwrap();
+
+        let mut target = TargetGame {
+            spec: GameSpec { common_root_paths: vec![moved.display().to_string()], ..Default::default() },
+            root_path: Some(stale.clone()),
+            data_path: None,
+            mods_path: None,
+            store: None,
+        };
+
+        let (resolved, notice) = target.resolve_root_checked().unwrap();
+        assert_eq!(resolved, moved);
+        assert!(notice.unwrap().header.contains("moved"));
+        assert_eq!(target.store.as_deref(), Some("steam"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolve_root_checked_errors_naming_the_stale_path_when_nothing_is_found() {
+        let stale = std::env::temp_dir().join(format!("modcrab-relocate-missing-test-{}", std::process::id()));
+        let mut target = TargetGame { spec: GameSpec::default(), root_path: Some(stale.clone()), data_path: None, mods_path: None, store: None };
+
+        let err = target.resolve_root_checked().unwrap_err();
+        assert!(matches!(err, crate::structs::error::AppError::Game(GameError::InvalidPath("root", p)) if p == stale));
+    }
+
+    #[test]
+    fn scan_for_root_with_store_prefers_heroic_over_the_static_steam_list() {
+        let dir = std::env::temp_dir().join(format!("modcrab-heroic-scan-test-{}", std::process::id()));
+        let install = dir.join("Games/Heroic/Skyrim Special Edition");
+        std::fs::create_dir_all(&install).unwrap();
+        let manifest = dir.join("installed.json");
+        std::fs::write(&manifest, format!(r#"[{{"title": "Skyrim Special Edition", "install_path": "{}"}}]"#, install.display())).unwrap();
+
+        // `scan_heroic_library` always reads `~/.config/heroic/installed.json`, which
+        // this test can't safely redirect, so it exercises the JSON parsing directly
+        // via the same manifest shape instead of going through `scan_for_root_with_store`.
+        let contents = std::fs::read_to_string(&manifest).unwrap();
+        #[derive(serde::Deserialize)]
+        struct Entry {
+            title: String,
+            install_path: String,
+        }
+        let entries: Vec<Entry> = serde_json::from_str(&contents).unwrap();
+        assert_eq!(entries[0].title, "Skyrim Special Edition");
+        assert_eq!(PathBuf::from(&entries[0].install_path), install);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_for_root_with_store_degrades_to_steam_when_no_launcher_config_matches() {
+        let spec = GameSpec {
+            heroic_game_title: Some("Definitely Not A Real Game Title 12345".to_owned()),
+            lutris_slug: Some("definitely-not-a-real-slug-12345".to_owned()),
+            common_root_paths: vec!["/nonexistent/path/for/modcrab/tests".to_owned()],
+            ..Default::default()
+        };
+        assert!(matches!(spec.scan_for_root_with_store(), Err(GameError::ScanFailed("root"))));
+    }
+
+    #[test]
+    fn source_is_local_when_id_is_unset() {
+        assert_eq!(ModSpec::new("Unlinked").source().unwrap(), ModSource::Local);
+    }
+
+    #[test]
+    fn source_parses_a_bare_numeric_id() {
+        let mut spec = ModSpec::new("USSEP");
+        spec.id = Some("12345".to_owned());
+        assert_eq!(spec.source().unwrap(), ModSource::NexusMod { mod_id: "12345".to_owned(), file_id: None, domain: None });
+    }
+
+    #[test]
+    fn source_parses_a_mod_id_with_a_file_id() {
+        let mut spec = ModSpec::new("USSEP");
+        spec.id = Some("12345:67890".to_owned());
+        assert_eq!(spec.source().unwrap(), ModSource::NexusMod { mod_id: "12345".to_owned(), file_id: Some("67890".to_owned()), domain: None });
+    }
+
+    #[test]
+    fn source_parses_a_nexus_mod_page_url() {
+        let mut spec = ModSpec::new("USSEP");
+        spec.id = Some("https://www.nexusmods.com/skyrimspecialedition/mods/266".to_owned());
+        assert_eq!(
+            spec.source().unwrap(),
+            ModSource::NexusMod { mod_id: "266".to_owned(), file_id: None, domain: Some("skyrimspecialedition".to_owned()) }
+        );
+    }
+
+    #[test]
+    fn source_keeps_a_non_nexus_url_opaque() {
+        let mut spec = ModSpec::new("Custom");
+        spec.id = Some("https://example.com/mods/custom.zip".to_owned());
+        assert_eq!(spec.source().unwrap(), ModSource::Url("https://example.com/mods/custom.zip".to_owned()));
+    }
+
+    #[test]
+    fn source_rejects_a_malformed_id() {
+        let mut spec = ModSpec::new("Bogus");
+        spec.id = Some("not-an-id".to_owned());
+        let err = spec.source().unwrap_err();
+        assert!(err.to_string().contains("unrecognized 'id' value"));
+    }
+
+    #[test]
+    fn source_rejects_a_nexus_url_missing_a_mod_id() {
+        let mut spec = ModSpec::new("Bogus");
+        spec.id = Some("https://www.nexusmods.com/skyrimspecialedition/mods/".to_owned());
+        assert!(spec.source().is_err());
+    }
+
+    #[test]
+    fn check_domain_mismatch_warns_when_the_url_names_a_different_game() {
+        let source = ModSource::NexusMod { mod_id: "266".to_owned(), file_id: None, domain: Some("skyrim".to_owned()) };
+        let notice = check_domain_mismatch("USSEP", &source, Some("skyrimspecialedition")).unwrap();
+        assert_eq!(notice.preset, NoticePreset::Warning);
+    }
+
+    #[test]
+    fn check_domain_mismatch_is_silent_when_domains_match_or_are_unknown() {
+        let source = ModSource::NexusMod { mod_id: "266".to_owned(), file_id: None, domain: Some("skyrim".to_owned()) };
+        assert!(check_domain_mismatch("USSEP", &source, Some("skyrim")).is_none());
+        assert!(check_domain_mismatch("USSEP", &source, None).is_none());
+        assert!(check_domain_mismatch("USSEP", &ModSource::NexusMod { mod_id: "266".to_owned(), file_id: None, domain: None }, Some("skyrim")).is_none());
+    }
+}
@@ -0,0 +1,84 @@
+//! Embedded starter templates for `modcrab init --game <name>`.
+
+/// One game-specific starter: a target line, a handful of near-universal mods listed
+/// as disabled examples, and a human-readable name for `--list-templates`.
+pub struct Template {
+    pub key: &'static str,
+    pub display_name: &'static str,
+    pub example_mods: &'static [&'static str],
+}
+
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        key: "skyrim special edition",
+        display_name: "Skyrim Special Edition",
+        example_mods: &["SKSE64", "Address Library for SKSE Plugins", "Unofficial Skyrim Special Edition Patch"],
+    },
+    Template {
+        key: "skyrim",
+        display_name: "Skyrim",
+        example_mods: &["SKSE", "Unofficial Skyrim Legendary Edition Patch"],
+    },
+    Template {
+        key: "fallout 4",
+        display_name: "Fallout 4",
+        example_mods: &["F4SE", "Unofficial Fallout 4 Patch"],
+    },
+    Template {
+        key: "starfield",
+        display_name: "Starfield",
+        example_mods: &["SFSE"],
+    },
+];
+
+pub fn find(key: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|t| t.key.eq_ignore_ascii_case(key))
+}
+
+/// Lines to append to the modpack's `.gitignore` so generated/transient state doesn't
+/// get committed.
+pub const GITIGNORE_ADDITIONS: &str = "overwrite/\ndownloads/\n.modcrab/data.bin\n.modcrab/sessions.log\n";
+
+/// Renders this template's starter `config/template.lua`: the target pre-filled, and
+/// its example mods commented out as disabled `table.insert` calls.
+pub fn render_lua(template: &Template) -> String {
+    let mut lua = format!(
+        "-- config/*.lua files load in natural sort order, so a numeric prefix like\n\
+         -- `01_base.lua`, `02_graphics.lua`, ..., `10_patches.lua` loads in the order the\n\
+         -- numbers suggest rather than ASCII order.\nmodcrab.target = \"{}\"\n\n-- Near-universal mods for {} — uncomment and install before enabling.\n",
+        template.display_name, template.display_name
+    );
+    for name in template.example_mods {
+        lua.push_str(&format!("-- table.insert(modcrab.mods, {{ name = \"{name}\", is_enabled = false }})\n"));
+    }
+    lua
+}
+
+/// A placeholder explaining what `profiles/` is for, until profile support exists.
+pub const PROFILES_STUB: &str = "# Each subdirectory here will hold a config/ override for a named profile.\n# Placeholder: modcrab does not yet switch between profiles.\n";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lua::eval_config;
+    use crate::modpack;
+
+    #[test]
+    fn every_template_round_trips_through_eval_config_and_builds_clean() {
+        for template in TEMPLATES {
+            let dir = std::env::temp_dir().join(format!("modcrab-template-test-{}-{}", template.key.replace(' ', "_"), std::process::id()));
+            modpack::init_modpack(&dir).unwrap();
+            let layout = modpack::PackLayout::resolve(&dir).unwrap();
+            std::fs::write(layout.config.join("template.lua"), render_lua(template)).unwrap();
+
+            let config = eval_config(&layout.config, &crate::global_config::GlobalConfigPath::default()).unwrap();
+            assert!(config.target.is_some(), "template '{}' produced no target", template.key);
+
+            let data = modpack::build_modpack(&layout, &crate::global_config::GlobalConfigPath::default()).unwrap();
+            assert!(data.mods.is_empty(), "template '{}' should only contain disabled examples", template.key);
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
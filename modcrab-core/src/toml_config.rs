@@ -0,0 +1,187 @@
+//! Evaluates a modpack's `config/*.toml` files into [`ModSpec`]s, for users who'd
+//! rather declare their modpack than script it. Parsed independently of the Lua
+//! files and merged into the same [`AppConfig`](crate::structs::data::AppConfig) by
+//! `lua::eval_config`, so the two formats can coexist in the same `config/`.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::structs::data::ModMeta;
+use crate::structs::error::AppResult;
+use crate::structs::spec::ModSpec;
+use crate::util::notice::{Notice, NoticePreset};
+
+/// The top-level shape of one `config/*.toml` file: a `[modcrab]` section mirroring
+/// `modcrab.target`/`modcrab.nexus_key` from Lua, plus a `[[mods]]` array mirroring
+/// `modcrab.mods`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TomlModpack {
+    #[serde(default)]
+    pub modcrab: TomlModcrabSection,
+    #[serde(default)]
+    pub mods: Vec<TomlMod>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TomlModcrabSection {
+    pub target: Option<String>,
+    pub nexus_key: Option<String>,
+    pub active_profile: Option<String>,
+    /// `[modcrab.meta]`, mirroring Lua's `modcrab.meta = { ... }`.
+    #[serde(default)]
+    pub meta: ModMeta,
+}
+
+/// One `[[mods]]` entry, covering every [`ModSpec`] field with the same defaults
+/// Lua's `table_to_mod_spec` applies when a key is omitted.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TomlMod {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub is_enabled: bool,
+    #[serde(default)]
+    pub is_root: bool,
+    #[serde(default = "default_priority")]
+    pub priority: i32,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub optional_dependencies: Vec<String>,
+    #[serde(default)]
+    pub after: Vec<String>,
+    #[serde(default)]
+    pub before: Vec<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub pin: Option<String>,
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    #[serde(default)]
+    pub hide: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_priority() -> i32 {
+    50
+}
+
+impl From<TomlMod> for ModSpec {
+    fn from(m: TomlMod) -> Self {
+        Self {
+            name: m.name,
+            is_enabled: m.is_enabled,
+            is_root: m.is_root,
+            priority: m.priority,
+            dependencies: m.dependencies,
+            optional_dependencies: m.optional_dependencies,
+            after: m.after,
+            before: m.before,
+            id: m.id,
+            group: m.group,
+            pin: m.pin,
+            profiles: m.profiles,
+            hide: m.hide,
+            exclude: m.exclude,
+        }
+    }
+}
+
+/// Parses one `config/*.toml` file's contents.
+pub fn parse(path: &Path, text: &str) -> AppResult<TomlModpack> {
+    toml::from_str(text).map_err(|e| Notice::new(NoticePreset::Error, format!("failed to parse {}: {e}", path.display())).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_modcrab_section_and_mod_array() {
+        let text = r#"
+            [modcrab]
+            target = "Skyrim Special Edition"
+            nexus_key = "abc123"
+
+            [[mods]]
+            name = "USSEP"
+            priority = 10
+
+            [[mods]]
+            name = "Disabled Mod"
+            is_enabled = false
+        "#;
+
+        let parsed = parse(Path::new("config/test.toml"), text).unwrap();
+        assert_eq!(parsed.modcrab.target.as_deref(), Some("Skyrim Special Edition"));
+        assert_eq!(parsed.modcrab.nexus_key.as_deref(), Some("abc123"));
+        assert_eq!(parsed.mods.len(), 2);
+        assert_eq!(parsed.mods[0].priority, 10);
+        assert!(!parsed.mods[1].is_enabled);
+    }
+
+    #[test]
+    fn mod_defaults_match_lua_table_to_mod_spec_defaults() {
+        let text = r#"
+            [[mods]]
+            name = "Bare"
+        "#;
+
+        let parsed = parse(Path::new("config/test.toml"), text).unwrap();
+        let spec: ModSpec = parsed.mods.into_iter().next().unwrap().into();
+        assert!(spec.is_enabled);
+        assert!(!spec.is_root);
+        assert_eq!(spec.priority, 50);
+        assert!(spec.dependencies.is_empty());
+        assert_eq!(spec.pin, None);
+    }
+
+    #[test]
+    fn pin_carries_through_into_mod_spec() {
+        let text = r#"
+            [[mods]]
+            name = "USSEP"
+            pin = "4.3.1"
+        "#;
+
+        let parsed = parse(Path::new("config/test.toml"), text).unwrap();
+        let spec: ModSpec = parsed.mods.into_iter().next().unwrap().into();
+        assert_eq!(spec.pin.as_deref(), Some("4.3.1"));
+    }
+
+    #[test]
+    fn hide_carries_through_into_mod_spec() {
+        let text = r#"
+            [[mods]]
+            name = "Patch"
+            hide = ["*.esp", "readme.txt"]
+        "#;
+
+        let parsed = parse(Path::new("config/test.toml"), text).unwrap();
+        let spec: ModSpec = parsed.mods.into_iter().next().unwrap().into();
+        assert_eq!(spec.hide, vec!["*.esp".to_owned(), "readme.txt".to_owned()]);
+    }
+
+    #[test]
+    fn exclude_carries_through_into_mod_spec_alongside_hide() {
+        let text = r#"
+            [[mods]]
+            name = "Patch"
+            hide = ["*.esp"]
+            exclude = ["readme.txt", "docs/"]
+        "#;
+
+        let parsed = parse(Path::new("config/test.toml"), text).unwrap();
+        let spec: ModSpec = parsed.mods.into_iter().next().unwrap().into();
+        assert_eq!(spec.hide, vec!["*.esp".to_owned()]);
+        assert_eq!(spec.exclude, vec!["readme.txt".to_owned(), "docs/".to_owned()]);
+    }
+}
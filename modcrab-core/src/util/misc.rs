@@ -0,0 +1,109 @@
+//! Small helpers that don't belong to any particular module.
+
+use std::io::{self, Write};
+
+use nix::sys::signal::Signal;
+
+use crate::structs::error::AppResult;
+
+/// Expands a leading `~` (or `~/`) to the user's home directory, leaving the rest of
+/// the path untouched. Paths without a leading `~` are returned as-is.
+pub fn replace_path_home_prefix(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix('~') {
+        if let Some(home) = dirs::home_dir() {
+            return format!("{}{}", home.display(), rest);
+        }
+    }
+    path.to_owned()
+}
+
+/// Prompts `prompt [y/N]` and reads a line from stdin, treating a leading `y`/`Y` as
+/// confirmation and anything else (including an empty line) as declining.
+pub fn confirm(prompt: &str) -> io::Result<bool> {
+    print!("{prompt} [y/N] ");
+    io::stdout().flush()?;
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf)?;
+    Ok(buf.trim().to_lowercase().starts_with('y'))
+}
+
+/// Blocks until one of `signals` is delivered to this process, returning whichever one
+/// arrived. Unlike reading a line from stdin, this works the same whether stdin is a
+/// terminal, closed, or piped from a script, which is the point: `modcrab mount` needs
+/// a clean way to wait for `SIGTERM`/`SIGINT` (from `modcrab unmount`, or just `kill`)
+/// without assuming anyone is sitting at a keyboard.
+pub fn wait_for_signal(signals: &[Signal]) -> AppResult<Signal> {
+    let mut registered = signal_hook::iterator::Signals::new(signals.iter().map(|signal| *signal as i32))?;
+    let number = registered.forever().next().expect("Signals::forever blocks until a registered signal arrives");
+    Ok(signals.iter().copied().find(|signal| *signal as i32 == number).unwrap_or(Signal::SIGTERM))
+}
+
+/// Blocks until the user presses Enter or one of `signals` arrives, whichever comes
+/// first, by racing a line read against [`wait_for_signal`] on a channel. Kept for
+/// interactive use on top of `wait_for_signal` alone, since a human watching the
+/// terminal expects Enter to work too, not just Ctrl+C.
+pub fn wait_for_enter_key(signals: &[Signal]) -> AppResult<()> {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let signal_tx = tx.clone();
+    let signals = signals.to_vec();
+    std::thread::spawn(move || {
+        if let Ok(signal) = wait_for_signal(&signals) {
+            let _ = signal_tx.send(signal);
+        }
+    });
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let _ = io::stdin().read_line(&mut line);
+        let _ = tx.send(Signal::SIGINT);
+    });
+
+    rx.recv().ok();
+    Ok(())
+}
+
+/// Classic Levenshtein edit distance between two strings, case-insensitive. Used to
+/// suggest a likely match when a name a user typed doesn't exist.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut prev_diag = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let temp = row[j];
+            row[j] = if a[i - 1] == b[j - 1] { prev_diag } else { 1 + prev_diag.min(row[j]).min(row[j - 1]) };
+            prev_diag = temp;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Finds the candidate closest to `needle` by edit distance, for "did you mean"
+/// suggestions. Returns `None` if `candidates` is empty.
+pub fn closest_match<'a>(needle: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates.min_by_key(|candidate| levenshtein_distance(needle, candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_match_picks_the_nearest_candidate() {
+        let candidates = ["USSEP", "SKSE64", "Unofficial Skyrim Patch"];
+        assert_eq!(closest_match("usep", candidates.into_iter()), Some("USSEP"));
+        assert_eq!(closest_match("nothing close to anything", std::iter::empty()), None);
+    }
+
+    #[test]
+    fn wait_for_signal_returns_the_registered_signal_that_was_raised() {
+        let handle = std::thread::spawn(|| wait_for_signal(&[Signal::SIGUSR1, Signal::SIGUSR2]));
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        nix::sys::signal::raise(Signal::SIGUSR2).unwrap();
+        assert_eq!(handle.join().unwrap().unwrap(), Signal::SIGUSR2);
+    }
+}
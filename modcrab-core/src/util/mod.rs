@@ -0,0 +1,6 @@
+pub mod misc;
+pub mod names;
+pub mod notice;
+pub mod prompt;
+pub mod text;
+pub mod xdg;
@@ -0,0 +1,66 @@
+//! Normalizes a mod name into a stable lookup key, so names that only differ by
+//! whitespace or a unicode quote/dash variant (`JK's Skyrim` vs `JK’s Skyrim`) still
+//! match each other everywhere a name is used as a key: `after`/`before`/dependency
+//! resolution, the `root_mods`/`mods` maps in `build_modpack`, and conflict detection.
+
+/// Unifies a name for use as a lookup key: trims it, collapses runs of whitespace to a
+/// single space, maps unicode quote and dash variants to their ASCII equivalents, and
+/// lowercases the result.
+pub fn key(name: &str) -> String {
+    normalize(name).to_lowercase()
+}
+
+/// Like [`key`], but preserves case. Used to decide whether normalization actually
+/// changed a user-provided name, so that change can be warned about without also
+/// flagging plain case differences.
+pub fn normalize(name: &str) -> String {
+    let unified: String = name.chars().map(unify_char).collect();
+    let mut collapsed = String::with_capacity(unified.len());
+    let mut last_was_space = false;
+    for c in unified.trim().chars() {
+        if c.is_whitespace() {
+            if !last_was_space {
+                collapsed.push(' ');
+            }
+            last_was_space = true;
+        } else {
+            collapsed.push(c);
+            last_was_space = false;
+        }
+    }
+    collapsed
+}
+
+fn unify_char(c: char) -> char {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201B}' => '\'',
+        '\u{201C}' | '\u{201D}' => '"',
+        '\u{2013}' | '\u{2014}' | '\u{2212}' => '-',
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_unifies_smart_quotes_and_case() {
+        assert_eq!(key("JK's Skyrim"), key("JK\u{2019}s Skyrim"));
+    }
+
+    #[test]
+    fn key_unifies_dash_variants_and_collapses_whitespace() {
+        assert_eq!(key("Embers XD - Fire and Candle"), key("Embers XD \u{2013} Fire  and Candle"));
+    }
+
+    #[test]
+    fn normalize_trims_but_keeps_case() {
+        assert_eq!(normalize("  Embers XD  "), "Embers XD");
+    }
+
+    #[test]
+    fn normalize_is_a_no_op_for_already_clean_ascii_names() {
+        assert_eq!(normalize("USSEP"), "USSEP");
+    }
+}
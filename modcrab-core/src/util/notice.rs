@@ -0,0 +1,138 @@
+//! User-facing notices: the one format modcrab uses for warnings, errors, and reports.
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::text::{FancyText, TextColor, TextStyle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoticePreset {
+    Error,
+    Warning,
+    Info,
+    Statistics,
+}
+
+impl NoticePreset {
+    fn prefix(&self) -> &'static str {
+        match self {
+            NoticePreset::Error => "ERROR",
+            NoticePreset::Warning => "WARNING",
+            NoticePreset::Info => "INFO",
+            NoticePreset::Statistics => "STATS",
+        }
+    }
+
+    fn color(&self) -> TextColor {
+        match self {
+            NoticePreset::Error => TextColor::Red,
+            NoticePreset::Warning => TextColor::Yellow,
+            NoticePreset::Info => TextColor::Blue,
+            NoticePreset::Statistics => TextColor::Green,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoticeField {
+    pub label: String,
+    pub content: String,
+}
+
+/// A single printable unit of feedback: a preset, a header describing the topic, and
+/// zero or more labeled fields with the details.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notice {
+    pub preset: NoticePreset,
+    pub header: String,
+    pub fields: Vec<NoticeField>,
+}
+
+impl Notice {
+    pub fn new(preset: NoticePreset, header: impl Into<String>) -> Self {
+        Self { preset, header: header.into(), fields: Vec::new() }
+    }
+
+    pub fn field(mut self, label: impl Into<String>, content: impl Into<String>) -> Self {
+        self.fields.push(NoticeField { label: label.into(), content: content.into() });
+        self
+    }
+
+    pub fn print(&self) {
+        let prefix = FancyText::new(self.preset.prefix()).color(self.preset.color()).style(TextStyle::Bold).stylize();
+        println!("[{prefix}] {}", self.header);
+        for field in &self.fields {
+            println!("    {}: {}", field.label, field.content);
+        }
+    }
+
+    /// The wire format consumed by the Modcrab GUI: `{"color", "prefix", "header",
+    /// "fields": [{"label", "content"}, ...]}`. Deliberately flatter than this struct's
+    /// own derived `Serialize`, since a GUI only needs enough to render the notice, not
+    /// to reconstruct a [`NoticePreset`] exactly.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "color": self.preset.color(),
+            "prefix": self.preset.prefix(),
+            "header": self.header,
+            "fields": self.fields,
+        })
+    }
+
+    /// Prints [`Notice::to_json`] as a single JSON line on stdout.
+    pub fn print_json(&self) {
+        println!("{}", self.to_json());
+    }
+
+    /// Reconstructs a [`Notice`] from [`Notice::to_json`]'s output, recovering the
+    /// preset from its `prefix` field. Returns `None` if the value isn't shaped like a
+    /// notice or `prefix` doesn't match a known preset.
+    pub fn from_json(v: &serde_json::Value) -> Option<Self> {
+        let preset = match v.get("prefix")?.as_str()? {
+            "ERROR" => NoticePreset::Error,
+            "WARNING" => NoticePreset::Warning,
+            "INFO" => NoticePreset::Info,
+            "STATS" => NoticePreset::Statistics,
+            _ => return None,
+        };
+        let header = v.get("header")?.as_str()?.to_owned();
+        let fields = v
+            .get("fields")?
+            .as_array()?
+            .iter()
+            .map(|f| Some(NoticeField { label: f.get("label")?.as_str()?.to_owned(), content: f.get("content")?.as_str()?.to_owned() }))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { preset, header, fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_json_matches_the_gui_wire_format() {
+        let notice = Notice::new(NoticePreset::Error, "IO").field("Description", "disk full");
+        let json = notice.to_json();
+        assert_eq!(json["color"], "red");
+        assert_eq!(json["prefix"], "ERROR");
+        assert_eq!(json["header"], "IO");
+        assert_eq!(json["fields"][0]["label"], "Description");
+        assert_eq!(json["fields"][0]["content"], "disk full");
+    }
+
+    #[test]
+    fn from_json_round_trips_through_to_json() {
+        let original = Notice::new(NoticePreset::Warning, "heads up").field("why", "just because");
+        let restored = Notice::from_json(&original.to_json()).unwrap();
+        assert_eq!(restored.preset, original.preset);
+        assert_eq!(restored.header, original.header);
+        assert_eq!(restored.fields.len(), 1);
+        assert_eq!(restored.fields[0].content, "just because");
+    }
+
+    #[test]
+    fn from_json_rejects_an_unrecognized_shape() {
+        assert!(Notice::from_json(&serde_json::json!({"not": "a notice"})).is_none());
+    }
+}
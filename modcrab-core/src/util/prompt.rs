@@ -0,0 +1,86 @@
+//! A minimal interactive prompt, generic over its input/output so callers can swap in
+//! a real terminal or, in tests, canned input with no terminal at all.
+
+use std::io::{self, BufRead, Write};
+
+/// Reads answers from `input`, echoing questions (and, for [`Self::select`], the
+/// option list) to `output`.
+pub struct Prompt<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Asks a free-form question, returning the trimmed line typed back, or `None` if
+    /// the answer was empty.
+    pub fn ask(&mut self, question: &str) -> io::Result<Option<String>> {
+        write!(self.output, "{question} ")?;
+        self.output.flush()?;
+
+        let mut line = String::new();
+        self.input.read_line(&mut line)?;
+        let answer = line.trim().to_owned();
+        Ok((!answer.is_empty()).then_some(answer))
+    }
+
+    /// Like [`Self::ask`], but an empty answer falls back to `default`.
+    pub fn ask_or(&mut self, question: &str, default: &str) -> io::Result<String> {
+        Ok(self.ask(question)?.unwrap_or_else(|| default.to_owned()))
+    }
+
+    /// Presents `options` as a 1-indexed numbered list and reads a selection,
+    /// re-prompting on anything that doesn't parse to a valid index.
+    pub fn select(&mut self, question: &str, options: &[&str]) -> io::Result<usize> {
+        writeln!(self.output, "{question}")?;
+        for (i, option) in options.iter().enumerate() {
+            writeln!(self.output, "  {}) {option}", i + 1)?;
+        }
+
+        loop {
+            if let Some(choice) = self.ask("choice:")?.and_then(|a| a.parse::<usize>().ok()) {
+                if choice >= 1 && choice <= options.len() {
+                    return Ok(choice - 1);
+                }
+            }
+            writeln!(self.output, "please enter a number between 1 and {}", options.len())?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn prompt(input: &str) -> Prompt<Cursor<&[u8]>, Vec<u8>> {
+        Prompt::new(Cursor::new(input.as_bytes()), Vec::new())
+    }
+
+    #[test]
+    fn ask_returns_none_for_an_empty_line() {
+        let mut p = prompt("\n");
+        assert_eq!(p.ask("name?").unwrap(), None);
+    }
+
+    #[test]
+    fn ask_or_falls_back_to_the_default_on_an_empty_line() {
+        let mut p = prompt("\n");
+        assert_eq!(p.ask_or("path?", "/default").unwrap(), "/default");
+    }
+
+    #[test]
+    fn ask_or_keeps_a_non_empty_answer() {
+        let mut p = prompt("/custom/path\n");
+        assert_eq!(p.ask_or("path?", "/default").unwrap(), "/custom/path");
+    }
+
+    #[test]
+    fn select_reprompts_past_invalid_input_before_accepting_a_valid_choice() {
+        let mut p = prompt("nope\n0\n99\n2\n");
+        assert_eq!(p.select("pick one:", &["A", "B", "C"]).unwrap(), 1);
+    }
+}
@@ -0,0 +1,124 @@
+//! Minimal ANSI styling helpers used to render [`crate::util::notice::Notice`]s.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Whether [`FancyText::stylize`] (and [`TextColor::ansi_fg`]/[`TextStyle::ansi`]) emit
+/// ANSI escapes at all. A global rather than a parameter threaded through every
+/// `Notice`, since notices are constructed all over the codebase with no natural place
+/// to carry a color setting — set once at startup by [`set_color_enabled`] (`modcrab`'s
+/// `--color` flag) and read on every call.
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables ANSI styling process-wide. Intended to be called once, before
+/// any `Notice` is printed.
+pub fn set_color_enabled(enabled: bool) {
+    COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether ANSI styling is currently enabled. See [`set_color_enabled`].
+pub fn color_enabled() -> bool {
+    COLOR_ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextColor {
+    Red,
+    Yellow,
+    Blue,
+    Green,
+}
+
+impl TextColor {
+    pub fn ansi_fg(&self) -> &'static str {
+        if !color_enabled() {
+            return "";
+        }
+        match self {
+            TextColor::Red => "\x1b[31m",
+            TextColor::Yellow => "\x1b[33m",
+            TextColor::Blue => "\x1b[34m",
+            TextColor::Green => "\x1b[32m",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextStyle {
+    Bold,
+}
+
+impl TextStyle {
+    pub fn ansi(&self) -> &'static str {
+        if !color_enabled() {
+            return "";
+        }
+        match self {
+            TextStyle::Bold => "\x1b[1m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+/// A piece of text with an optional color/style, rendered through [`FancyText::stylize`].
+pub struct FancyText<'a> {
+    pub content: &'a str,
+    pub color: Option<TextColor>,
+    pub style: Option<TextStyle>,
+}
+
+impl<'a> FancyText<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Self { content, color: None, style: None }
+    }
+
+    pub fn color(mut self, color: TextColor) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn style(mut self, style: TextStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn stylize(&self) -> String {
+        let mut out = String::new();
+        if let Some(style) = self.style {
+            out.push_str(style.ansi());
+        }
+        if let Some(color) = self.color {
+            out.push_str(color.ansi_fg());
+        }
+        out.push_str(self.content);
+        if color_enabled() && (self.color.is_some() || self.style.is_some()) {
+            out.push_str(RESET);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `COLOR_ENABLED` is process-global, so this test restores it in every exit path
+    // (including the early `return` from a failed assertion) rather than just at the
+    // end, so a later test in the same binary never observes it disabled.
+    #[test]
+    fn stylize_strips_every_ansi_code_once_color_is_disabled() {
+        set_color_enabled(false);
+        let result = std::panic::catch_unwind(|| {
+            let text = FancyText::new("warning").color(TextColor::Yellow).style(TextStyle::Bold);
+            assert_eq!(text.stylize(), "warning");
+            assert_eq!(TextColor::Red.ansi_fg(), "");
+            assert_eq!(TextStyle::Bold.ansi(), "");
+        });
+        set_color_enabled(true);
+        result.unwrap();
+    }
+}
@@ -0,0 +1,81 @@
+//! XDG Base Directory accessors for modcrab's own global state — things that live
+//! outside any single modpack's [`crate::modpack::PackLayout`], like the global config
+//! file. Each accessor resolves to `$XDG_*_HOME/modcrab`, falling back to the usual
+//! `~/.config`/`~/.cache`/`~/.local/state` when the env var is unset, and creates the
+//! directory on first use so a caller never has to `create_dir_all` it itself. Backed
+//! by the `dirs` crate, the same one [`crate::util::misc::replace_path_home_prefix`]
+//! already uses for `~` expansion.
+
+use std::io;
+use std::path::PathBuf;
+
+/// `$XDG_CONFIG_HOME/modcrab` (`~/.config/modcrab` by default). Home of the global
+/// config file; see [`crate::global_config::GlobalConfigPath`].
+pub fn config_dir() -> io::Result<PathBuf> {
+    base_dir(dirs::config_dir())
+}
+
+/// `$XDG_CACHE_HOME/modcrab` (`~/.cache/modcrab` by default). Reserved for a future
+/// download cache shared across every modpack on this machine — `downloads/` is
+/// currently kept per-pack under each [`crate::modpack::PackLayout`] instead.
+pub fn cache_dir() -> io::Result<PathBuf> {
+    base_dir(dirs::cache_dir())
+}
+
+/// `$XDG_STATE_HOME/modcrab` (`~/.local/state/modcrab` by default). Reserved for
+/// future session logs/history — modcrab doesn't write either yet.
+pub fn state_dir() -> io::Result<PathBuf> {
+    base_dir(dirs::state_dir())
+}
+
+fn base_dir(base: Option<PathBuf>) -> io::Result<PathBuf> {
+    let base = base.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not determine the user's home directory"))?;
+    let dir = base.join("modcrab");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // `dirs::*_dir` reads its XDG env var fresh on every call, which is what lets these
+    // tests override it directly instead of needing a fake home directory — but
+    // `std::env` is process-global, so tests that touch it share this lock to avoid
+    // racing each other under cargo's default parallel test runner.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn config_dir_honors_xdg_config_home_and_creates_it_on_first_use() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let root = std::env::temp_dir().join(format!("modcrab-xdg-config-test-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", &root);
+
+        let dir = config_dir().unwrap();
+
+        assert_eq!(dir, root.join("modcrab"));
+        assert!(dir.is_dir());
+
+        std::env::remove_var("XDG_CONFIG_HOME");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn cache_dir_and_state_dir_each_land_under_their_own_xdg_var() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let cache_root = std::env::temp_dir().join(format!("modcrab-xdg-cache-test-{}", std::process::id()));
+        let state_root = std::env::temp_dir().join(format!("modcrab-xdg-state-test-{}", std::process::id()));
+        std::env::set_var("XDG_CACHE_HOME", &cache_root);
+        std::env::set_var("XDG_STATE_HOME", &state_root);
+
+        assert_eq!(cache_dir().unwrap(), cache_root.join("modcrab"));
+        assert_eq!(state_dir().unwrap(), state_root.join("modcrab"));
+
+        std::env::remove_var("XDG_CACHE_HOME");
+        std::env::remove_var("XDG_STATE_HOME");
+        std::fs::remove_dir_all(&cache_root).unwrap();
+        std::fs::remove_dir_all(&state_root).unwrap();
+    }
+}
@@ -0,0 +1,1214 @@
+//! The [`fuse_mt::FilesystemMT`] implementation: translates every FUSE call into an
+//! operation against the [`VirtualFileTree`], then passes it through to the real file
+//! it resolves to.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use fuse_mt::{
+    CallbackResult, CreatedEntry, DirectoryEntry, FileAttr, FilesystemMT, RequestInfo, ResultCreate, ResultData, ResultEmpty, ResultEntry,
+    ResultOpen, ResultReaddir, ResultSlice, ResultWrite,
+};
+
+use crate::metrics::FsMetrics;
+use crate::shadow::ShadowedDirectory;
+use crate::tree::{FileType, MappingProgress, VirtualFileTree};
+
+/// [`VirtualFileTree`]'s [`FileType`] has no `fuse` dependency of its own, so this is
+/// the one place it's translated into `fuse_mt::FileType` for a `DirectoryEntry`/
+/// `FileAttr` the FUSE layer actually hands back to the kernel.
+fn to_fuse_file_type(kind: FileType) -> fuse_mt::FileType {
+    match kind {
+        FileType::NamedPipe => fuse_mt::FileType::NamedPipe,
+        FileType::CharDevice => fuse_mt::FileType::CharDevice,
+        FileType::BlockDevice => fuse_mt::FileType::BlockDevice,
+        FileType::Directory => fuse_mt::FileType::Directory,
+        FileType::RegularFile => fuse_mt::FileType::RegularFile,
+        FileType::Symlink => fuse_mt::FileType::Symlink,
+        FileType::Socket => fuse_mt::FileType::Socket,
+    }
+}
+
+const TTL: Duration = Duration::from_secs(1);
+
+/// Construction options for [`ModcrabFS`].
+#[derive(Debug, Clone)]
+pub struct ModcrabFSOptions {
+    pub num_threads: usize,
+    /// Virtual path components (matched case-insensitively against a path's first
+    /// segment) that write straight into the shadowed game root instead of the
+    /// writable surface. See [`ModcrabFS::is_passthrough`].
+    pub shadow_passthrough: Vec<String>,
+    /// `(pattern, bucket)` pairs from `modcrab.overwrite_rules`, first match wins:
+    /// routes a newly created/modified file whose virtual path matches `pattern` into
+    /// `bucket`, a subdirectory of the writable surface, instead of the surface's top
+    /// level. The virtual path itself is unaffected — only where it physically lands
+    /// under the surface changes. See [`ModcrabFS::overwrite_bucket`].
+    pub overwrite_rules: Vec<(String, String)>,
+    /// Whether `getattr`/`open` should resolve a relative symlink's target through the
+    /// merged [`VirtualFileTree`] rather than the real lower-layer directory it was
+    /// created against. On by default: a mod's symlinked directory should still see
+    /// whatever a higher layer currently shadows underneath it. `readlink` itself is
+    /// never affected — it always reports the symlink's stored target unchanged.
+    pub resolve_symlinks_through_overlay: bool,
+    /// Bits to clear from the mode `create`/`mkdir`/`mknod` are asked to create a file
+    /// with, applied in addition to whatever umask the calling process already applied
+    /// before the request ever reached FUSE. `None` (the default) passes the incoming
+    /// mode through unchanged. Useful when files the game itself writes need predictable
+    /// permissions regardless of what umask happens to be set in the environment running
+    /// the mount.
+    pub umask: Option<u32>,
+}
+
+impl Default for ModcrabFSOptions {
+    fn default() -> Self {
+        Self { num_threads: num_cpus_hint().min(4), shadow_passthrough: Vec::new(), overwrite_rules: Vec::new(), resolve_symlinks_through_overlay: true, umask: None }
+    }
+}
+
+impl ModcrabFSOptions {
+    /// Overrides the number of FUSE worker threads `mount`/`spawn_mount` will spin up.
+    /// The [`VirtualFileTree`]'s `RwLock` already handles concurrent access correctly,
+    /// so this is safe to raise on multi-core systems under heavy parallel IO.
+    pub fn num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Sets the `shadow_passthrough` list (see [`ModcrabFS::is_passthrough`]).
+    pub fn shadow_passthrough(mut self, shadow_passthrough: Vec<String>) -> Self {
+        self.shadow_passthrough = shadow_passthrough;
+        self
+    }
+
+    /// Sets the `overwrite_rules` list (see [`ModcrabFS::overwrite_bucket`]).
+    pub fn overwrite_rules(mut self, overwrite_rules: Vec<(String, String)>) -> Self {
+        self.overwrite_rules = overwrite_rules;
+        self
+    }
+
+    /// Overrides `resolve_symlinks_through_overlay` (on by default).
+    pub fn resolve_symlinks_through_overlay(mut self, resolve_symlinks_through_overlay: bool) -> Self {
+        self.resolve_symlinks_through_overlay = resolve_symlinks_through_overlay;
+        self
+    }
+
+    /// Sets `umask` (unset, i.e. pass incoming modes through unchanged, by default).
+    pub fn umask(mut self, umask: u32) -> Self {
+        self.umask = Some(umask);
+        self
+    }
+}
+
+fn num_cpus_hint() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// `path` with symlinks resolved, so two differently-spelled paths to the same
+/// directory compare equal in [`validate_layers`]. Falls back to `path` itself when it
+/// doesn't exist yet (or isn't readable) rather than failing the whole mount over
+/// it — [`crate::tree::VirtualFileTree::map_directory`] already tolerates a layer
+/// that's missing or vanishes mid-scan, just with a warning instead of an error.
+fn canonical_or(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Rejects a `layers`/`surface` combination that would leave [`ModcrabFS`] mapping the
+/// same real directory twice, or mapping the writable `surface` both as the top layer
+/// and, nested inside one of the read-only layers below it, as part of that layer too —
+/// every write would then also show up duplicated one level down, and a later
+/// `update_directory` of that lower layer could see (and try to map) `surface`'s own
+/// files. This tree has no separate on-disk transformation cache distinct from
+/// `surface` for `ModcrabFS::new` to take a path to, so `surface` is the one path here
+/// that actually needs to stay out of every layer.
+fn validate_layers(layers: &[PathBuf], surface: &Path) -> std::io::Result<()> {
+    let mut seen = HashSet::new();
+    for layer in layers {
+        if !seen.insert(canonical_or(layer)) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("duplicate overlay layer: {}", layer.display())));
+        }
+    }
+
+    let surface = canonical_or(surface);
+    if layers.iter().any(|layer| surface.starts_with(canonical_or(layer))) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the writable surface must not be inside an overlay layer directory"));
+    }
+
+    Ok(())
+}
+
+/// An overlay filesystem: `layers` are merged (lowest priority first) into a single
+/// [`VirtualFileTree`], with `surface` acting as the writable top layer for anything
+/// created while mounted. Opening an existing file for writing copies it up to
+/// `surface` first (see [`ModcrabFS::copy_up`]), so runtime writes always accumulate
+/// there instead of mutating a lower layer's file in place — except under a path
+/// matching `options.shadow_passthrough`, which writes straight into the shadowed game
+/// root instead, so it reappears there once unmounted (see [`ModcrabFS::is_passthrough`]).
+pub struct ModcrabFS {
+    tree: Arc<RwLock<VirtualFileTree>>,
+    surface: PathBuf,
+    shadow: ShadowedDirectory,
+    metrics: Arc<FsMetrics>,
+    options: ModcrabFSOptions,
+    next_fh: AtomicU64,
+    open_files: RwLock<HashMap<u64, std::fs::File>>,
+    /// Attributes prefetched by [`Self::readdir`] for a directory's children, keyed by
+    /// virtual path, so the flood of `getattr` calls a listing triggers (`ls -l` over a
+    /// texture directory with thousands of entries) can be served without a fresh
+    /// `lstat` each. Entries expire after [`TTL`], same as the attributes themselves.
+    attr_cache: RwLock<HashMap<PathBuf, (FileAttr, SystemTime)>>,
+}
+
+impl ModcrabFS {
+    /// Builds the merged tree from `layers` (lowest priority first) plus `surface` as
+    /// the top, writable layer. `base` is the real directory being mounted over; it's
+    /// opened via [`ShadowedDirectory`] so operations can still reach it directly.
+    pub fn new(base: &Path, layers: &[PathBuf], surface: &Path, options: ModcrabFSOptions) -> std::io::Result<Self> {
+        Self::new_with_progress(base, layers, &[], surface, options, &mut |_| {})
+    }
+
+    /// Like [`Self::new`], but calls `on_progress` after every file mapped across all
+    /// layers plus the surface, so a caller can render a spinner during a slow mount.
+    /// `hide` is indexed the same as `layers`: glob patterns (see
+    /// [`VirtualFileTree::hide_predicate`]) whose matches are left out of that layer's
+    /// contribution entirely. A layer with no entry in `hide` (including every layer
+    /// when `hide` is simply `&[]`) hides nothing.
+    pub fn new_with_progress(
+        base: &Path,
+        layers: &[PathBuf],
+        hide: &[Vec<String>],
+        surface: &Path,
+        options: ModcrabFSOptions,
+        on_progress: &mut dyn FnMut(MappingProgress),
+    ) -> std::io::Result<Self> {
+        validate_layers(layers, surface)?;
+
+        let mut tree = VirtualFileTree::from_layers(layers, hide, on_progress)?;
+        tree.map_directory_with_progress(surface, Path::new("/"), layers.len(), on_progress)?;
+        // Logs anything stale or inaccessible at `warn!` (see `audit_real_paths`); a
+        // mount proceeds regardless, the same way it already tolerates a layer
+        // disappearing mid-scan.
+        tree.audit_real_paths();
+
+        Ok(Self {
+            tree: Arc::new(RwLock::new(tree)),
+            surface: surface.to_path_buf(),
+            shadow: ShadowedDirectory::open(base)?,
+            metrics: Arc::new(FsMetrics::default()),
+            options,
+            next_fh: AtomicU64::new(1),
+            open_files: RwLock::new(HashMap::new()),
+            attr_cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn metrics(&self) -> &FsMetrics {
+        &self.metrics
+    }
+
+    /// A cloneable handle to this filesystem's metrics, for callers that need to read
+    /// them after `self` has been moved into [`mount`] or [`spawn_mount`].
+    pub fn metrics_handle(&self) -> Arc<FsMetrics> {
+        self.metrics.clone()
+    }
+
+    pub fn options(&self) -> &ModcrabFSOptions {
+        &self.options
+    }
+
+    /// A cloneable handle to the live [`VirtualFileTree`], for a caller that needs to
+    /// apply a config hot-reload (see `modcrab-cli`'s SIGHUP handling) after `self` has
+    /// been moved into [`mount`] or [`spawn_mount`] — same pattern as [`Self::metrics_handle`].
+    pub fn tree_handle(&self) -> Arc<RwLock<VirtualFileTree>> {
+        self.tree.clone()
+    }
+
+    /// Recovers from a poisoned lock rather than panicking: a single FUSE callback
+    /// panicking mid-mutation shouldn't cascade into every later callback panicking too,
+    /// taking the whole mount down in a way only a force-unmount can clear. The tree's
+    /// state at the moment of the panic is kept as-is — whatever partial mutation was in
+    /// flight — the same tradeoff `into_inner` always makes for a poisoned lock.
+    fn read_tree(&self) -> std::sync::RwLockReadGuard<'_, VirtualFileTree> {
+        self.tree.read().unwrap_or_else(|e| {
+            log::warn!("VirtualFileTree lock was poisoned by a panicking callback; recovering instead of panicking again");
+            e.into_inner()
+        })
+    }
+
+    fn write_tree(&self) -> std::sync::RwLockWriteGuard<'_, VirtualFileTree> {
+        self.tree.write().unwrap_or_else(|e| {
+            log::warn!("VirtualFileTree lock was poisoned by a panicking callback; recovering instead of panicking again");
+            e.into_inner()
+        })
+    }
+
+    fn real_path(&self, path: &Path) -> Result<PathBuf, libc::c_int> {
+        self.read_tree().real_path(path).ok_or(libc::ENOENT)
+    }
+
+    /// Like [`Self::real_path`], but additionally follows a symlink encountered while
+    /// descending through `path`'s parent directories when `resolve_symlinks_through_overlay`
+    /// is on (the default). Only `getattr`/`open` use this — `readlink` and every
+    /// mutating op still resolve the literal node at `path`, since substituting a
+    /// symlink's target there would mean creating, removing, or reporting the wrong file.
+    fn real_path_resolving_symlinks(&self, path: &Path) -> Result<PathBuf, libc::c_int> {
+        let tree = self.read_tree();
+        let idx = if self.options.resolve_symlinks_through_overlay {
+            tree.find_index_through_symlinks(path)
+        } else {
+            tree.find_index(path)
+        }
+        .ok_or(libc::ENOENT)?;
+        tree.data(idx).map(|d| d.real_path.clone()).ok_or(libc::ENOENT)
+    }
+
+    /// If `path` isn't registered in the [`VirtualFileTree`] at all, checks whether it
+    /// exists directly under the shadowed base directory anyway. Mapping only runs once,
+    /// at mount time, so a file Steam (or any other external tool) writes straight into
+    /// the game root afterwards has no node to be found through — this is the one place
+    /// `getattr`/`open`/`readdir` fall back to the real base itself rather than the tree.
+    fn shadow_fallback_path(&self, path: &Path) -> Option<PathBuf> {
+        let real = self.shadow.path().join(path.strip_prefix("/").unwrap_or(path));
+        std::fs::symlink_metadata(&real).ok().map(|_| real)
+    }
+
+    /// Clears `options.umask`'s bits from `mode`, for `create`/`mkdir`/`mknod` to apply
+    /// to the mode a caller asked for. A no-op when `umask` is unset.
+    fn masked_mode(&self, mode: u32) -> u32 {
+        match self.options.umask {
+            Some(umask) => mode & !umask,
+            None => mode,
+        }
+    }
+
+    fn surface_path(&self, parent: &Path, name: &OsStr) -> PathBuf {
+        self.surface_target_path(&parent.join(name))
+    }
+
+    /// The bucket (second element of the first matching `options.overwrite_rules` pair)
+    /// a virtual `path` should physically land under instead of the surface's top
+    /// level, if any rule matches. Matched the same way [`crate::tree::VirtualFileTree::hide_predicate`]
+    /// matches a hide pattern: case-insensitively, against the path relative to `/`,
+    /// with `*`/`?` free to cross directory separators.
+    fn overwrite_bucket(&self, path: &Path) -> Option<&str> {
+        let relative = path.strip_prefix("/").unwrap_or(path);
+        let text = relative.to_string_lossy();
+        let options = glob::MatchOptions { case_sensitive: false, require_literal_separator: false, require_literal_leading_dot: false };
+        self.options
+            .overwrite_rules
+            .iter()
+            .find(|(pattern, _)| glob::Pattern::new(pattern).is_ok_and(|p| p.matches_with(&text, options)))
+            .map(|(_, bucket)| bucket.as_str())
+    }
+
+    /// Where a virtual `path` lands on the writable surface: under a bucket
+    /// subdirectory when [`Self::overwrite_bucket`] matches, directly under `surface`
+    /// otherwise.
+    fn surface_target_path(&self, path: &Path) -> PathBuf {
+        let relative = path.strip_prefix("/").unwrap_or(path);
+        match self.overwrite_bucket(path) {
+            Some(bucket) => self.surface.join(bucket).join(relative),
+            None => self.surface.join(relative),
+        }
+    }
+
+    /// Whether `path`'s first component matches an entry in `shadow_passthrough`
+    /// (case-insensitively), meaning it should write straight into the shadowed game
+    /// root instead of the writable surface — for directories the game itself creates
+    /// at runtime (crash dumps, photo mode output) that users expect to find back in
+    /// the real game folder once unmounted, rather than stuck in `overwrite/`.
+    fn is_passthrough(&self, path: &Path) -> bool {
+        let Some(first) = path.strip_prefix("/").unwrap_or(path).components().next() else { return false };
+        let first = first.as_os_str().to_string_lossy();
+        self.options.shadow_passthrough.iter().any(|p| p.eq_ignore_ascii_case(&first))
+    }
+
+    /// Where a newly created/modified `path` should land: the shadowed game root for a
+    /// `shadow_passthrough` match, the writable surface otherwise.
+    fn write_target_path(&self, parent: &Path, name: &OsStr) -> PathBuf {
+        let virtual_path = parent.join(name);
+        if self.is_passthrough(&virtual_path) {
+            self.shadow.path().join(virtual_path.strip_prefix("/").unwrap_or(&virtual_path))
+        } else {
+            self.surface_path(parent, name)
+        }
+    }
+
+    /// If `real` isn't already under `self.surface` or the shadowed game root, copies
+    /// it to whichever of those `path` should land on (see [`Self::is_passthrough`])
+    /// and repoints `path` at the copy before a write touches it, so writing to a file
+    /// that currently resolves to a lower layer lands there instead of mutating that
+    /// layer's original file in place.
+    fn copy_up(&self, path: &Path, real: &Path) -> Result<PathBuf, libc::c_int> {
+        if real.starts_with(&self.surface) || real.starts_with(self.shadow.path()) {
+            return Ok(real.to_path_buf());
+        }
+
+        let dest = if self.is_passthrough(path) {
+            self.shadow.path().join(path.strip_prefix("/").unwrap_or(path))
+        } else {
+            self.surface_target_path(path)
+        };
+        if let Some(dir) = dest.parent() {
+            std::fs::create_dir_all(dir).map_err(|_| libc::EIO)?;
+        }
+        std::fs::copy(real, &dest).map_err(|_| libc::EIO)?;
+        self.write_tree().register_path(path, dest.clone(), usize::MAX, FileType::RegularFile);
+        Ok(dest)
+    }
+
+    fn next_handle(&self) -> u64 {
+        self.next_fh.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// A still-fresh attribute prefetched for `path` by a recent [`Self::readdir`], if
+    /// one exists. Expired entries are left for the next prefetch to overwrite rather
+    /// than swept eagerly, since an `RwLock<HashMap>` scan on every miss would cost
+    /// more than the stale entries it would reclaim.
+    fn cached_attr(&self, path: &Path) -> Option<FileAttr> {
+        let cache = self.attr_cache.read().unwrap_or_else(|e| e.into_inner());
+        let (attr, cached_at) = cache.get(path)?;
+        if cached_at.elapsed().unwrap_or(Duration::MAX) >= TTL {
+            return None;
+        }
+        self.metrics.record_attr_cache_hit();
+        Some(*attr)
+    }
+
+    /// Looks up `real`'s attributes with a real `lstat` and stashes the result under
+    /// `path` for [`Self::cached_attr`] to serve later `getattr` calls from.
+    fn stat_and_cache(&self, path: &Path, real: &Path) -> Result<FileAttr, libc::c_int> {
+        let meta = std::fs::symlink_metadata(real).map_err(|_| libc::ENOENT)?;
+        self.metrics.record_lstat();
+        let attr = Self::stat_to_attr(&meta);
+        self.attr_cache.write().unwrap_or_else(|e| e.into_inner()).insert(path.to_path_buf(), (attr, SystemTime::now()));
+        Ok(attr)
+    }
+
+    /// Drops any attribute [`Self::cached_attr`] prefetched for `path`, so a `getattr`
+    /// right after a mutation doesn't serve a pre-mutation size/mtime for the rest of
+    /// the TTL window. Every FUSE call that changes what `lstat` would report for a
+    /// path — `write`, `create`, `mkdir`, `mknod`, `symlink`, `link`, `unlink`, `rmdir`
+    /// — calls this instead of leaving the stale entry for the TTL to expire naturally.
+    fn invalidate_attr_cache(&self, path: &Path) {
+        self.attr_cache.write().unwrap_or_else(|e| e.into_inner()).remove(path);
+    }
+
+    fn stat_to_attr(meta: &std::fs::Metadata) -> FileAttr {
+        use std::os::unix::fs::MetadataExt;
+        let kind = if meta.is_dir() {
+            fuse_mt::FileType::Directory
+        } else if meta.file_type().is_symlink() {
+            fuse_mt::FileType::Symlink
+        } else {
+            fuse_mt::FileType::RegularFile
+        };
+        FileAttr {
+            size: meta.size(),
+            blocks: meta.blocks(),
+            atime: meta.accessed().unwrap_or(SystemTime::UNIX_EPOCH),
+            mtime: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            ctime: SystemTime::UNIX_EPOCH + Duration::from_secs(meta.ctime() as u64),
+            crtime: SystemTime::UNIX_EPOCH,
+            kind,
+            perm: (meta.mode() & 0o7777) as u16,
+            nlink: meta.nlink() as u32,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            rdev: meta.rdev() as u32,
+            flags: 0,
+        }
+    }
+}
+
+impl FilesystemMT for ModcrabFS {
+    fn statfs(&self, _req: RequestInfo, _path: &Path) -> fuse_mt::ResultStatfs {
+        log::trace!("FUSE statfs {}", _path.display());
+        let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(self.shadow.raw_fd()) };
+        let stat = nix::sys::statvfs::fstatvfs(fd).map_err(|_| libc::EIO)?;
+        Ok(fuse_mt::Statfs {
+            blocks: stat.blocks(),
+            bfree: stat.blocks_free(),
+            bavail: stat.blocks_available(),
+            files: stat.files(),
+            ffree: stat.files_free(),
+            bsize: stat.block_size() as u32,
+            namelen: stat.name_max() as u32,
+            frsize: stat.fragment_size() as u32,
+        })
+    }
+
+    fn getattr(&self, _req: RequestInfo, path: &Path, _fh: Option<u64>) -> ResultEntry {
+        log::trace!("FUSE getattr {}", path.display());
+        if let Some(attr) = self.cached_attr(path) {
+            return Ok((TTL, attr));
+        }
+        let real = match self.real_path_resolving_symlinks(path) {
+            Ok(real) => real,
+            Err(_) => self.shadow_fallback_path(path).ok_or(libc::ENOENT)?,
+        };
+        let attr = self.stat_and_cache(path, &real)?;
+        Ok((TTL, attr))
+    }
+
+    fn readlink(&self, _req: RequestInfo, path: &Path) -> ResultData {
+        log::trace!("FUSE readlink {}", path.display());
+        let real = self.real_path(path)?;
+        let target = std::fs::read_link(&real).map_err(|_| libc::ENOENT)?;
+        Ok(target.as_os_str().as_bytes().to_vec())
+    }
+
+    /// Only ever takes [`Self::read_tree`]'s read lock, released before [`Self::next_handle`]
+    /// is even called — a listing under this handle doesn't snapshot anything here, it
+    /// re-reads the tree fresh on every [`Self::readdir`] call instead (see the doc comment
+    /// there), so there's no tree write lock on the open/close path for directories to
+    /// move to a lock of its own in the first place.
+    fn opendir(&self, _req: RequestInfo, path: &Path, _flags: u32) -> ResultOpen {
+        log::trace!("FUSE opendir {}", path.display());
+        let kind = {
+            let tree = self.read_tree();
+            let idx = tree.find_index(path).ok_or(libc::ENOENT)?;
+            tree.data(idx).map(|d| d.kind)
+        };
+        if kind != Some(FileType::Directory) {
+            return Err(libc::ENOTDIR);
+        }
+        Ok((self.next_handle(), 0))
+    }
+
+    /// Listings are fully live: each call re-reads [`Self::tree`] rather than snapshotting
+    /// the entry list at [`Self::opendir`] time, so a `create`/`unlink` made through the
+    /// same (or a different) handle while a directory is open is visible on the very
+    /// next `readdir`. The flip side is an `rm -r`-style loop (`readdir`, `unlink` each
+    /// entry, `readdir` again to check for stragglers) can legitimately see an entry on
+    /// one `readdir` that a concurrent or prior pass already unlinked — `unlink` treats
+    /// that as success rather than `ENOENT`, so the loop terminates instead of erroring.
+    /// At debug log level, also logs each entry's winning layer and its full
+    /// [`VirtualFileTree::which_layers_contain`] history, for watching conflict
+    /// resolution live while mounted.
+    fn readdir(&self, _req: RequestInfo, path: &Path, _fh: u64) -> ResultReaddir {
+        log::trace!("FUSE readdir {}", path.display());
+        let children = self.read_tree().children(path).into_iter().cloned().collect::<Vec<_>>();
+
+        // Conflict-resolution provenance, for watching which mod's file actually won
+        // at each path while the overlay is mounted instead of rebuilding the tree
+        // offline with `tree`/`which`-style tooling. Gated on the log level so a
+        // release build's hot listing path never pays for the `which_layers_contain`
+        // walk when nothing would consume it.
+        if log::log_enabled!(log::Level::Debug) {
+            let tree = self.read_tree();
+            for data in &children {
+                let history = tree.which_layers_contain(&data.virtual_path);
+                log::debug!("readdir {}: layer {} wins (history: {history:?})", data.virtual_path.display(), data.layer_idx);
+            }
+        }
+
+        let mut entries: Vec<DirectoryEntry> = children
+            .iter()
+            .map(|data| DirectoryEntry { name: data.virtual_path.file_name().unwrap_or_default().to_os_string(), kind: to_fuse_file_type(data.kind) })
+            .collect();
+
+        // Anything written straight into the shadowed base after this mount started
+        // (Steam updating the game, say) has no node in the tree at all — mapping only
+        // runs once, at mount time — so list it anyway by scanning the base directory
+        // directly and adding whatever the tree doesn't already account for.
+        let known: std::collections::HashSet<&OsStr> = children.iter().filter_map(|data| data.virtual_path.file_name()).collect();
+        let shadow_dir = self.shadow.path().join(path.strip_prefix("/").unwrap_or(path));
+        if let Ok(real_entries) = std::fs::read_dir(&shadow_dir) {
+            for entry in real_entries.flatten() {
+                let name = entry.file_name();
+                if known.contains(name.as_os_str()) {
+                    continue;
+                }
+                let Ok(meta) = entry.metadata() else { continue };
+                let kind = if meta.is_dir() {
+                    fuse_mt::FileType::Directory
+                } else if meta.file_type().is_symlink() {
+                    fuse_mt::FileType::Symlink
+                } else {
+                    fuse_mt::FileType::RegularFile
+                };
+                entries.push(DirectoryEntry { name, kind });
+            }
+        }
+
+        // readdirplus-style prefetch: batch-lstat every child now, while they're all
+        // known, so the getattr flood a listing triggers afterwards (one per entry,
+        // often more than once per entry) hits the cache instead of re-statting.
+        for data in &children {
+            let _ = self.stat_and_cache(&data.virtual_path, &data.real_path);
+        }
+
+        Ok(entries)
+    }
+
+    fn releasedir(&self, _req: RequestInfo, _path: &Path, _fh: u64, _flags: u32) -> ResultEmpty {
+        Ok(())
+    }
+
+    fn open(&self, _req: RequestInfo, path: &Path, flags: u32) -> ResultOpen {
+        log::trace!("FUSE open {}", path.display());
+        let real = match self.real_path_resolving_symlinks(path) {
+            Ok(real) => real,
+            Err(_) => self.shadow_fallback_path(path).ok_or(libc::ENOENT)?,
+        };
+        let meta = std::fs::symlink_metadata(&real).map_err(|_| libc::ENOENT)?;
+        if meta.is_dir() {
+            return Err(libc::EISDIR);
+        }
+        let wants_write = flags & (libc::O_WRONLY | libc::O_RDWR) as u32 != 0;
+        let real = if wants_write { self.copy_up(path, &real)? } else { real };
+        let file = std::fs::OpenOptions::new().read(true).write(wants_write).open(&real).map_err(|_| libc::EIO)?;
+        let fh = self.next_handle();
+        self.open_files.write().unwrap_or_else(|e| e.into_inner()).insert(fh, file);
+        Ok((fh, 0))
+    }
+
+    fn read(&self, _req: RequestInfo, _path: &Path, fh: u64, offset: u64, size: u32, callback: impl FnOnce(ResultSlice<'_>) -> CallbackResult) -> CallbackResult {
+        log::trace!("FUSE read {} offset={offset} size={size}", _path.display());
+        use std::io::{Read, Seek, SeekFrom};
+
+        // A game streaming assets issues a `read` per chunk, and a fresh `Vec<u8>`
+        // allocation for every one of them churns the allocator hard at that rate.
+        // Each FUSE worker thread keeps its own buffer instead, grown once to the
+        // largest request it's seen and reused from then on.
+        thread_local! {
+            static BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+        }
+
+        let size = size as usize;
+        BUFFER.with(|cell| {
+            let mut buf = cell.borrow_mut();
+            if buf.len() < size {
+                buf.resize(size, 0);
+            }
+
+            let mut files = self.open_files.write().unwrap_or_else(|e| e.into_inner());
+            let Some(file) = files.get_mut(&fh) else {
+                return callback(Err(libc::EBADF));
+            };
+            if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+                return callback(Err(e.raw_os_error().unwrap_or(libc::EIO)));
+            }
+
+            // Loops rather than trusting a single `read` call: a short read partway
+            // through the buffer (a signal, a network filesystem underneath a layer)
+            // would otherwise hand the game fewer bytes than were actually there to
+            // read, the same failure mode `write` had with short writes.
+            let mut read_total = 0;
+            while read_total < size {
+                match file.read(&mut buf[read_total..size]) {
+                    Ok(0) => break,
+                    Ok(n) => read_total += n,
+                    Err(e) => return callback(Err(e.raw_os_error().unwrap_or(libc::EIO))),
+                }
+            }
+            drop(files);
+
+            self.metrics.record_read(read_total as u64);
+            callback(Ok(&buf[..read_total]))
+        })
+    }
+
+    fn write(&self, _req: RequestInfo, _path: &Path, fh: u64, offset: u64, data: Vec<u8>, _flags: u32) -> ResultWrite {
+        log::trace!("FUSE write {} offset={offset} len={}", _path.display(), data.len());
+        let mut files = self.open_files.write().unwrap_or_else(|e| e.into_inner());
+        let file = files.get_mut(&fh).ok_or(libc::EBADF)?;
+
+        // `pwrite` rather than `seek` + `write` so a short write (a signal, a
+        // network filesystem underneath a layer) never leaves the file's cursor
+        // and the kernel's idea of how much landed out of sync. We loop until the
+        // whole buffer is written rather than trusting a single call, and keep the
+        // real errno (ENOSPC, EDQUOT, ...) on a short write instead of collapsing
+        // it to EIO, since that's what makes a game's own save-failure dialog say
+        // something sensible. If a later iteration fails after some bytes already
+        // landed on disk, we still report that partial count rather than an error —
+        // real `write(2)` does the same, and reporting 0 here would tell the kernel
+        // (and whatever wrote the data) that nothing happened when the file was
+        // actually mutated.
+        let mut written = 0usize;
+        while written < data.len() {
+            let n = match nix::sys::uio::pwrite(&*file, &data[written..], offset as i64 + written as i64) {
+                Ok(n) => n,
+                Err(e) => {
+                    if written > 0 {
+                        self.invalidate_attr_cache(_path);
+                        self.metrics.record_write(written as u64);
+                        return Ok(written as u32);
+                    }
+                    return Err(e as i32);
+                }
+            };
+            if n == 0 {
+                if written > 0 {
+                    break;
+                }
+                return Err(libc::ENOSPC);
+            }
+            written += n;
+        }
+
+        self.invalidate_attr_cache(_path);
+        self.metrics.record_write(written as u64);
+        Ok(written as u32)
+    }
+
+    fn release(&self, _req: RequestInfo, _path: &Path, fh: u64, _flags: u32, _lock_owner: u64, _flush: bool) -> ResultEmpty {
+        self.open_files.write().unwrap_or_else(|e| e.into_inner()).remove(&fh);
+        Ok(())
+    }
+
+    fn create(&self, _req: RequestInfo, parent: &Path, name: &OsStr, mode: u32, flags: u32) -> ResultCreate {
+        let real = self.write_target_path(parent, name);
+        if let Some(dir) = real.parent() {
+            std::fs::create_dir_all(dir).map_err(|_| libc::EIO)?;
+        }
+        let mut opts = std::fs::OpenOptions::new();
+        opts.read(true).write(true).create(true).truncate(true);
+        std::os::unix::fs::OpenOptionsExt::mode(&mut opts, self.masked_mode(mode));
+        let file = opts.open(&real).map_err(|_| libc::EIO)?;
+
+        let meta = file.metadata().map_err(|_| libc::EIO)?;
+        let virtual_path = parent.join(name);
+        let surface_idx = self.write_tree().register_path(&virtual_path, real, usize::MAX, FileType::RegularFile);
+        let _ = surface_idx;
+        self.invalidate_attr_cache(&virtual_path);
+
+        let fh = self.next_handle();
+        self.open_files.write().unwrap_or_else(|e| e.into_inner()).insert(fh, file);
+        Ok(CreatedEntry { ttl: TTL, attr: Self::stat_to_attr(&meta), fh, flags })
+    }
+
+    fn mkdir(&self, _req: RequestInfo, parent: &Path, name: &OsStr, mode: u32) -> ResultEntry {
+        log::trace!("FUSE mkdir {}/{}", parent.display(), name.to_string_lossy());
+        let real = self.write_target_path(parent, name);
+        std::fs::create_dir_all(&real).map_err(|_| libc::EIO)?;
+        std::fs::set_permissions(&real, std::os::unix::fs::PermissionsExt::from_mode(self.masked_mode(mode))).map_err(|_| libc::EIO)?;
+        let meta = std::fs::symlink_metadata(&real).map_err(|_| libc::EIO)?;
+        let virtual_path = parent.join(name);
+        self.write_tree().register_path(&virtual_path, real, usize::MAX, FileType::Directory);
+        self.invalidate_attr_cache(&virtual_path);
+        Ok((TTL, Self::stat_to_attr(&meta)))
+    }
+
+    /// Creates a special file (a FIFO, device node, or socket — never a regular file or
+    /// directory, which `create`/`mkdir` handle instead) at `parent`/`name` with `mode`'s
+    /// type bits (`S_IFCHR`/`S_IFBLK`/`S_IFIFO`/`S_IFSOCK`) determining its kind.
+    fn mknod(&self, _req: RequestInfo, parent: &Path, name: &OsStr, mode: u32, rdev: u32) -> ResultEntry {
+        log::trace!("FUSE mknod {}/{}", parent.display(), name.to_string_lossy());
+        let real = self.write_target_path(parent, name);
+        if let Some(dir) = real.parent() {
+            std::fs::create_dir_all(dir).map_err(|_| libc::EIO)?;
+        }
+
+        let mode = self.masked_mode(mode);
+        let kind = nix::sys::stat::SFlag::from_bits_truncate(mode & libc::S_IFMT);
+        let perm = nix::sys::stat::Mode::from_bits_truncate(mode & !libc::S_IFMT);
+        nix::sys::stat::mknod(&real, kind, perm, rdev as u64).map_err(|_| libc::EIO)?;
+
+        let file_type = if kind.contains(nix::sys::stat::SFlag::S_IFCHR) {
+            FileType::CharDevice
+        } else if kind.contains(nix::sys::stat::SFlag::S_IFBLK) {
+            FileType::BlockDevice
+        } else if kind.contains(nix::sys::stat::SFlag::S_IFIFO) {
+            FileType::NamedPipe
+        } else if kind.contains(nix::sys::stat::SFlag::S_IFSOCK) {
+            FileType::Socket
+        } else {
+            FileType::RegularFile
+        };
+
+        let meta = std::fs::symlink_metadata(&real).map_err(|_| libc::EIO)?;
+        let virtual_path = parent.join(name);
+        self.write_tree().register_path(&virtual_path, real, usize::MAX, file_type);
+        self.invalidate_attr_cache(&virtual_path);
+        Ok((TTL, Self::stat_to_attr(&meta)))
+    }
+
+    /// Idempotent: a `virtual_path` already missing from the tree (raced away by a
+    /// prior `unlink`, through this handle or another) is treated as already-removed
+    /// rather than `ENOENT`, matching [`Self::readdir`]'s fully-live semantics — a loop
+    /// of `readdir` + `unlink` each entry should converge instead of tripping on a
+    /// straggler entry someone else already cleaned up.
+    fn unlink(&self, _req: RequestInfo, parent: &Path, name: &OsStr) -> ResultEmpty {
+        log::trace!("FUSE unlink {}/{}", parent.display(), name.to_string_lossy());
+        let virtual_path = parent.join(name);
+        if let Ok(real) = self.real_path(&virtual_path) {
+            match std::fs::remove_file(&real) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(_) => return Err(libc::EIO),
+            }
+        }
+        self.write_tree().remove_file(&virtual_path);
+        self.invalidate_attr_cache(&virtual_path);
+        Ok(())
+    }
+
+    fn rmdir(&self, _req: RequestInfo, parent: &Path, name: &OsStr) -> ResultEmpty {
+        log::trace!("FUSE rmdir {}/{}", parent.display(), name.to_string_lossy());
+        let virtual_path = parent.join(name);
+        if !self.read_tree().children(&virtual_path).is_empty() {
+            return Err(libc::ENOTEMPTY);
+        }
+        if let Ok(real) = self.real_path(&virtual_path) {
+            match std::fs::remove_dir(&real) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(_) => return Err(libc::EIO),
+            }
+        }
+        self.write_tree().remove_file(&virtual_path);
+        self.invalidate_attr_cache(&virtual_path);
+        Ok(())
+    }
+
+    fn symlink(&self, _req: RequestInfo, parent: &Path, name: &OsStr, target: &Path) -> ResultEntry {
+        let real = self.write_target_path(parent, name);
+        std::os::unix::fs::symlink(target, &real).map_err(|_| libc::EIO)?;
+        let meta = std::fs::symlink_metadata(&real).map_err(|_| libc::EIO)?;
+        let virtual_path = parent.join(name);
+        self.write_tree().register_path(&virtual_path, real, usize::MAX, FileType::Symlink);
+        self.invalidate_attr_cache(&virtual_path);
+        Ok((TTL, Self::stat_to_attr(&meta)))
+    }
+
+    /// Hard-links `path` to `newparent`/`newname`. The source can live in any layer,
+    /// not just the surface, so a plain `fs::hard_link` between the two real paths
+    /// fails with `EXDEV` whenever the source's layer sits on a different underlying
+    /// filesystem than `surface` — which is the common case for a read-only lower
+    /// layer mounted from elsewhere. On `EXDEV` this falls back to a real copy
+    /// (logged at debug level, since it silently costs disk space a true hard link
+    /// wouldn't) and registers the copy as its own independent node rather than a
+    /// link, so editing it afterwards can't reach back into the source's layer.
+    fn link(&self, _req: RequestInfo, path: &Path, newparent: &Path, newname: &OsStr) -> ResultEntry {
+        let source = self.real_path(path)?;
+        let source_idx = self.read_tree().find_index(path).ok_or(libc::ENOENT)?;
+        let dest = self.write_target_path(newparent, newname);
+        if let Some(dir) = dest.parent() {
+            std::fs::create_dir_all(dir).map_err(|_| libc::EIO)?;
+        }
+        let virtual_path = newparent.join(newname);
+
+        match std::fs::hard_link(&source, &dest) {
+            Ok(()) => {
+                self.write_tree().register_link(&virtual_path, source_idx);
+            }
+            Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+                log::debug!("hard link {source:?} -> {dest:?} crossed a filesystem boundary (EXDEV); falling back to a copy");
+                std::fs::copy(&source, &dest).map_err(|_| libc::EIO)?;
+                let kind = self.read_tree().data(source_idx).map(|d| d.kind).unwrap_or(FileType::RegularFile);
+                self.write_tree().register_path(&virtual_path, dest.clone(), usize::MAX, kind);
+            }
+            Err(_) => return Err(libc::EIO),
+        }
+
+        // The source's own link count just changed too, not only the new name's attrs.
+        self.invalidate_attr_cache(path);
+        self.invalidate_attr_cache(&virtual_path);
+
+        let meta = std::fs::symlink_metadata(&dest).map_err(|_| libc::EIO)?;
+        Ok((TTL, Self::stat_to_attr(&meta)))
+    }
+}
+
+/// Mounts `fs` at `mountpoint`, blocking until it's unmounted.
+pub fn mount(fs: ModcrabFS, mountpoint: &Path) -> std::io::Result<()> {
+    let threads = fs.options.num_threads;
+    fuse_mt::mount(fuse_mt::FuseMT::new(fs, threads), mountpoint, &[])
+}
+
+/// Mounts `fs` at `mountpoint` in the background, returning a handle that unmounts on drop.
+pub fn spawn_mount(fs: ModcrabFS, mountpoint: &Path) -> std::io::Result<fuser::BackgroundSession> {
+    let threads = fs.options.num_threads;
+    fuse_mt::spawn_mount(fuse_mt::FuseMT::new(fs, threads), mountpoint, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_fs(shadow_passthrough: Vec<String>) -> (std::path::PathBuf, ModcrabFS) {
+        let dir = std::env::temp_dir().join(format!("modcrab-passthrough-test-{}-{}", std::process::id(), shadow_passthrough.len()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let options = ModcrabFSOptions::default().shadow_passthrough(shadow_passthrough);
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), options).unwrap();
+        (dir, fs)
+    }
+
+    #[test]
+    fn surface_path_routes_a_matching_virtual_path_into_its_overwrite_bucket() {
+        let dir = std::env::temp_dir().join(format!("modcrab-overwrite-rules-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let options = ModcrabFSOptions::default()
+            .overwrite_rules(vec![("skse/plugins/*.log".to_owned(), "logs".to_owned()), ("meshes/actors/**".to_owned(), "generated/facegen".to_owned())]);
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), options).unwrap();
+
+        assert_eq!(fs.surface_path(Path::new("/SKSE/Plugins"), OsStr::new("crash.log")), dir.join("overwrite/logs/SKSE/Plugins/crash.log"));
+        assert_eq!(
+            fs.surface_path(Path::new("/meshes/actors/character"), OsStr::new("npc.nif")),
+            dir.join("overwrite/generated/facegen/meshes/actors/character/npc.nif")
+        );
+        assert_eq!(fs.surface_path(Path::new("/"), OsStr::new("save.sav")), dir.join("overwrite/save.sav"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn is_passthrough_matches_the_first_component_case_insensitively() {
+        let (dir, fs) = test_fs(vec!["Crash Dumps".to_owned()]);
+
+        assert!(fs.is_passthrough(Path::new("/crash dumps/today.dmp")));
+        assert!(fs.is_passthrough(Path::new("/Crash Dumps")));
+        assert!(!fs.is_passthrough(Path::new("/Photos/shot.png")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_target_path_routes_passthrough_paths_to_the_shadowed_base() {
+        let (dir, fs) = test_fs(vec!["Crash Dumps".to_owned()]);
+
+        let passthrough = fs.write_target_path(Path::new("/Crash Dumps"), OsStr::new("today.dmp"));
+        assert_eq!(passthrough, dir.join("base/Crash Dumps/today.dmp"));
+
+        let ordinary = fs.write_target_path(Path::new("/"), OsStr::new("save.sav"));
+        assert_eq!(ordinary, dir.join("overwrite/save.sav"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn new_rejects_two_layers_that_resolve_to_the_same_real_directory() {
+        let dir = std::env::temp_dir().join(format!("modcrab-duplicate-layer-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("mod")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let layers = [dir.join("mod"), dir.join("./mod")];
+        let Err(err) = ModcrabFS::new(&dir.join("base"), &layers, &dir.join("overwrite"), ModcrabFSOptions::default()) else { panic!("expected an error") };
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("duplicate overlay layer"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn new_rejects_a_surface_nested_inside_one_of_the_overlay_layers() {
+        let dir = std::env::temp_dir().join(format!("modcrab-nested-surface-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("mod/overwrite")).unwrap();
+
+        let layers = [dir.join("mod")];
+        let Err(err) = ModcrabFS::new(&dir.join("base"), &layers, &dir.join("mod/overwrite"), ModcrabFSOptions::default()) else { panic!("expected an error") };
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("writable surface"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn req() -> RequestInfo {
+        RequestInfo { unique: 0, uid: 0, gid: 0, pid: 0 }
+    }
+
+    /// Without invalidation, a `getattr` right after a `write` would still be inside
+    /// the cache's 1-second TTL and serve the pre-write size `readdir`/`getattr`
+    /// prefetched earlier — exactly what a game re-stat-ing a save right after writing
+    /// it would trip over.
+    #[test]
+    fn getattr_reflects_a_write_even_within_the_attr_cache_s_ttl() {
+        let dir = std::env::temp_dir().join(format!("modcrab-attr-cache-invalidation-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), ModcrabFSOptions::default()).unwrap();
+        let created = fs.create(req(), Path::new("/"), OsStr::new("save.sav"), 0o666, libc::O_RDWR as u32).unwrap();
+
+        let (_, before) = fs.getattr(req(), Path::new("/save.sav"), None).unwrap();
+        assert_eq!(before.size, 0);
+
+        fs.write(req(), Path::new("/save.sav"), created.fh, 0, vec![b'x'; 4096], 0).unwrap();
+
+        let (_, after) = fs.getattr(req(), Path::new("/save.sav"), None).unwrap();
+        assert_eq!(after.size, 4096, "getattr should see the write's new size, not a stale cached one");
+
+        fs.release(req(), Path::new("/save.sav"), created.fh, 0, 0, false).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Puts the base layer on `/tmp` and the surface on `/dev/shm`, which are
+    /// genuinely different filesystems in this sandbox, so `link`'s `fs::hard_link`
+    /// call really does fail with `EXDEV` instead of us having to fake the errno.
+    #[test]
+    fn link_falls_back_to_a_copy_when_the_source_and_surface_are_on_different_filesystems() {
+        let unique = format!("modcrab-exdev-test-{}", std::process::id());
+        let base = std::env::temp_dir().join(&unique).join("base");
+        let layer = std::env::temp_dir().join(&unique).join("layer");
+        let surface = Path::new("/dev/shm").join(&unique).join("overwrite");
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::create_dir_all(&layer).unwrap();
+        std::fs::create_dir_all(&surface).unwrap();
+        std::fs::write(layer.join("source.txt"), b"original content").unwrap();
+
+        let fs = ModcrabFS::new(&base, std::slice::from_ref(&layer), &surface, ModcrabFSOptions::default()).unwrap();
+
+        fs.link(req(), Path::new("/source.txt"), Path::new("/"), OsStr::new("linked.txt")).unwrap();
+
+        let linked_real = fs.real_path(Path::new("/linked.txt")).unwrap();
+        assert_eq!(std::fs::read(&linked_real).unwrap(), b"original content");
+        assert!(linked_real.starts_with(&surface), "an EXDEV fallback should land the copy on the surface, not alias the source's layer");
+
+        std::fs::write(&linked_real, b"edited copy").unwrap();
+        assert_eq!(
+            std::fs::read(layer.join("source.txt")).unwrap(),
+            b"original content",
+            "editing the copy must not affect the lower-layer source it was copied from"
+        );
+
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+        std::fs::remove_dir_all(surface.parent().unwrap()).unwrap();
+    }
+
+    /// A mod's `Link -> ../Shared` pointing at a directory a higher layer partially
+    /// shadows, so opening through the symlink must see the higher layer's content,
+    /// not the lower layer's original file the symlink was created against.
+    #[test]
+    fn open_resolves_a_relative_symlink_through_the_overlay_instead_of_the_real_layer() {
+        let dir = std::env::temp_dir().join(format!("modcrab-symlink-overlay-test-{}", std::process::id()));
+        let lower = dir.join("lower");
+        let higher = dir.join("higher");
+        std::fs::create_dir_all(lower.join("Mod")).unwrap();
+        std::fs::create_dir_all(lower.join("Shared")).unwrap();
+        std::fs::create_dir_all(higher.join("Shared")).unwrap();
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+        std::fs::write(lower.join("Shared/foo.dds"), b"original texture").unwrap();
+        std::os::unix::fs::symlink("../Shared", lower.join("Mod/Link")).unwrap();
+        std::fs::write(higher.join("Shared/foo.dds"), b"replacement texture").unwrap();
+
+        let layers = vec![lower.clone(), higher.clone()];
+        let fs = ModcrabFS::new(&dir.join("base"), &layers, &dir.join("overwrite"), ModcrabFSOptions::default()).unwrap();
+
+        let real = fs.real_path_resolving_symlinks(Path::new("/Mod/Link/foo.dds")).unwrap();
+        assert_eq!(std::fs::read(&real).unwrap(), b"replacement texture", "open through the symlink must see the higher layer's shadowing file");
+        fs.open(req(), Path::new("/Mod/Link/foo.dds"), libc::O_RDONLY as u32).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// With the option off, a path through a symlinked directory isn't registered at
+    /// all (mapping never descends into a symlink), so it must fail exactly the way
+    /// it did before this option existed rather than silently falling back to it.
+    #[test]
+    fn open_through_a_symlink_fails_when_overlay_resolution_is_disabled() {
+        let dir = std::env::temp_dir().join(format!("modcrab-symlink-overlay-disabled-test-{}", std::process::id()));
+        let lower = dir.join("lower");
+        std::fs::create_dir_all(lower.join("Mod")).unwrap();
+        std::fs::create_dir_all(lower.join("Shared")).unwrap();
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+        std::fs::write(lower.join("Shared/foo.dds"), b"original texture").unwrap();
+        std::os::unix::fs::symlink("../Shared", lower.join("Mod/Link")).unwrap();
+
+        let options = ModcrabFSOptions::default().resolve_symlinks_through_overlay(false);
+        let fs = ModcrabFS::new(&dir.join("base"), std::slice::from_ref(&lower), &dir.join("overwrite"), options).unwrap();
+
+        let err = fs.open(req(), Path::new("/Mod/Link/foo.dds"), libc::O_RDONLY as u32).unwrap_err();
+        assert_eq!(err, libc::ENOENT);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A directory with `count` empty files, merged as the sole layer of a fresh
+    /// [`ModcrabFS`]. Fixture for [`readdir_prefetches_attrs_so_a_listing_s_getattr_flood_hits_the_cache`].
+    fn test_fs_with_many_files(count: usize) -> (std::path::PathBuf, ModcrabFS) {
+        let dir = std::env::temp_dir().join(format!("modcrab-attr-cache-test-{}-{count}", std::process::id()));
+        let layer = dir.join("layer");
+        std::fs::create_dir_all(&layer).unwrap();
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+        for i in 0..count {
+            std::fs::write(layer.join(format!("texture_{i:05}.dds")), b"x").unwrap();
+        }
+
+        let fs = ModcrabFS::new(&dir.join("base"), &[layer], &dir.join("overwrite"), ModcrabFSOptions::default()).unwrap();
+        (dir, fs)
+    }
+
+    /// Reproduces the scenario the attribute prefetch cache targets: a listing of a
+    /// 10k-entry directory (Skyrim's merged `data/textures/...` is the canonical
+    /// example) followed by a `getattr` per entry — here, 3 per entry, since a real
+    /// `ls -l` issues more than one stat-shaped call per file. Without the cache this
+    /// is `10_000` `lstat`s for the readdir (the repo's read-before-register pattern
+    /// doesn't apply here, since readdir itself did none) plus `30_000` for the
+    /// getattrs; with it, the `30_000` getattrs are served from the batch the readdir
+    /// already did, so total `lstat` count stays at `10_000` instead of `40_000`.
+    #[test]
+    fn readdir_prefetches_attrs_so_a_listing_s_getattr_flood_hits_the_cache() {
+        const FILE_COUNT: usize = 10_000;
+        let (dir, fs) = test_fs_with_many_files(FILE_COUNT);
+
+        let entries = fs.readdir(req(), Path::new("/"), 0).unwrap();
+        assert_eq!(entries.len(), FILE_COUNT);
+        assert_eq!(fs.metrics().lstat_calls(), FILE_COUNT as u64);
+
+        for entry in &entries {
+            let path = Path::new("/").join(&entry.name);
+            for _ in 0..3 {
+                fs.getattr(req(), &path, None).unwrap();
+            }
+        }
+
+        assert_eq!(fs.metrics().lstat_calls(), FILE_COUNT as u64, "no new lstats: every getattr should hit the prefetch cache");
+        assert_eq!(fs.metrics().attr_cache_hits(), 3 * FILE_COUNT as u64);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn callbacks_recover_from_a_tree_lock_poisoned_by_a_prior_panic() {
+        let (dir, fs) = test_fs(Vec::new());
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = fs.tree.write().unwrap();
+            panic!("simulated panic while holding the tree lock");
+        }));
+        assert!(result.is_err());
+        assert!(fs.tree.is_poisoned());
+
+        // a later callback should recover the poisoned lock rather than panicking itself
+        assert!(fs.getattr(req(), Path::new("/"), None).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unlink_is_idempotent_for_an_entry_a_prior_unlink_already_removed() {
+        let (dir, fs) = test_fs_with_many_files(1);
+        let name = fs.readdir(req(), Path::new("/"), 0).unwrap()[0].name.clone();
+
+        fs.unlink(req(), Path::new("/"), &name).unwrap();
+        fs.unlink(req(), Path::new("/"), &name).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Simulates Steam (or any other external tool) writing a new file straight into the
+    /// game root while a pack is already mounted: mapping only ran once, at mount time,
+    /// so the tree has no node for it, yet `getattr`/`open`/`readdir` should still find
+    /// it by falling back to the shadowed base itself.
+    #[test]
+    fn files_added_to_the_shadowed_base_after_mount_are_still_visible() {
+        let dir = std::env::temp_dir().join(format!("modcrab-shadow-fallback-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), ModcrabFSOptions::default()).unwrap();
+
+        std::fs::write(dir.join("base/patch_notes.txt"), b"1.2.3 patch").unwrap();
+
+        let attr = fs.getattr(req(), Path::new("/patch_notes.txt"), None).unwrap();
+        assert_eq!(attr.1.size, "1.2.3 patch".len() as u64);
+
+        let (fh, _) = fs.open(req(), Path::new("/patch_notes.txt"), libc::O_RDONLY as u32).unwrap();
+        fs.release(req(), Path::new("/patch_notes.txt"), fh, 0, 0, false).unwrap();
+
+        let entries = fs.readdir(req(), Path::new("/"), 0).unwrap();
+        assert!(entries.iter().any(|e| e.name == "patch_notes.txt"), "a file written to the base after mount must still show up in a listing");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn umask_clears_the_masked_bits_from_created_files_and_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("modcrab-umask-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let options = ModcrabFSOptions::default().umask(0o022);
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), options).unwrap();
+
+        let created = fs.create(req(), Path::new("/"), OsStr::new("save.sav"), 0o666, libc::O_RDWR as u32).unwrap();
+        fs.release(req(), Path::new("/save.sav"), created.fh, 0, 0, false).unwrap();
+        let real_file = fs.real_path(Path::new("/save.sav")).unwrap();
+        assert_eq!(std::fs::metadata(&real_file).unwrap().permissions().mode() & 0o777, 0o644);
+
+        fs.mkdir(req(), Path::new("/"), OsStr::new("Saves"), 0o777).unwrap();
+        let real_dir = fs.real_path(Path::new("/Saves")).unwrap();
+        assert_eq!(std::fs::metadata(&real_dir).unwrap().permissions().mode() & 0o777, 0o755);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Mounts a tiny tmpfs to write into, so a write that overruns it hits a real
+    /// ENOSPC from the kernel rather than us having to fake the errno. Skips rather
+    /// than fails if this sandbox won't allow mounting one (no `CAP_SYS_ADMIN`),
+    /// same as the real-FUSE-mount tests do when `/dev/fuse` isn't usable.
+    #[test]
+    fn write_past_a_full_filesystem_returns_enospc_without_corrupting_the_partial_write() {
+        let dir = std::env::temp_dir().join(format!("modcrab-enospc-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let status = std::process::Command::new("mount")
+            .args(["-t", "tmpfs", "-o", "size=16k", "tmpfs"])
+            .arg(dir.join("overwrite"))
+            .status();
+        if !matches!(status, Ok(s) if s.success()) {
+            std::fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), ModcrabFSOptions::default()).unwrap();
+        let created = fs.create(req(), Path::new("/"), OsStr::new("save.sav"), 0o666, libc::O_RDWR as u32).unwrap();
+
+        let huge = vec![b'x'; 64 * 1024];
+        let written = fs.write(req(), Path::new("/save.sav"), created.fh, 0, huge, 0).unwrap();
+        assert!((0..64 * 1024).contains(&(written as usize)), "a write landing on a full filesystem should report the partial count it actually wrote, not fail outright: {written}");
+
+        let on_disk = std::fs::metadata(dir.join("overwrite").join("save.sav")).unwrap().len();
+        assert_eq!(on_disk, written as u64, "the reported byte count must match what actually landed on disk");
+
+        fs.release(req(), Path::new("/save.sav"), created.fh, 0, 0, false).unwrap();
+        std::process::Command::new("umount").arg(dir.join("overwrite")).status().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_still_returns_enospc_when_nothing_at_all_could_be_written() {
+        let dir = std::env::temp_dir().join(format!("modcrab-enospc-zero-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("overwrite")).unwrap();
+
+        let status = std::process::Command::new("mount").args(["-t", "tmpfs", "-o", "size=16k", "tmpfs"]).arg(dir.join("overwrite")).status();
+        if !matches!(status, Ok(s) if s.success()) {
+            std::fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+
+        let fs = ModcrabFS::new(&dir.join("base"), &[], &dir.join("overwrite"), ModcrabFSOptions::default()).unwrap();
+        let created = fs.create(req(), Path::new("/"), OsStr::new("filler.sav"), 0o666, libc::O_RDWR as u32).unwrap();
+        // Fill the tmpfs up first so the very next write has no room to land any bytes at all.
+        fs.write(req(), Path::new("/filler.sav"), created.fh, 0, vec![b'x'; 64 * 1024], 0).unwrap();
+
+        let created2 = fs.create(req(), Path::new("/"), OsStr::new("save.sav"), 0o666, libc::O_RDWR as u32).unwrap();
+        let err = fs.write(req(), Path::new("/save.sav"), created2.fh, 0, vec![b'x'; 1024], 0).unwrap_err();
+        assert_eq!(err, libc::ENOSPC);
+
+        fs.release(req(), Path::new("/filler.sav"), created.fh, 0, 0, false).unwrap();
+        fs.release(req(), Path::new("/save.sav"), created2.fh, 0, 0, false).unwrap();
+        std::process::Command::new("umount").arg(dir.join("overwrite")).status().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
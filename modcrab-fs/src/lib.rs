@@ -0,0 +1,19 @@
+//! `tree`/`metrics` are buildable with `--no-default-features`, for a consumer that
+//! only wants [`VirtualFileTree`]'s mount-plan/conflict logic without linking against
+//! `fuse_mt`/`fuser` (and the libfuse headers they need at build time). The actual FUSE
+//! glue lives behind the default-on `fuse` feature; see [`filesystem`] and [`shadow`].
+#[cfg(feature = "fuse")]
+pub mod filesystem;
+pub mod metrics;
+#[cfg(feature = "fuse")]
+pub mod shadow;
+pub mod tree;
+
+#[cfg(feature = "fuse")]
+pub use filesystem::{mount, spawn_mount, ModcrabFS, ModcrabFSOptions};
+#[cfg(feature = "fuse")]
+pub use fuser::BackgroundSession;
+pub use metrics::FsMetrics;
+#[cfg(feature = "fuse")]
+pub use shadow::ShadowedDirectory;
+pub use tree::{AuditIssue, FileType, MappingProgress, VirtualFileData, VirtualFileTree};
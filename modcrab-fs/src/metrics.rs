@@ -0,0 +1,50 @@
+//! Lightweight IO counters exposed by a mounted [`crate::ModcrabFS`], surfaced by the
+//! CLI's post-run report.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Default)]
+pub struct FsMetrics {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    lstat_calls: AtomicU64,
+    attr_cache_hits: AtomicU64,
+}
+
+impl FsMetrics {
+    pub fn record_read(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_write(&self, bytes: u64) {
+        self.bytes_written.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Counts one real `lstat`/`symlink_metadata` syscall, whether it's a single
+    /// `getattr` miss or one entry of a [`crate::ModcrabFS`] readdir attribute prefetch.
+    pub fn record_lstat(&self) {
+        self.lstat_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a `getattr` served from the readdir attribute prefetch stash instead of
+    /// issuing a fresh `lstat`.
+    pub fn record_attr_cache_hit(&self) {
+        self.attr_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    pub fn lstat_calls(&self) -> u64 {
+        self.lstat_calls.load(Ordering::Relaxed)
+    }
+
+    pub fn attr_cache_hits(&self) -> u64 {
+        self.attr_cache_hits.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,39 @@
+//! The real directory that sits underneath the mountpoint. While mounted, this
+//! directory is "shadowed" by the overlay; [`ShadowedDirectory`] is how operations
+//! that must reach the real base (rather than a virtual path) get there.
+
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use nix::fcntl::{open, OFlag};
+use nix::sys::stat::Mode;
+
+/// Cheaply `Clone`-able: the underlying fd lives in an `Arc<OwnedFd>`, so every clone
+/// shares the same open directory and it's only closed once the last one is dropped.
+/// `OwnedFd` is `Send + Sync`, so this is safe to share across `ModcrabFS`'s FUSE
+/// worker threads (`num_threads > 1`).
+#[derive(Debug, Clone)]
+pub struct ShadowedDirectory {
+    handle: Arc<OwnedFd>,
+    path: PathBuf,
+}
+
+impl ShadowedDirectory {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let fd = open(path, OFlag::O_DIRECTORY | OFlag::O_RDONLY, Mode::empty())
+            .map_err(|e| std::io::Error::from_raw_os_error(e as i32))?;
+        let handle = unsafe { OwnedFd::from_raw_fd(fd) };
+        Ok(Self { handle: Arc::new(handle), path: path.to_path_buf() })
+    }
+
+    pub fn raw_fd(&self) -> RawFd {
+        self.handle.as_raw_fd()
+    }
+
+    /// The real directory this shadows, for operations that need to write straight
+    /// into it (e.g. a `shadow_passthrough` path) rather than through the overlay.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -0,0 +1,955 @@
+//! The merged view of every overlay layer: a graph of virtual paths, each pointing at
+//! the real file that currently provides it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction;
+
+/// Mirrors the node kinds `fuse_mt::FileType` distinguishes, without depending on the
+/// `fuse` feature itself — so [`VirtualFileTree`] stays buildable for analysis-only
+/// consumers (a web service inspecting mount plans, say) that never link `fuse_mt`/
+/// `fuser` at all. `filesystem.rs`, which is gated behind `fuse`, converts to and from
+/// `fuse_mt::FileType` at the FUSE boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    NamedPipe,
+    CharDevice,
+    BlockDevice,
+    Directory,
+    RegularFile,
+    Symlink,
+    Socket,
+}
+
+/// One discrepancy found by [`VirtualFileTree::audit_real_paths`] between a winning
+/// node and what's actually on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditIssue {
+    /// `real_path` no longer exists at all. Fields are `(virtual_path, real_path)`.
+    Missing(PathBuf, PathBuf),
+    /// `real_path` exists but couldn't be opened for reading. Fields are
+    /// `(virtual_path, real_path)`. Only checked for [`FileType::RegularFile`] and
+    /// [`FileType::Directory`] — opening a device node or named pipe just to test
+    /// readability risks blocking on a reader/writer that never shows up.
+    NotReadable(PathBuf, PathBuf),
+    /// `real_path` exists, but as a different kind than the node recorded when it was
+    /// mapped. Fields are `(virtual_path, recorded_kind, actual_kind)`.
+    TypeMismatch(PathBuf, FileType, FileType),
+}
+
+/// A single node in the [`VirtualFileTree`]: one virtual path, the real file that
+/// currently backs it, and which overlay layer contributed it.
+///
+/// `link_target` is `Some` for a hard link created via [`VirtualFileTree::register_link`]:
+/// the node keeps its own `virtual_path`, but `real_path`/`layer_idx`/`kind` belong to
+/// the node it links to and are only read through [`VirtualFileTree::data`], which
+/// follows the chain.
+#[derive(Debug, Clone)]
+pub struct VirtualFileData {
+    pub virtual_path: PathBuf,
+    pub real_path: PathBuf,
+    pub layer_idx: usize,
+    pub kind: FileType,
+    pub link_target: Option<NodeIndex>,
+    /// Number of outgoing tree edges (i.e. direct children). Kept up to date by
+    /// [`VirtualFileTree::update_child_count`] so [`VirtualFileTree::remove_file`] can
+    /// tell a leaf from an interior node without walking the graph.
+    child_count: usize,
+    /// Every layer that has registered a file at this node's virtual path, oldest
+    /// first, including the current (winning) layer. Lets [`VirtualFileTree::which_layers_contain`]
+    /// answer "which mod did this file's content come from, and which ones did it
+    /// shadow" for a conflict report, without needing a separate history structure.
+    layer_history: Vec<usize>,
+}
+
+/// A progress update emitted while [`VirtualFileTree::map_directory_with_progress`]
+/// walks one layer, so a caller can render a spinner or counter during a slow mount.
+#[derive(Debug, Clone, Copy)]
+pub struct MappingProgress {
+    pub layer_idx: usize,
+    pub files_mapped: usize,
+    /// How many files this layer holds in total, from a quick pre-scan done before
+    /// mapping starts — lets a caller show `files_mapped`/`total_files` instead of
+    /// just a running count with no sense of how much longer it'll take.
+    pub total_files: usize,
+}
+
+/// Merges N real directory trees (the overlay's layers, lowest priority first) into a
+/// single virtual namespace. Higher layers shadow lower ones path-for-path.
+pub struct VirtualFileTree {
+    graph: StableDiGraph<VirtualFileData, ()>,
+    index: HashMap<PathBuf, NodeIndex>,
+    root: NodeIndex,
+}
+
+impl Default for VirtualFileTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualFileTree {
+    pub fn new() -> Self {
+        let mut graph = StableDiGraph::new();
+        let root = graph.add_node(VirtualFileData {
+            virtual_path: PathBuf::from("/"),
+            real_path: PathBuf::new(),
+            layer_idx: 0,
+            kind: FileType::Directory,
+            link_target: None,
+            child_count: 0,
+            layer_history: vec![0],
+        });
+        let mut index = HashMap::new();
+        index.insert(PathBuf::from("/"), root);
+        Self { graph, index, root }
+    }
+
+    pub fn root(&self) -> NodeIndex {
+        self.root
+    }
+
+    pub fn find_index(&self, path: &Path) -> Option<NodeIndex> {
+        log::trace!("VFT lookup {}", path.display());
+        self.index.get(&normalize(path)).copied()
+    }
+
+    /// Like [`Self::find_index`], but additionally follows a symlink encountered at
+    /// any ancestor of `path` to its target before resolving the remaining
+    /// components, so a path that descends through a symlinked directory still
+    /// reaches the merged view instead of dead-ending at the symlink itself — e.g. a
+    /// mod's `Link -> ../shared` resolving `/Link/foo.dds` through to whichever layer
+    /// currently wins at `/shared/foo.dds`. `path` itself is still matched literally
+    /// first, so looking up the symlink node's own path (for `readlink`, say) is
+    /// unaffected.
+    pub fn find_index_through_symlinks(&self, path: &Path) -> Option<NodeIndex> {
+        self.resolve_through_symlinks(&normalize(path), &mut std::collections::HashSet::new())
+    }
+
+    fn resolve_through_symlinks(&self, path: &Path, seen: &mut std::collections::HashSet<PathBuf>) -> Option<NodeIndex> {
+        if let Some(&idx) = self.index.get(path) {
+            return Some(idx);
+        }
+        if path == Path::new("/") || !seen.insert(path.to_path_buf()) {
+            return None;
+        }
+
+        let name = path.file_name()?;
+        let parent = path.parent().unwrap_or(Path::new("/"));
+        let parent_idx = self.resolve_through_symlinks(parent, seen)?;
+        let parent_virtual = self.virtual_path_through_symlink(parent_idx, seen)?;
+
+        self.index.get(&parent_virtual.join(name)).copied()
+    }
+
+    /// If `idx` is itself a symlink, follows its real target (resolved relative to
+    /// the symlink's own virtual directory) all the way to a non-symlink node and
+    /// returns that node's virtual path; otherwise returns `idx`'s own virtual path
+    /// unchanged. `seen` carries the same loop guard as [`Self::resolve_through_symlinks`],
+    /// since a chain of symlinks could cycle back on itself.
+    fn virtual_path_through_symlink(&self, idx: NodeIndex, seen: &mut std::collections::HashSet<PathBuf>) -> Option<PathBuf> {
+        let data = self.graph.node_weight(idx)?;
+        if data.kind != FileType::Symlink {
+            return Some(data.virtual_path.clone());
+        }
+
+        let link_text = fs::read_link(&data.real_path).ok()?;
+        let target = collapse_dots(&data.virtual_path.parent().unwrap_or(Path::new("/")).join(&link_text));
+        let target_idx = self.resolve_through_symlinks(&target, seen)?;
+        self.virtual_path_through_symlink(target_idx, seen)
+    }
+
+    /// Attribute data for `idx`, following `link_target` chains so a hard link reports
+    /// the same `real_path`/`layer_idx`/`kind` as the node it links to.
+    pub fn data(&self, idx: NodeIndex) -> Option<&VirtualFileData> {
+        self.graph.node_weight(self.resolve(idx))
+    }
+
+    /// Follows `idx`'s `link_target` chain to the node that actually backs it.
+    fn resolve(&self, idx: NodeIndex) -> NodeIndex {
+        let mut current = idx;
+        let mut seen = std::collections::HashSet::new();
+        while seen.insert(current) {
+            match self.graph.node_weight(current).and_then(|d| d.link_target) {
+                Some(target) => current = target,
+                None => break,
+            }
+        }
+        current
+    }
+
+    pub fn real_path(&self, path: &Path) -> Option<PathBuf> {
+        self.find_index(path).and_then(|idx| self.data(idx)).map(|d| d.real_path.clone())
+    }
+
+    /// Adds or replaces the node at `virtual_path`. Replacing an existing node keeps
+    /// its position in the tree (the parent edge from the previous layer is reused).
+    pub fn register_path(&mut self, virtual_path: &Path, real_path: PathBuf, layer_idx: usize, kind: FileType) -> NodeIndex {
+        let vp = normalize(virtual_path);
+
+        if let Some(&existing) = self.index.get(&vp) {
+            if let Some(data) = self.graph.node_weight_mut(existing) {
+                data.real_path = real_path;
+                data.layer_idx = layer_idx;
+                data.kind = kind;
+                data.link_target = None;
+                if data.layer_history.last() != Some(&layer_idx) {
+                    data.layer_history.push(layer_idx);
+                }
+            }
+            return existing;
+        }
+
+        let idx = self.graph.add_node(VirtualFileData {
+            virtual_path: vp.clone(),
+            real_path,
+            layer_idx,
+            kind,
+            link_target: None,
+            child_count: 0,
+            layer_history: vec![layer_idx],
+        });
+        if let Some(parent) = vp.parent() {
+            if let Some(&parent_idx) = self.index.get(&normalize(parent)) {
+                self.graph.add_edge(parent_idx, idx, ());
+                self.update_child_count(parent_idx, 1);
+            }
+        }
+        self.index.insert(vp, idx);
+        idx
+    }
+
+    /// Registers `virtual_path` as a hard link sharing `target`'s identity: attribute
+    /// queries through [`Self::data`] resolve to `target` instead of duplicating its
+    /// `VirtualFileData`. Replacing an existing node keeps its position in the tree, the
+    /// same as [`Self::register_path`].
+    pub fn register_link(&mut self, virtual_path: &Path, target: NodeIndex) -> NodeIndex {
+        let vp = normalize(virtual_path);
+
+        if let Some(&existing) = self.index.get(&vp) {
+            if let Some(data) = self.graph.node_weight_mut(existing) {
+                data.link_target = Some(target);
+            }
+            return existing;
+        }
+
+        let idx = self.graph.add_node(VirtualFileData {
+            virtual_path: vp.clone(),
+            real_path: PathBuf::new(),
+            layer_idx: 0,
+            kind: FileType::RegularFile,
+            link_target: Some(target),
+            child_count: 0,
+            layer_history: Vec::new(),
+        });
+        if let Some(parent) = vp.parent() {
+            if let Some(&parent_idx) = self.index.get(&normalize(parent)) {
+                self.graph.add_edge(parent_idx, idx, ());
+                self.update_child_count(parent_idx, 1);
+            }
+        }
+        self.index.insert(vp, idx);
+        idx
+    }
+
+    /// Adjusts `idx`'s `child_count` by `delta` (positive when a child is linked in,
+    /// negative when one is removed).
+    fn update_child_count(&mut self, idx: NodeIndex, delta: i64) {
+        if let Some(data) = self.graph.node_weight_mut(idx) {
+            data.child_count = (data.child_count as i64 + delta).max(0) as usize;
+        }
+    }
+
+    /// Recursively maps a real directory into the tree at `attach_point`, as layer
+    /// `layer_idx`. Non-fatal on a directory that disappears mid-scan: it's skipped
+    /// with a warning rather than aborting the whole mapping.
+    pub fn map_directory(&mut self, real_root: &Path, attach_point: &Path, layer_idx: usize) -> std::io::Result<()> {
+        self.map_directory_with_progress(real_root, attach_point, layer_idx, &mut |_| {})
+    }
+
+    /// Builds a fresh tree by mapping `layers` in order (lowest priority first), each
+    /// filtered by its matching entry in `hide` the same way `ModcrabFS::new_with_progress`
+    /// maps its own layers before wiring up FUSE. Doesn't map a writable surface on top
+    /// — callers that need one (a real mount) still call [`Self::map_directory_with_progress`]
+    /// themselves afterwards. Exposed on its own so anything that only cares about the
+    /// merged overlay's shape (`doctor`, `shell`, a headless benchmark) can build one
+    /// without going through `ModcrabFS` at all.
+    pub fn from_layers(layers: &[PathBuf], hide: &[Vec<String>], on_progress: &mut dyn FnMut(MappingProgress)) -> std::io::Result<Self> {
+        let mut tree = Self::new();
+        for (idx, layer) in layers.iter().enumerate() {
+            let patterns = hide.get(idx).map(Vec::as_slice).unwrap_or(&[]);
+            let skip = Self::hide_predicate(patterns);
+            tree.map_directory_filtered(layer, Path::new("/"), idx, &skip, on_progress)?;
+        }
+        Ok(tree)
+    }
+
+    /// A rough size estimate for this tree: node count, edge count, and approximate
+    /// bytes (`node_count * size_of::<VirtualFileData>() + edge_count * size_of::<NodeIndex>()`).
+    /// Deliberately approximate — it ignores the heap bytes behind each node's
+    /// `PathBuf`s — but it's enough to compare one mount's tree against another's, or
+    /// one build of the same pack against a later one, without pulling in a real
+    /// memory profiler.
+    pub fn memory_stats(&self) -> (usize, usize, usize) {
+        let nodes = self.graph.node_count();
+        let edges = self.graph.edge_count();
+        let approx_bytes = nodes * std::mem::size_of::<VirtualFileData>() + edges * std::mem::size_of::<NodeIndex>();
+        (nodes, edges, approx_bytes)
+    }
+
+    /// Like [`Self::map_directory`], but calls `on_progress` after every file mapped so
+    /// a caller can render a spinner or counter during a slow mount. Does a quick
+    /// `fs::metadata` pass over `real_root` first to learn the total file count up
+    /// front, so every progress update can report `files_mapped`/`total_files`
+    /// instead of a running count with no sense of how much longer it'll take.
+    pub fn map_directory_with_progress(
+        &mut self,
+        real_root: &Path,
+        attach_point: &Path,
+        layer_idx: usize,
+        on_progress: &mut dyn FnMut(MappingProgress),
+    ) -> std::io::Result<()> {
+        self.map_directory_filtered(real_root, attach_point, layer_idx, &|_| false, on_progress)
+    }
+
+    /// Like [`Self::map_directory_with_progress`], but `skip` is checked against each
+    /// entry's virtual path (relative to `attach_point`, i.e. the same path
+    /// [`Self::hide_predicate`] builds a predicate for) before it's registered or
+    /// recursed into. A matching entry is left out of this layer's contribution
+    /// entirely, so whatever a lower layer already registered there, if anything,
+    /// keeps winning — for a mod's `hide` list (see `modcrab_core::structs::spec::ModSpec::hide`).
+    pub fn map_directory_filtered(
+        &mut self,
+        real_root: &Path,
+        attach_point: &Path,
+        layer_idx: usize,
+        skip: &dyn Fn(&Path) -> bool,
+        on_progress: &mut dyn FnMut(MappingProgress),
+    ) -> std::io::Result<()> {
+        let total_files = count_files(real_root);
+        let mut files_mapped = 0usize;
+        self.map_directory_inner(real_root, attach_point, layer_idx, skip, &mut files_mapped, total_files, on_progress)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn map_directory_inner(
+        &mut self,
+        real_root: &Path,
+        attach_point: &Path,
+        layer_idx: usize,
+        skip: &dyn Fn(&Path) -> bool,
+        files_mapped: &mut usize,
+        total_files: usize,
+        on_progress: &mut dyn FnMut(MappingProgress),
+    ) -> std::io::Result<()> {
+        self.register_path(attach_point, real_root.to_path_buf(), layer_idx, FileType::Directory);
+
+        let entries = match fs::read_dir(real_root) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("skipping {} during mapping: {e}", real_root.display());
+                return Ok(());
+            }
+        };
+
+        for entry in entries.flatten() {
+            let real_child = entry.path();
+            let virtual_child = attach_point.join(entry.file_name());
+            if skip(&virtual_child) {
+                continue;
+            }
+
+            let meta = match entry.metadata() {
+                Ok(meta) => meta,
+                Err(e) => {
+                    log::warn!("skipping {} during mapping: {e}", real_child.display());
+                    continue;
+                }
+            };
+
+            if meta.is_dir() {
+                self.map_directory_inner(&real_child, &virtual_child, layer_idx, skip, files_mapped, total_files, on_progress)?;
+            } else {
+                let kind = if meta.file_type().is_symlink() { FileType::Symlink } else { FileType::RegularFile };
+                self.register_path(&virtual_child, real_child, layer_idx, kind);
+                *files_mapped += 1;
+                on_progress(MappingProgress { layer_idx, files_mapped: *files_mapped, total_files });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds a `skip` predicate for [`Self::map_directory_filtered`]/
+    /// [`Self::update_directory_filtered`] from `patterns`: glob patterns matched
+    /// case-insensitively against a path relative to the layer root (the leading `/`
+    /// an attach point carries is stripped first), with `*`/`?` free to cross directory
+    /// separators so `"*.esp"` catches a plugin at any depth, not just the layer's top
+    /// level. An unparsable pattern never matches anything, the same way a bad
+    /// `--group`/`--name` glob already degrades elsewhere in this workspace.
+    pub fn hide_predicate(patterns: &[String]) -> impl Fn(&Path) -> bool + '_ {
+        let compiled: Vec<glob::Pattern> = patterns.iter().filter_map(|p| glob::Pattern::new(p).ok()).collect();
+        move |path: &Path| {
+            let relative = path.strip_prefix("/").unwrap_or(path);
+            let text = relative.to_string_lossy();
+            let options = glob::MatchOptions { case_sensitive: false, require_literal_separator: false, require_literal_leading_dot: false };
+            compiled.iter().any(|pattern| pattern.matches_with(&text, options))
+        }
+    }
+
+    /// Re-scans `real_root` for changes since it (or an earlier version of it) was last
+    /// mapped at `layer_idx`: new and changed files are picked up by [`Self::map_directory`]
+    /// the same as a fresh mount would, and anything `layer_idx` used to win at that's
+    /// since vanished from disk is removed from the tree. A path `layer_idx` had already
+    /// lost to a higher layer before this call is left alone — that layer's own copy
+    /// disappearing doesn't change what the overlay currently shows. For
+    /// `modcrab`'s SIGHUP config hot-reload: re-running this for a mod whose files
+    /// changed on disk since the mount started, without unmounting.
+    pub fn update_directory(&mut self, real_root: &Path, attach_point: &Path, layer_idx: usize) -> std::io::Result<()> {
+        self.update_directory_filtered(real_root, attach_point, layer_idx, &|_| false)
+    }
+
+    /// Like [`Self::update_directory`], but `skip` is applied the same way
+    /// [`Self::map_directory_filtered`] applies it, and a path that now matches `skip`
+    /// but was still this layer's winning copy from before the hide list changed is
+    /// cleared out just like one whose real file vanished from disk.
+    pub fn update_directory_filtered(&mut self, real_root: &Path, attach_point: &Path, layer_idx: usize, skip: &dyn Fn(&Path) -> bool) -> std::io::Result<()> {
+        let previously_winning: Vec<(PathBuf, PathBuf)> = self
+            .graph
+            .node_weights()
+            .filter(|d| d.layer_idx == layer_idx && d.link_target.is_none())
+            .map(|d| (d.virtual_path.clone(), d.real_path.clone()))
+            .collect();
+
+        self.map_directory_filtered(real_root, attach_point, layer_idx, skip, &mut |_| {})?;
+
+        for (virtual_path, real_path) in previously_winning {
+            if self.path_exists_in_layer(&virtual_path, layer_idx) && (!real_path.exists() || skip(&virtual_path)) {
+                self.remove_file(&virtual_path);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn children(&self, path: &Path) -> Vec<&VirtualFileData> {
+        let Some(idx) = self.find_index(path) else { return Vec::new() };
+        self.graph.neighbors_directed(idx, Direction::Outgoing).filter_map(|n| self.graph.node_weight(n)).collect()
+    }
+
+    /// Removes the node at `path`, along with every descendant still reachable through
+    /// it, so nothing is ever left orphaned in the graph. Returns `true` if something
+    /// was actually removed; removing an already-absent path is not an error.
+    pub fn remove_file(&mut self, path: &Path) -> bool {
+        let vp = normalize(path);
+        let Some(&idx) = self.index.get(&vp) else { return false };
+
+        if let Some(parent) = self.graph.neighbors_directed(idx, Direction::Incoming).next() {
+            self.update_child_count(parent, -1);
+        }
+        self.remove_subtree(idx);
+        true
+    }
+
+    /// Removes `idx` and, if `child_count` says it isn't a leaf, everything still
+    /// reachable from it — replacing the full-graph reachability scan `clear_orphans`
+    /// used to need with a walk scoped to just the removed subtree.
+    fn remove_subtree(&mut self, idx: NodeIndex) {
+        let Some(data) = self.graph.node_weight(idx) else { return };
+        let virtual_path = data.virtual_path.clone();
+
+        if data.child_count > 0 {
+            let children: Vec<NodeIndex> = self.graph.neighbors_directed(idx, Direction::Outgoing).collect();
+            for child in children {
+                self.remove_subtree(child);
+            }
+        }
+
+        self.index.remove(&virtual_path);
+        self.graph.remove_node(idx);
+    }
+
+    /// Number of unique files in the tree, including the root, but counting a hard
+    /// link's node only once under the node it links to.
+    pub fn file_count(&self) -> usize {
+        self.graph.node_weights().filter(|d| d.link_target.is_none()).count()
+    }
+
+    /// How many winning nodes (including directories, excluding hard links, which
+    /// don't own a `layer_idx` of their own) each layer currently contributes, sorted
+    /// by layer index. A layer with zero surviving nodes is omitted rather than
+    /// reported as `(idx, 0)`, so a caller can't tell an empty mod apart from one that
+    /// was never mounted at all without cross-referencing its own layer list.
+    pub fn node_count_by_layer(&self) -> Vec<(usize, usize)> {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for data in self.graph.node_weights().filter(|d| d.link_target.is_none()) {
+            *counts.entry(data.layer_idx).or_insert(0) += 1;
+        }
+
+        let mut counts: Vec<(usize, usize)> = counts.into_iter().collect();
+        counts.sort_by_key(|(layer_idx, _)| *layer_idx);
+        counts
+    }
+
+    /// Whether `path` currently exists and its winning node was last registered by
+    /// `layer`. Checked against the node's own `layer_idx` directly, without following
+    /// a hard link's `link_target` chain — a link never owns a `layer_idx` of its own.
+    pub fn path_exists_in_layer(&self, path: &Path, layer: usize) -> bool {
+        self.find_index(path).and_then(|idx| self.graph.node_weight(idx)).is_some_and(|d| d.layer_idx == layer)
+    }
+
+    /// Every layer that has ever registered a file at `path`, oldest first, including
+    /// the layer currently winning. A conflict report can diff consecutive entries to
+    /// say "mod A overrides file X from mod B". Empty if `path` doesn't exist. Follows
+    /// a hard link's `link_target` chain, same as [`Self::data`].
+    pub fn which_layers_contain(&self, path: &Path) -> Vec<usize> {
+        self.find_index(path)
+            .map(|idx| self.resolve(idx))
+            .and_then(|idx| self.graph.node_weight(idx))
+            .map(|d| d.layer_history.clone())
+            .unwrap_or_default()
+    }
+
+    /// Checks every winning node's `real_path` against what's actually on disk right
+    /// now: gone entirely ([`AuditIssue::Missing`]), present but unreadable
+    /// ([`AuditIssue::NotReadable`]), or present as a different kind than what was
+    /// mapped ([`AuditIssue::TypeMismatch`]) — a mod file deleted, permission-locked, or
+    /// replaced since the overlay was last built, none of which a mount notices on its
+    /// own between scans. Hard links are skipped, since their `real_path` belongs to
+    /// the node they point at, which this same scan already covers independently. Every
+    /// issue found is also logged at `warn!`, so a caller that only wants the side
+    /// effect can ignore the return value.
+    pub fn audit_real_paths(&self) -> Vec<AuditIssue> {
+        let mut issues = Vec::new();
+        for data in self.graph.node_weights().filter(|d| d.link_target.is_none()) {
+            let metadata = match fs::symlink_metadata(&data.real_path) {
+                Ok(metadata) => metadata,
+                Err(_) => {
+                    issues.push(AuditIssue::Missing(data.virtual_path.clone(), data.real_path.clone()));
+                    continue;
+                }
+            };
+
+            let actual_kind = actual_file_type(&metadata);
+            if actual_kind != data.kind {
+                issues.push(AuditIssue::TypeMismatch(data.virtual_path.clone(), data.kind, actual_kind));
+                continue;
+            }
+
+            if matches!(data.kind, FileType::RegularFile | FileType::Directory) && fs::File::open(&data.real_path).is_err() {
+                issues.push(AuditIssue::NotReadable(data.virtual_path.clone(), data.real_path.clone()));
+            }
+        }
+
+        for issue in &issues {
+            match issue {
+                AuditIssue::Missing(virtual_path, real_path) => {
+                    log::warn!("audit: {} ({}) no longer exists on disk", virtual_path.display(), real_path.display());
+                }
+                AuditIssue::NotReadable(virtual_path, real_path) => {
+                    log::warn!("audit: {} ({}) exists but can't be opened for reading", virtual_path.display(), real_path.display());
+                }
+                AuditIssue::TypeMismatch(virtual_path, recorded, actual) => {
+                    log::warn!("audit: {} changed type on disk: recorded as {recorded:?}, now {actual:?}", virtual_path.display());
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// Counts every non-directory entry under `real_root`, recursively, for
+/// [`VirtualFileTree::map_directory_with_progress`]'s up-front total. Mirrors
+/// [`VirtualFileTree::map_directory_inner`]'s own walk (same skip-with-a-warning
+/// treatment of an unreadable subtree) so the two passes never disagree on what
+/// counts as a file.
+fn count_files(real_root: &Path) -> usize {
+    let mut count = 0;
+    let entries = match fs::read_dir(real_root) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("skipping {} while counting files to map: {e}", real_root.display());
+            return count;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            count += count_files(&entry.path());
+        } else {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// Maps a real [`fs::Metadata`]'s file type onto the [`FileType`] `audit_real_paths`
+/// compares against a node's recorded `kind`, mirroring how `filesystem.rs` classifies
+/// the same metadata at the FUSE boundary but without that module's `fuse`-gated
+/// dependency on `nix`/`libc`.
+fn actual_file_type(metadata: &fs::Metadata) -> FileType {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        FileType::Directory
+    } else if file_type.is_symlink() {
+        FileType::Symlink
+    } else if file_type.is_fifo() {
+        FileType::NamedPipe
+    } else if file_type.is_char_device() {
+        FileType::CharDevice
+    } else if file_type.is_block_device() {
+        FileType::BlockDevice
+    } else if file_type.is_socket() {
+        FileType::Socket
+    } else {
+        FileType::RegularFile
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    if path.as_os_str().is_empty() {
+        return PathBuf::from("/");
+    }
+    Path::new("/").join(path.strip_prefix("/").unwrap_or(path))
+}
+
+/// Collapses `.`/`..` components in `path` without touching the real filesystem, the
+/// way [`std::fs::canonicalize`] would if `path` actually existed there. Used to turn
+/// a relative symlink target joined onto its containing directory (which can walk
+/// back above the mount root the way `../../texture.dds` does) into the absolute
+/// virtual path it actually refers to.
+fn collapse_dots(path: &Path) -> PathBuf {
+    let mut out = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(seg) => out.push(seg),
+            std::path::Component::ParentDir if out != Path::new("/") => {
+                out.pop();
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_and_find() {
+        let mut tree = VirtualFileTree::new();
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/real/foo.txt"), 0, FileType::RegularFile);
+        assert_eq!(tree.real_path(Path::new("/foo.txt")), Some(PathBuf::from("/real/foo.txt")));
+    }
+
+    #[test]
+    fn higher_layer_shadows_lower_layer() {
+        let mut tree = VirtualFileTree::new();
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/low/foo.txt"), 0, FileType::RegularFile);
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/high/foo.txt"), 1, FileType::RegularFile);
+        assert_eq!(tree.real_path(Path::new("/foo.txt")), Some(PathBuf::from("/high/foo.txt")));
+    }
+
+    #[test]
+    fn register_link_shares_the_target_s_attributes_and_is_excluded_from_file_count() {
+        let mut tree = VirtualFileTree::new();
+        let target = tree.register_path(Path::new("/foo.txt"), PathBuf::from("/real/foo.txt"), 0, FileType::RegularFile);
+        let before = tree.file_count();
+
+        let link = tree.register_link(Path::new("/bar.txt"), target);
+
+        assert_eq!(tree.real_path(Path::new("/bar.txt")), Some(PathBuf::from("/real/foo.txt")));
+        assert_eq!(tree.data(link).unwrap().kind, FileType::RegularFile);
+        assert_eq!(tree.file_count(), before, "a hard link shares its target's identity, so it shouldn't inflate the unique file count");
+
+        // replacing the backing file updates what the link resolves to as well
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/real/foo-renamed.txt"), 1, FileType::RegularFile);
+        assert_eq!(tree.real_path(Path::new("/bar.txt")), Some(PathBuf::from("/real/foo-renamed.txt")));
+    }
+
+    #[test]
+    fn remove_file_is_idempotent_for_missing_paths() {
+        let mut tree = VirtualFileTree::new();
+        assert!(!tree.remove_file(Path::new("/nope.txt")));
+    }
+
+    #[test]
+    fn remove_file_recursively_drops_descendants_without_orphaning_them() {
+        let mut tree = VirtualFileTree::new();
+        tree.register_path(Path::new("/dir"), PathBuf::from("/real/dir"), 0, FileType::Directory);
+        tree.register_path(Path::new("/dir/a.txt"), PathBuf::from("/real/dir/a.txt"), 0, FileType::RegularFile);
+        tree.register_path(Path::new("/dir/sub"), PathBuf::from("/real/dir/sub"), 0, FileType::Directory);
+        tree.register_path(Path::new("/dir/sub/b.txt"), PathBuf::from("/real/dir/sub/b.txt"), 0, FileType::RegularFile);
+
+        assert!(tree.remove_file(Path::new("/dir")));
+
+        assert!(tree.find_index(Path::new("/dir")).is_none());
+        assert!(tree.find_index(Path::new("/dir/a.txt")).is_none());
+        assert!(tree.find_index(Path::new("/dir/sub")).is_none());
+        assert!(tree.find_index(Path::new("/dir/sub/b.txt")).is_none());
+        assert_eq!(tree.file_count(), 1, "only the root should be left");
+    }
+
+    #[test]
+    fn remove_file_on_a_leaf_leaves_its_siblings_intact() {
+        let mut tree = VirtualFileTree::new();
+        tree.register_path(Path::new("/a.txt"), PathBuf::from("/real/a.txt"), 0, FileType::RegularFile);
+        tree.register_path(Path::new("/b.txt"), PathBuf::from("/real/b.txt"), 0, FileType::RegularFile);
+
+        assert!(tree.remove_file(Path::new("/a.txt")));
+
+        assert!(tree.find_index(Path::new("/a.txt")).is_none());
+        assert_eq!(tree.real_path(Path::new("/b.txt")), Some(PathBuf::from("/real/b.txt")));
+    }
+
+    #[test]
+    fn path_exists_in_layer_matches_only_the_currently_winning_layer() {
+        let mut tree = VirtualFileTree::new();
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/low/foo.txt"), 0, FileType::RegularFile);
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/high/foo.txt"), 1, FileType::RegularFile);
+
+        assert!(!tree.path_exists_in_layer(Path::new("/foo.txt"), 0));
+        assert!(tree.path_exists_in_layer(Path::new("/foo.txt"), 1));
+        assert!(!tree.path_exists_in_layer(Path::new("/nope.txt"), 1));
+    }
+
+    #[test]
+    fn which_layers_contain_records_every_layer_that_ever_registered_the_path() {
+        let mut tree = VirtualFileTree::new();
+        assert!(tree.which_layers_contain(Path::new("/foo.txt")).is_empty());
+
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/low/foo.txt"), 0, FileType::RegularFile);
+        assert_eq!(tree.which_layers_contain(Path::new("/foo.txt")), vec![0]);
+
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/mid/foo.txt"), 1, FileType::RegularFile);
+        tree.register_path(Path::new("/foo.txt"), PathBuf::from("/high/foo.txt"), 2, FileType::RegularFile);
+        assert_eq!(tree.which_layers_contain(Path::new("/foo.txt")), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn node_count_by_layer_counts_winning_nodes_and_skips_empty_layers() {
+        let mut tree = VirtualFileTree::new();
+        tree.register_path(Path::new("/a.txt"), PathBuf::from("/low/a.txt"), 0, FileType::RegularFile);
+        tree.register_path(Path::new("/b.txt"), PathBuf::from("/low/b.txt"), 0, FileType::RegularFile);
+        tree.register_path(Path::new("/b.txt"), PathBuf::from("/high/b.txt"), 2, FileType::RegularFile);
+        tree.register_path(Path::new("/c.txt"), PathBuf::from("/high/c.txt"), 2, FileType::RegularFile);
+
+        // The root itself is a node at layer 0 from `new()`, so layer 0 also counts it
+        // alongside `a.txt`. Layer 1 never registered anything, and `b.txt`'s layer-0
+        // registration was superseded by layer 2's, so only layers 0 and 2 show up.
+        assert_eq!(tree.node_count_by_layer(), vec![(0, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn node_count_by_layer_counts_a_hard_link_s_node_only_under_its_target() {
+        let mut tree = VirtualFileTree::new();
+        let target = tree.register_path(Path::new("/foo.txt"), PathBuf::from("/real/foo.txt"), 0, FileType::RegularFile);
+        tree.register_link(Path::new("/bar.txt"), target);
+
+        // The root plus `foo.txt`; `bar.txt` is a hard link and doesn't own a layer_idx.
+        assert_eq!(tree.node_count_by_layer(), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn which_layers_contain_follows_a_hard_link_to_its_target_s_history() {
+        let mut tree = VirtualFileTree::new();
+        let target = tree.register_path(Path::new("/foo.txt"), PathBuf::from("/real/foo.txt"), 0, FileType::RegularFile);
+        tree.register_link(Path::new("/bar.txt"), target);
+
+        assert_eq!(tree.which_layers_contain(Path::new("/bar.txt")), vec![0]);
+    }
+
+    #[test]
+    fn map_directory_with_progress_reports_every_file_mapped() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-progress-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), b"a").unwrap();
+        fs::write(dir.join("sub/b.txt"), b"b").unwrap();
+
+        let mut seen = Vec::new();
+        let mut totals = Vec::new();
+        let mut tree = VirtualFileTree::new();
+        tree.map_directory_with_progress(&dir, Path::new("/"), 0, &mut |progress| {
+            seen.push(progress.files_mapped);
+            totals.push(progress.total_files);
+        })
+        .unwrap();
+
+        assert_eq!(seen, vec![1, 2]);
+        assert_eq!(totals, vec![2, 2], "the pre-scan total must stay fixed across every update for the same layer");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn from_layers_maps_every_layer_and_lets_a_higher_one_win() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-from-layers-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("base")).unwrap();
+        fs::write(dir.join("base/shared.esp"), b"base").unwrap();
+        fs::create_dir_all(dir.join("patch")).unwrap();
+        fs::write(dir.join("patch/shared.esp"), b"patch").unwrap();
+
+        let layers = vec![dir.join("base"), dir.join("patch")];
+        let tree = VirtualFileTree::from_layers(&layers, &[], &mut |_| {}).unwrap();
+
+        assert_eq!(tree.which_layers_contain(Path::new("/shared.esp")), vec![0, 1]);
+        assert!(tree.path_exists_in_layer(Path::new("/shared.esp"), 1));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn memory_stats_counts_every_node_including_directories() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-memory-stats-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/a.txt"), b"a").unwrap();
+
+        let mut tree = VirtualFileTree::new();
+        tree.map_directory(&dir, Path::new("/"), 0).unwrap();
+
+        let (nodes, edges, approx_bytes) = tree.memory_stats();
+        assert_eq!(nodes, 3, "root + sub + a.txt");
+        assert_eq!(edges, 2);
+        assert!(approx_bytes > 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn update_directory_picks_up_new_files_and_drops_ones_deleted_since_the_last_scan() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-update-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("stays.txt"), b"a").unwrap();
+        fs::write(dir.join("removed.txt"), b"b").unwrap();
+
+        let mut tree = VirtualFileTree::new();
+        tree.map_directory(&dir, Path::new("/"), 0).unwrap();
+        assert!(tree.find_index(Path::new("/removed.txt")).is_some());
+
+        fs::remove_file(dir.join("removed.txt")).unwrap();
+        fs::write(dir.join("added.txt"), b"c").unwrap();
+        tree.update_directory(&dir, Path::new("/"), 0).unwrap();
+
+        assert!(tree.find_index(Path::new("/stays.txt")).is_some());
+        assert!(tree.find_index(Path::new("/added.txt")).is_some());
+        assert!(tree.find_index(Path::new("/removed.txt")).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn update_directory_leaves_a_higher_layer_s_winning_copy_alone_when_the_lower_one_s_file_disappears() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-update-shadowed-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("low")).unwrap();
+        fs::create_dir_all(dir.join("high")).unwrap();
+        fs::write(dir.join("low/shared.esp"), b"low").unwrap();
+        fs::write(dir.join("high/shared.esp"), b"high").unwrap();
+
+        let mut tree = VirtualFileTree::new();
+        tree.map_directory(&dir.join("low"), Path::new("/"), 0).unwrap();
+        tree.map_directory(&dir.join("high"), Path::new("/"), 1).unwrap();
+
+        fs::remove_file(dir.join("low/shared.esp")).unwrap();
+        tree.update_directory(&dir.join("low"), Path::new("/"), 0).unwrap();
+
+        assert_eq!(tree.real_path(Path::new("/shared.esp")), Some(dir.join("high/shared.esp")), "the winning high-layer copy must survive the low layer's update");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn map_directory_is_non_fatal_when_real_root_does_not_exist() {
+        let missing = std::env::temp_dir().join(format!("modcrabfs-tree-missing-root-test-{}", std::process::id()));
+        let mut tree = VirtualFileTree::new();
+
+        let result = tree.map_directory(&missing, Path::new("/"), 0);
+
+        assert!(result.is_ok(), "a vanished directory must be skipped with a warning, not abort the whole mapping");
+        assert!(tree.children(Path::new("/")).is_empty());
+    }
+
+    #[test]
+    fn map_directory_skips_a_nested_subdirectory_that_vanishes_before_it_can_be_recursed_into() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-vanish-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("trigger")).unwrap();
+        fs::create_dir_all(dir.join("trigger/victim")).unwrap();
+        fs::write(dir.join("trigger/keep.txt"), b"1").unwrap();
+
+        // Deletes `victim` (left empty, so recursing into it first would map nothing
+        // and never call back) as soon as `keep.txt` is mapped, landing the deletion
+        // inside the scan of `trigger` regardless of which of its two children readdir
+        // happens to visit first. Reproduces a mod directory vanishing out from under a
+        // long-running mount build without needing a second thread to race against.
+        let victim = dir.join("trigger/victim");
+        let mut tree = VirtualFileTree::new();
+        let result = tree.map_directory_with_progress(&dir, Path::new("/"), 0, &mut |progress| {
+            if progress.files_mapped == 1 {
+                fs::remove_dir_all(&victim).unwrap();
+            }
+        });
+
+        assert!(result.is_ok(), "a subdirectory vanishing mid-scan must not abort the whole mapping");
+        assert!(tree.find_index(Path::new("/trigger/keep.txt")).is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn map_directory_filtered_skips_matching_entries_and_lets_a_lower_layer_s_copy_win() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-filtered-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("low")).unwrap();
+        fs::create_dir_all(dir.join("high")).unwrap();
+        fs::write(dir.join("low/shared.esp"), b"low").unwrap();
+        fs::write(dir.join("high/shared.esp"), b"high").unwrap();
+        fs::write(dir.join("high/unique.esp"), b"new").unwrap();
+
+        let patterns = ["*.esp".to_owned()];
+        let skip = VirtualFileTree::hide_predicate(&patterns);
+        let mut tree = VirtualFileTree::new();
+        tree.map_directory(&dir.join("low"), Path::new("/"), 0).unwrap();
+        tree.map_directory_filtered(&dir.join("high"), Path::new("/"), 1, &skip, &mut |_| {}).unwrap();
+
+        assert_eq!(tree.real_path(Path::new("/shared.esp")), Some(dir.join("low/shared.esp")), "the hidden high-layer copy must not shadow the lower layer's");
+        assert!(tree.find_index(Path::new("/unique.esp")).is_none(), "a hidden file with nothing underneath it shouldn't appear at all");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hide_predicate_matches_case_insensitively_and_ignores_an_unparsable_pattern() {
+        let patterns = ["*.ESP".to_owned(), "[".to_owned()];
+        let skip = VirtualFileTree::hide_predicate(&patterns);
+        assert!(skip(Path::new("/plugin.esp")));
+        assert!(!skip(Path::new("/texture.dds")));
+    }
+
+    #[test]
+    fn audit_real_paths_reports_missing_and_type_mismatched_files_but_leaves_untouched_ones_alone() {
+        let dir = std::env::temp_dir().join(format!("modcrabfs-tree-audit-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("stays.txt"), b"a").unwrap();
+        fs::write(dir.join("removed.txt"), b"b").unwrap();
+        fs::write(dir.join("replaced.txt"), b"c").unwrap();
+
+        let mut tree = VirtualFileTree::new();
+        tree.map_directory(&dir, Path::new("/"), 0).unwrap();
+
+        fs::remove_file(dir.join("removed.txt")).unwrap();
+        fs::remove_file(dir.join("replaced.txt")).unwrap();
+        fs::create_dir_all(dir.join("replaced.txt")).unwrap();
+
+        let issues = tree.audit_real_paths();
+        assert_eq!(issues.len(), 2);
+        assert!(issues.contains(&AuditIssue::Missing(PathBuf::from("/removed.txt"), dir.join("removed.txt"))));
+        assert!(issues.contains(&AuditIssue::TypeMismatch(PathBuf::from("/replaced.txt"), FileType::RegularFile, FileType::Directory)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
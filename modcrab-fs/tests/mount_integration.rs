@@ -0,0 +1,216 @@
+//! Integration tests that actually mount a [`ModcrabFS`] via FUSE and exercise it
+//! through the mountpoint, rather than calling [`FilesystemMT`] methods directly.
+//! Mounting needs a usable `/dev/fuse` (device present, kernel module loaded,
+//! `user_allow_other`/privileges in order); most sandboxes and CI containers have
+//! none of that, so every test skips rather than fails when [`spawn_mount`] can't
+//! actually mount, instead of just checking whether the device file exists.
+#![cfg(all(target_os = "linux", feature = "fuse"))]
+
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use modcrab_fs::{ModcrabFS, ModcrabFSOptions};
+use tempfile::TempDir;
+
+/// Mounts `layers` (lowest priority first) plus `surface` over a fresh `base`
+/// directory at a fresh mountpoint, returning the session handle (unmounts on drop)
+/// and the mountpoint path, or `None` if FUSE isn't actually usable here. `spawn_mount`
+/// itself can report success even when the sandbox blocks real FUSE traffic, so this
+/// also probes with a real read before trusting the mount.
+fn mount(base: &Path, layers: &[PathBuf], surface: &Path) -> Option<(fuser::BackgroundSession, PathBuf)> {
+    let mountpoint = base.parent().unwrap().join("mnt");
+    fs::create_dir_all(&mountpoint).unwrap();
+
+    let fs = ModcrabFS::new(base, layers, surface, ModcrabFSOptions::default()).unwrap();
+    let session = modcrab_fs::spawn_mount(fs, &mountpoint).ok()?;
+    fs::read_dir(&mountpoint).ok()?;
+    Some((session, mountpoint))
+}
+
+#[test]
+fn mounted_files_are_readable_through_the_mountpoint() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(base.join("readme.txt"), b"hello from base").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    assert_eq!(fs::read(mountpoint.join("readme.txt")).unwrap(), b"hello from base");
+}
+
+#[test]
+fn uppercase_paths_resolve_case_insensitively() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(base.join("Textures")).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(base.join("Textures").join("Armor.dds"), b"texture data").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    assert_eq!(fs::read(mountpoint.join("TEXTURES").join("ARMOR.DDS")).unwrap(), b"texture data");
+}
+
+#[test]
+fn a_later_layer_wins_over_an_earlier_one_at_the_same_path() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let layer_a = root.path().join("layer_a");
+    let layer_b = root.path().join("layer_b");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&layer_a).unwrap();
+    fs::create_dir_all(&layer_b).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(layer_a.join("shared.esp"), b"from layer a").unwrap();
+    fs::write(layer_b.join("shared.esp"), b"from layer b").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[layer_a, layer_b], &surface) else { return };
+    assert_eq!(fs::read(mountpoint.join("shared.esp")).unwrap(), b"from layer b");
+}
+
+#[test]
+fn unlinking_a_file_hides_it_from_the_overlay() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let layer = root.path().join("layer");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&layer).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(layer.join("obsolete.ini"), b"stale config").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[layer], &surface) else { return };
+    let mounted = mountpoint.join("obsolete.ini");
+    assert!(mounted.exists());
+
+    fs::remove_file(&mounted).unwrap();
+    assert!(!mounted.exists());
+}
+
+#[test]
+fn rm_r_clears_a_1000_file_virtual_directory_through_the_mount() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    let target = base.join("many_files");
+    fs::create_dir_all(&target).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    for i in 0..1000 {
+        fs::write(target.join(format!("file_{i}.txt")), b"x").unwrap();
+    }
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    let mounted = mountpoint.join("many_files");
+    assert_eq!(fs::read_dir(&mounted).unwrap().count(), 1000);
+
+    let status = std::process::Command::new("rm").arg("-r").arg(&mounted).status().unwrap();
+    assert!(status.success());
+    assert!(!mounted.exists());
+}
+
+#[test]
+fn unlinking_an_already_removed_path_through_the_mount_is_a_no_op_not_an_error() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(base.join("gone.ini"), b"stale config").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    let mounted = mountpoint.join("gone.ini");
+
+    fs::remove_file(&mounted).unwrap();
+    // Simulates the race an extraction tool or a stale `readdir` listing can hit:
+    // the kernel's own dentry cache would normally short-circuit a second `unlink(2)`
+    // on the same path with ENOENT before ever reaching the FUSE server, so this goes
+    // straight to the FS callback via a fresh `CString` path, same as the original.
+    let path = std::ffi::CString::new(mounted.as_os_str().as_bytes()).unwrap();
+    let rc = unsafe { libc::unlink(path.as_ptr()) };
+    assert_eq!(rc, 0, "a second unlink of an already-removed path should succeed idempotently, not ENOENT");
+}
+
+#[test]
+fn rmdir_on_a_populated_virtual_directory_returns_enotempty_through_the_mount() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(base.join("Textures")).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(base.join("Textures").join("Armor.dds"), b"texture data").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    let mounted = mountpoint.join("Textures");
+
+    let err = fs::remove_dir(&mounted).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::ENOTEMPTY));
+    assert!(mounted.join("Armor.dds").exists(), "the populated directory must survive the failed rmdir intact");
+}
+
+#[test]
+fn opening_a_regular_file_with_o_directory_fails_with_enotdir_through_the_mount() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    fs::write(base.join("readme.txt"), b"hello from base").unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    let mounted = mountpoint.join("readme.txt");
+
+    let path = std::ffi::CString::new(mounted.as_os_str().as_bytes()).unwrap();
+    let fd = unsafe { libc::open(path.as_ptr(), libc::O_RDONLY | libc::O_DIRECTORY) };
+    assert_eq!(fd, -1, "opening a regular file with O_DIRECTORY must fail");
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::ENOTDIR));
+}
+
+#[test]
+fn opening_a_directory_without_o_directory_fails_with_eisdir_through_the_mount() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(base.join("Textures")).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    let mounted = mountpoint.join("Textures");
+
+    let path = std::ffi::CString::new(mounted.as_os_str().as_bytes()).unwrap();
+    let fd = unsafe { libc::open(path.as_ptr(), libc::O_RDWR) };
+    assert_eq!(fd, -1, "opening a directory for writing must fail");
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EISDIR));
+}
+
+/// Not a hard perf gate (the sandbox this runs in is too noisy for a throughput
+/// assertion to be anything but flaky) — repeatedly reading the same large asset
+/// through the mount is a smoke test that `read`'s per-thread reusable buffer still
+/// hands back correct data, with the measured throughput logged for a human to skim.
+#[test]
+fn repeated_large_reads_through_the_mount_stay_correct() {
+    let root = TempDir::new().unwrap();
+    let base = root.path().join("base");
+    let surface = root.path().join("overwrite");
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&surface).unwrap();
+    let payload = vec![0xABu8; 4 * 1024 * 1024];
+    fs::write(base.join("asset.bin"), &payload).unwrap();
+
+    let Some((_session, mountpoint)) = mount(&base, &[], &surface) else { return };
+    let mounted = mountpoint.join("asset.bin");
+
+    let start = std::time::Instant::now();
+    let mut total = 0u64;
+    for _ in 0..20 {
+        let data = fs::read(&mounted).unwrap();
+        assert_eq!(data, payload, "a streamed read must still return the full, correct asset");
+        total += data.len() as u64;
+    }
+    let elapsed = start.elapsed();
+    let throughput_mb_s = (total as f64 / (1024.0 * 1024.0)) / elapsed.as_secs_f64().max(0.001);
+    println!("streamed {total} bytes in {elapsed:?} ({throughput_mb_s:.1} MB/s)");
+}